@@ -0,0 +1,25 @@
+-- Existing installs created these tables by hand, hence IF NOT EXISTS.
+CREATE TABLE IF NOT EXISTS users (
+    id SERIAL PRIMARY KEY,
+    username VARCHAR NOT NULL UNIQUE,
+    password VARCHAR NOT NULL,
+    secret_pass VARCHAR NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS expense_tags (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS transactions (
+    tran_id SERIAL PRIMARY KEY,
+    tran_type VARCHAR NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tran_source VARCHAR NOT NULL,
+    date TIMESTAMP NOT NULL,
+    tran_amount DOUBLE PRECISION NOT NULL,
+    tran_comment VARCHAR,
+    tag_id INTEGER REFERENCES expense_tags(id)
+);
+
+CREATE INDEX IF NOT EXISTS transactions_user_id_date_idx ON transactions (user_id, date);
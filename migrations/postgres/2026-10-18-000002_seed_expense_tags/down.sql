@@ -0,0 +1,3 @@
+DELETE FROM expense_tags
+WHERE name IN ('Food', 'Transport', 'Housing', 'Utilities', 'Health', 'Entertainment', 'Shopping', 'Other')
+  AND id NOT IN (SELECT tag_id FROM transactions WHERE tag_id IS NOT NULL);
@@ -0,0 +1,13 @@
+INSERT INTO expense_tags (name)
+SELECT v.name
+FROM (VALUES
+    ('Food'),
+    ('Transport'),
+    ('Housing'),
+    ('Utilities'),
+    ('Health'),
+    ('Entertainment'),
+    ('Shopping'),
+    ('Other')
+) AS v(name)
+WHERE NOT EXISTS (SELECT 1 FROM expense_tags);
@@ -0,0 +1,2 @@
+ALTER TABLE transactions
+    ALTER COLUMN tran_amount TYPE DOUBLE PRECISION USING tran_amount / 100.0;
@@ -0,0 +1,3 @@
+-- Amounts are stored as integer cents from now on.
+ALTER TABLE transactions
+    ALTER COLUMN tran_amount TYPE BIGINT USING ROUND(tran_amount * 100)::BIGINT;
@@ -0,0 +1,3 @@
+DROP TABLE exchange_rates;
+ALTER TABLE transactions DROP COLUMN currency;
+ALTER TABLE users DROP COLUMN base_currency;
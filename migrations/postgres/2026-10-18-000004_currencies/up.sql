@@ -0,0 +1,11 @@
+ALTER TABLE users ADD COLUMN base_currency VARCHAR(3) NOT NULL DEFAULT 'EUR';
+ALTER TABLE transactions ADD COLUMN currency VARCHAR(3) NOT NULL DEFAULT 'EUR';
+
+CREATE TABLE exchange_rates (
+    id SERIAL PRIMARY KEY,
+    rate_date DATE NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    quote_currency VARCHAR(3) NOT NULL,
+    rate_micros BIGINT NOT NULL CHECK (rate_micros > 0),
+    UNIQUE (rate_date, currency, quote_currency)
+);
@@ -0,0 +1,5 @@
+-- Transfers have no meaning without accounts.
+DELETE FROM transactions WHERE tran_type = 'Transfer';
+ALTER TABLE transactions DROP COLUMN transfer_account_id;
+ALTER TABLE transactions DROP COLUMN account_id;
+DROP TABLE accounts;
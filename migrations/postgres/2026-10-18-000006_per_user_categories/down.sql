@@ -0,0 +1,17 @@
+-- Point transactions back at the shared template with the same name.
+UPDATE transactions
+SET tag_id = (
+    SELECT g.id
+    FROM expense_tags g
+    JOIN expense_tags c ON c.name = g.name
+    WHERE c.id = transactions.tag_id
+      AND g.user_id IS NULL
+    ORDER BY g.id
+    LIMIT 1
+)
+WHERE tag_id IS NOT NULL;
+
+DELETE FROM expense_tags WHERE user_id IS NOT NULL;
+DROP INDEX expense_tags_user_id_idx;
+ALTER TABLE expense_tags DROP COLUMN color;
+ALTER TABLE expense_tags DROP COLUMN user_id;
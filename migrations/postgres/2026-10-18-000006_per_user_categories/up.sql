@@ -0,0 +1,35 @@
+ALTER TABLE expense_tags ADD COLUMN user_id INTEGER REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE expense_tags ADD COLUMN color VARCHAR(7) NOT NULL DEFAULT '#888888';
+
+UPDATE expense_tags SET color = CASE (id - 1) % 8
+    WHEN 0 THEN '#cc1a66'
+    WHEN 1 THEN '#1acc66'
+    WHEN 2 THEN '#6666ff'
+    WHEN 3 THEN '#ff9933'
+    WHEN 4 THEN '#9933ff'
+    WHEN 5 THEN '#33ffcc'
+    WHEN 6 THEN '#cccc33'
+    ELSE '#4d4d4d'
+END;
+
+-- The existing global tags become templates; every user gets a private copy
+-- and their transactions are pointed at it.
+INSERT INTO expense_tags (name, color, user_id)
+SELECT t.name, t.color, u.id
+FROM expense_tags t CROSS JOIN users u
+WHERE t.user_id IS NULL
+ORDER BY u.id, t.id;
+
+UPDATE transactions
+SET tag_id = (
+    SELECT c.id
+    FROM expense_tags c
+    JOIN expense_tags g ON g.name = c.name
+    WHERE g.id = transactions.tag_id
+      AND c.user_id = transactions.user_id
+    ORDER BY c.id
+    LIMIT 1
+)
+WHERE tag_id IS NOT NULL;
+
+CREATE INDEX expense_tags_user_id_idx ON expense_tags (user_id);
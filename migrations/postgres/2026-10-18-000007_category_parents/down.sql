@@ -0,0 +1 @@
+ALTER TABLE expense_tags DROP COLUMN parent_id;
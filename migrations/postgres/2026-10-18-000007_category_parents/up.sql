@@ -0,0 +1 @@
+ALTER TABLE expense_tags ADD COLUMN parent_id INTEGER REFERENCES expense_tags(id) ON DELETE SET NULL;
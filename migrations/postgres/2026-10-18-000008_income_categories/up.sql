@@ -0,0 +1,17 @@
+ALTER TABLE expense_tags ADD COLUMN kind VARCHAR(10) NOT NULL DEFAULT 'expense';
+
+INSERT INTO expense_tags (name, color, kind) VALUES
+    ('Salary', '#1acc66', 'income'),
+    ('Freelance', '#6666ff', 'income'),
+    ('Refunds', '#ff9933', 'income'),
+    ('Other income', '#4d4d4d', 'income');
+
+-- Users who already have their own categories get the income defaults as well;
+-- everyone else receives them together with the expense defaults on next login.
+INSERT INTO expense_tags (name, color, kind, user_id)
+SELECT t.name, t.color, t.kind, u.id
+FROM expense_tags t CROSS JOIN users u
+WHERE t.user_id IS NULL
+  AND t.kind = 'income'
+  AND EXISTS (SELECT 1 FROM expense_tags x WHERE x.user_id = u.id)
+ORDER BY u.id, t.id;
@@ -0,0 +1,22 @@
+CREATE TABLE audit_log (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    action VARCHAR NOT NULL CHECK (action IN ('insert', 'update', 'delete')),
+    entity VARCHAR NOT NULL,
+    entity_id INTEGER,
+    old_value TEXT,
+    new_value TEXT
+);
+
+CREATE INDEX audit_log_user_time ON audit_log (user_id, created_at);
+
+-- The log is append-only: rows can be added but never changed or removed.
+CREATE FUNCTION audit_log_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_no_update BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION audit_log_append_only();
@@ -0,0 +1,12 @@
+CREATE TABLE attachments (
+    id SERIAL PRIMARY KEY,
+    transaction_id INTEGER NOT NULL REFERENCES transactions(tran_id) ON DELETE CASCADE,
+    file_name VARCHAR NOT NULL,
+    content_hash VARCHAR(64) NOT NULL,
+    mime_type VARCHAR NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX attachments_transaction ON attachments (transaction_id);
+CREATE INDEX attachments_hash ON attachments (content_hash);
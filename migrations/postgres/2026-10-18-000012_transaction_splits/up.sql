@@ -0,0 +1,8 @@
+CREATE TABLE transaction_splits (
+    id SERIAL PRIMARY KEY,
+    transaction_id INTEGER NOT NULL REFERENCES transactions(tran_id) ON DELETE CASCADE,
+    tag_id INTEGER REFERENCES expense_tags(id) ON DELETE SET NULL,
+    amount BIGINT NOT NULL CHECK (amount > 0)
+);
+
+CREATE INDEX transaction_splits_transaction ON transaction_splits (transaction_id);
@@ -0,0 +1,16 @@
+CREATE TABLE recurring_rules (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tran_type VARCHAR NOT NULL,
+    source VARCHAR NOT NULL,
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    currency VARCHAR(3) NOT NULL,
+    tag_id INTEGER REFERENCES expense_tags(id) ON DELETE SET NULL,
+    account_id INTEGER REFERENCES accounts(id) ON DELETE SET NULL,
+    frequency VARCHAR NOT NULL,
+    start_date DATE NOT NULL,
+    end_date DATE,
+    last_run DATE
+);
+
+CREATE INDEX recurring_rules_user ON recurring_rules (user_id);
@@ -0,0 +1,9 @@
+CREATE TABLE budgets (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES expense_tags(id) ON DELETE CASCADE,
+    monthly_limit BIGINT NOT NULL CHECK (monthly_limit > 0),
+    rollover BOOLEAN NOT NULL DEFAULT FALSE,
+    start_month DATE NOT NULL,
+    UNIQUE (user_id, tag_id)
+);
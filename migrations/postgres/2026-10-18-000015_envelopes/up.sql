@@ -0,0 +1,26 @@
+-- Income and spending count towards envelopes from envelope_start, which is set the
+-- first time the mode is turned on and kept when it is turned off again.
+ALTER TABLE users ADD COLUMN envelope_mode BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN envelope_start DATE;
+
+CREATE TABLE envelopes (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR NOT NULL,
+    tag_id INTEGER REFERENCES expense_tags(id) ON DELETE SET NULL,
+    UNIQUE (user_id, tag_id)
+);
+
+-- Money moved into (to_envelope) or out of (from_envelope) envelopes; a missing side
+-- is the user's pool of income that is still available to assign.
+CREATE TABLE envelope_moves (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    from_envelope INTEGER REFERENCES envelopes(id),
+    to_envelope INTEGER REFERENCES envelopes(id),
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    moved_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (from_envelope IS NOT NULL OR to_envelope IS NOT NULL)
+);
+
+CREATE INDEX envelope_moves_user ON envelope_moves (user_id);
@@ -0,0 +1,19 @@
+CREATE TABLE savings_goals (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR NOT NULL,
+    target_amount BIGINT NOT NULL CHECK (target_amount > 0),
+    currency VARCHAR(3) NOT NULL,
+    target_date DATE NOT NULL
+);
+
+-- Part of an income or transfer transaction set aside for a goal, in the
+-- transaction's currency.
+CREATE TABLE goal_contributions (
+    id SERIAL PRIMARY KEY,
+    goal_id INTEGER NOT NULL REFERENCES savings_goals(id) ON DELETE CASCADE,
+    transaction_id INTEGER NOT NULL REFERENCES transactions(tran_id) ON DELETE CASCADE,
+    amount BIGINT NOT NULL CHECK (amount > 0)
+);
+
+CREATE INDEX goal_contributions_goal ON goal_contributions (goal_id);
@@ -0,0 +1,2 @@
+DROP INDEX transactions_external_id;
+ALTER TABLE transactions DROP COLUMN external_id;
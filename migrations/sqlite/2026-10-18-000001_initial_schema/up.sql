@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS users (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL UNIQUE,
+    password TEXT NOT NULL,
+    secret_pass TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS expense_tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS transactions (
+    tran_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tran_type TEXT NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tran_source TEXT NOT NULL,
+    date TIMESTAMP NOT NULL,
+    tran_amount DOUBLE NOT NULL,
+    tran_comment TEXT,
+    tag_id INTEGER REFERENCES expense_tags(id)
+);
+
+CREATE INDEX IF NOT EXISTS transactions_user_id_date_idx ON transactions (user_id, date);
@@ -0,0 +1,13 @@
+INSERT INTO expense_tags (name)
+SELECT column1
+FROM (VALUES
+    ('Food'),
+    ('Transport'),
+    ('Housing'),
+    ('Utilities'),
+    ('Health'),
+    ('Entertainment'),
+    ('Shopping'),
+    ('Other')
+)
+WHERE NOT EXISTS (SELECT 1 FROM expense_tags);
@@ -0,0 +1,20 @@
+-- Amounts are stored as integer cents from now on. SQLite cannot change a column
+-- type in place, so the table is rebuilt.
+CREATE TABLE transactions_new (
+    tran_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    tran_type TEXT NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tran_source TEXT NOT NULL,
+    date TIMESTAMP NOT NULL,
+    tran_amount BIGINT NOT NULL,
+    tran_comment TEXT,
+    tag_id INTEGER REFERENCES expense_tags(id)
+);
+
+INSERT INTO transactions_new (tran_id, tran_type, user_id, tran_source, date, tran_amount, tran_comment, tag_id)
+SELECT tran_id, tran_type, user_id, tran_source, date, CAST(ROUND(tran_amount * 100) AS INTEGER), tran_comment, tag_id
+FROM transactions;
+
+DROP TABLE transactions;
+ALTER TABLE transactions_new RENAME TO transactions;
+CREATE INDEX transactions_user_id_date_idx ON transactions (user_id, date);
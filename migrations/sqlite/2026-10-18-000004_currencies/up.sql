@@ -0,0 +1,11 @@
+ALTER TABLE users ADD COLUMN base_currency TEXT NOT NULL DEFAULT 'EUR';
+ALTER TABLE transactions ADD COLUMN currency TEXT NOT NULL DEFAULT 'EUR';
+
+CREATE TABLE exchange_rates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    rate_date DATE NOT NULL,
+    currency TEXT NOT NULL,
+    quote_currency TEXT NOT NULL,
+    rate_micros BIGINT NOT NULL CHECK (rate_micros > 0),
+    UNIQUE (rate_date, currency, quote_currency)
+);
@@ -0,0 +1,18 @@
+CREATE TABLE accounts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    UNIQUE (user_id, name)
+);
+
+ALTER TABLE transactions ADD COLUMN account_id INTEGER REFERENCES accounts(id);
+ALTER TABLE transactions ADD COLUMN transfer_account_id INTEGER REFERENCES accounts(id);
+
+-- Every existing user gets one account holding all of their current transactions.
+INSERT INTO accounts (user_id, name) SELECT id, 'Main' FROM users;
+
+UPDATE transactions
+SET account_id = (
+    SELECT a.id FROM accounts a
+    WHERE a.user_id = transactions.user_id AND a.name = 'Main'
+);
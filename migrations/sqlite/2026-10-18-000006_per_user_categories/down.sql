@@ -0,0 +1,21 @@
+-- Point transactions back at the shared template with the same name.
+UPDATE transactions
+SET tag_id = (
+    SELECT g.id
+    FROM expense_tags g
+    JOIN expense_tags c ON c.name = g.name
+    WHERE c.id = transactions.tag_id
+      AND g.user_id IS NULL
+    ORDER BY g.id
+    LIMIT 1
+)
+WHERE tag_id IS NOT NULL;
+
+-- SQLite cannot drop a foreign-key column, so the table is rebuilt.
+CREATE TABLE expense_tags_old (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL
+);
+INSERT INTO expense_tags_old (id, name) SELECT id, name FROM expense_tags WHERE user_id IS NULL;
+DROP TABLE expense_tags;
+ALTER TABLE expense_tags_old RENAME TO expense_tags;
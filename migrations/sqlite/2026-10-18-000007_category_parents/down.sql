@@ -0,0 +1,11 @@
+-- SQLite cannot drop a foreign-key column, so the table is rebuilt.
+CREATE TABLE expense_tags_old (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    user_id INTEGER REFERENCES users(id) ON DELETE CASCADE,
+    color TEXT NOT NULL DEFAULT '#888888'
+);
+INSERT INTO expense_tags_old (id, name, user_id, color) SELECT id, name, user_id, color FROM expense_tags;
+DROP TABLE expense_tags;
+ALTER TABLE expense_tags_old RENAME TO expense_tags;
+CREATE INDEX expense_tags_user_id_idx ON expense_tags (user_id);
@@ -0,0 +1,4 @@
+UPDATE transactions SET tag_id = NULL
+WHERE tag_id IN (SELECT id FROM expense_tags WHERE kind = 'income');
+DELETE FROM expense_tags WHERE kind = 'income';
+ALTER TABLE expense_tags DROP COLUMN kind;
@@ -0,0 +1 @@
+ALTER TABLE transactions ADD COLUMN deleted_at TIMESTAMP;
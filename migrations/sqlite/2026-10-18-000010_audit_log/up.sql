@@ -0,0 +1,23 @@
+CREATE TABLE audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    action TEXT NOT NULL CHECK (action IN ('insert', 'update', 'delete')),
+    entity TEXT NOT NULL,
+    entity_id INTEGER,
+    old_value TEXT,
+    new_value TEXT
+);
+
+CREATE INDEX audit_log_user_time ON audit_log (user_id, created_at);
+
+-- The log is append-only: rows can be added but never changed or removed.
+CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
+
+CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
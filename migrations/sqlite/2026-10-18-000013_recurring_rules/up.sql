@@ -0,0 +1,16 @@
+CREATE TABLE recurring_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tran_type TEXT NOT NULL,
+    source TEXT NOT NULL,
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    currency TEXT NOT NULL,
+    tag_id INTEGER REFERENCES expense_tags(id) ON DELETE SET NULL,
+    account_id INTEGER REFERENCES accounts(id) ON DELETE SET NULL,
+    frequency TEXT NOT NULL,
+    start_date DATE NOT NULL,
+    end_date DATE,
+    last_run DATE
+);
+
+CREATE INDEX recurring_rules_user ON recurring_rules (user_id);
@@ -0,0 +1,4 @@
+DROP TABLE envelope_moves;
+DROP TABLE envelopes;
+ALTER TABLE users DROP COLUMN envelope_start;
+ALTER TABLE users DROP COLUMN envelope_mode;
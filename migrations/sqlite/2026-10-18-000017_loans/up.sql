@@ -0,0 +1,22 @@
+-- direction is 'borrowed' for debts such as a mortgage and 'lent' for money owed to the user.
+CREATE TABLE loans (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    direction TEXT NOT NULL,
+    principal BIGINT NOT NULL CHECK (principal > 0),
+    currency TEXT NOT NULL,
+    annual_rate_bp INTEGER NOT NULL CHECK (annual_rate_bp >= 0),
+    term_months INTEGER NOT NULL CHECK (term_months > 0),
+    start_date DATE NOT NULL,
+    account_id INTEGER REFERENCES accounts(id) ON DELETE SET NULL,
+    interest_tag_id INTEGER REFERENCES expense_tags(id) ON DELETE SET NULL
+);
+
+-- A repayment transaction of a loan. Its amount minus the interest is principal.
+CREATE TABLE loan_payments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    loan_id INTEGER NOT NULL REFERENCES loans(id) ON DELETE CASCADE,
+    transaction_id INTEGER NOT NULL UNIQUE REFERENCES transactions(tran_id) ON DELETE CASCADE,
+    interest BIGINT NOT NULL CHECK (interest >= 0)
+);
@@ -0,0 +1,4 @@
+-- Id the bank gave a transaction in an imported statement, such as an OFX FITID.
+-- Re-importing an overlapping statement skips rows whose id is already stored.
+ALTER TABLE transactions ADD COLUMN external_id TEXT;
+CREATE UNIQUE INDEX transactions_external_id ON transactions (user_id, external_id);
@@ -0,0 +1,38 @@
+use crate::controller::audit_controller::{audited, record};
+use crate::model::{Account, DbPool, NewAccount, NewAuditEntry};
+use crate::model::account::DEFAULT_ACCOUNT_NAME;
+use crate::model::audit::{describe_account, ACTION_INSERT, ENTITY_ACCOUNT};
+
+/// Loads the user's accounts, creating the default one for users who have none yet.
+pub fn load_accounts(pool: &DbPool, uid: i32) -> Result<Vec<Account>, String> {
+    let accounts = pool.load_accounts(uid)
+        .map_err(|e| format!("Query error: {:?}", e))?;
+    if !accounts.is_empty() {
+        return Ok(accounts);
+    }
+
+    insert_account(pool, uid, DEFAULT_ACCOUNT_NAME)?;
+    pool.load_accounts(uid)
+        .map_err(|e| format!("Query error: {:?}", e))
+}
+
+pub fn create_account(pool: &DbPool, uid: i32, name: &str) -> Result<Vec<Account>, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Account name is empty".into());
+    }
+    insert_account(pool, uid, name)?;
+    load_accounts(pool, uid)
+}
+
+fn insert_account(pool: &DbPool, uid: i32, name: &str) -> Result<(), String> {
+    audited(pool, |repo| {
+        repo.insert_account(&NewAccount { user_id: uid, name })?;
+        if let Some(created) = repo.load_accounts(uid)?.iter().find(|a| a.name == name) {
+            record(repo, NewAuditEntry::new(uid, ACTION_INSERT, ENTITY_ACCOUNT, Some(created.id))
+                .after(describe_account(created)))?;
+        }
+        Ok(())
+    })
+    .map_err(|e| format!("Account error: {:?}", e))
+}
@@ -1,17 +1,52 @@
 use iced::Command;
+use iced::widget::text_input;
 use crate::controller::login_controller::attempt_password_reset;
-use crate::controller::transaction_controller::delete_transaction;
-use crate::model::{CombinedApp, Message, Screen, DashboardViewMode, AuthData};
+use crate::controller::transaction_controller::{
+    delete_transaction, empty_trash, load_trash, purge_expired_trash, purge_transaction, replay_mutation,
+    restore_from_trash,
+};
+use crate::model::state::{comment_line_id, insert_comment_line, SplitInput};
+use crate::model::loan::parse_rate;
+use crate::model::csv_import::{detect_delimiter, guess_columns, read_csv, CsvMapping};
+use crate::model::currency::is_currency_code;
+use crate::model::import::{detect_format, BankStatement, ImportFormat};
+use crate::model::camt::parse_camt053;
+use crate::model::mt940::parse_mt940;
+use crate::model::ofx::parse_ofx;
+use crate::model::qif::parse_qif;
+use crate::model::{AuditFilter, CombinedApp, EnvelopeChoice, Message, Screen, DashboardViewMode, AuthData, Money, Mutation, NewLoan, NewRecurringRule, NewSavingsGoal, SplitLine, Toast, TransactionDraft};
 use crate::controller::{
     login_controller::{attempt_login, handle_successful_login},
     registration_controller::attempt_register,
-    transaction_controller::{add_expense, add_income},
+    transaction_controller::{add_expense, add_income, add_transfer, update_transaction},
+    account_controller::create_account,
+    attachment_controller::{load_attachments, remove_attachment, save_with_files},
+    audit_controller::load_audit_log,
+    budget_controller::{load_budgets, remove_budget, save_budget},
+    envelope_controller::{create_envelope, delete_envelope, load_envelope_book, move_money, set_envelope_mode},
+    savings_controller::{create_goal, delete_goal, earmark, remove_contribution},
+    loan_controller::{create_loan, delete_loan, record_repayment},
+    import_controller::{export_qif_file, import_transactions},
+    recurring_controller::{book_due_occurrences, create_rule, delete_rule, load_rules},
+    category_controller::{create_category, delete_category, merge_categories, reload_categories, update_category},
+    currency_controller::{import_exchange_rates, set_base_currency},
 };
-use chrono:: NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use std::time::Instant;
 
 pub fn update(app: &mut CombinedApp, message: Message) -> Command<Message> {
     use Message::*;
 
+    // Any change to the expense form needs a fresh budget check before saving.
+    if matches!(
+        message,
+        ChangeExpenseSum(_) | CategorySelected(_) | ExpenseCurrencySelected(_) | ChangeExpenseDateString(_)
+            | SetExpenseDateToToday | SplitTransaction | AddSplitLine | RemoveSplitLine(_)
+            | SplitCategorySelected(..) | SplitAmountChanged(..)
+    ) {
+        app.budget_warning = None;
+    }
+
     match message {
         LoginUsernameChanged(v) => app.login_username = v,
         LoginPasswordChanged(v) => app.login_password = v,
@@ -49,27 +84,233 @@ pub fn update(app: &mut CombinedApp, message: Message) -> Command<Message> {
         
 
         Message::DeleteTransaction(tx_id) => {
-            let pool = app.pool.clone();
-            return Command::perform(
-        async move {
-            delete_transaction(&pool, tx_id).map_err(|e| e.to_string())
-                },
-        Message::TransactionDeleted,
-            );
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                return Command::perform(
+                    async move { delete_transaction(&pool, uid, tx_id) },
+                    Message::TransactionDeleted,
+                );
+            }
         }
 
-        Message::TransactionDeleted(Ok(())) => {
+        Message::TransactionDeleted(Ok(tx)) => {
+            record_mutation(app, Mutation::Deleted(tx));
+            return reload_transactions(app);
+        }
+
+        Message::TransactionDeleted(Err(e)) => app.toast = Some(Toast::new(e, false)),
+        TransactionAdded(Ok(tx)) => {
+            record_mutation(app, Mutation::Added(tx));
+            return reload_transactions(app);
+        }
+        TransactionAdded(Err(e)) => app.dashboard_message = e,
+        Undo => {
+            if let (Some(uid), Some(mutation)) = (app.user_id, app.history.take_undo()) {
+                let pool = app.pool.clone();
+                return Command::perform(
+                    async move {
+                        match replay_mutation(&pool, uid, &mutation.inverse()) {
+                            Ok(_) => Ok(mutation),
+                            Err(e) => Err((mutation, e)),
+                        }
+                    },
+                    Undone,
+                );
+            }
+        }
+        Redo => {
+            if let (Some(uid), Some(mutation)) = (app.user_id, app.history.take_redo()) {
+                let pool = app.pool.clone();
+                return Command::perform(
+                    async move {
+                        match replay_mutation(&pool, uid, &mutation) {
+                            Ok(_) => Ok(mutation),
+                            Err(e) => Err((mutation, e)),
+                        }
+                    },
+                    Redone,
+                );
+            }
+        }
+        Undone(Ok(mutation)) => {
+            app.toast = Some(Toast::new(format!("Undone: {}", mutation.describe()), false));
+            app.history.push_redo(mutation);
+            return reload_transactions(app);
+        }
+        Redone(Ok(mutation)) => {
+            app.toast = Some(Toast::new(format!("Redone: {}", mutation.describe()), true));
+            app.history.push_undo(mutation);
+            return reload_transactions(app);
+        }
+        // A replay that failed leaves the mutation on the stack it came from.
+        Undone(Err((mutation, e))) => {
+            app.toast = Some(Toast::new(e, false));
+            app.history.push_undo(mutation);
+            return reload_transactions(app);
+        }
+        Redone(Err((mutation, e))) => {
+            app.toast = Some(Toast::new(e, false));
+            app.history.push_redo(mutation);
+            return reload_transactions(app);
+        }
+        ToastTick => {
+            if app.toast.as_ref().is_some_and(|t| t.expired(Instant::now())) {
+                app.toast = None;
+            }
+        }
+        DismissToast => app.toast = None,
+        ChooseTrash => {
+            app.trash_message.clear();
+            app.current_screen = Screen::Dashboard(DashboardViewMode::Trash);
+            return reload_trash(app);
+        }
+        TrashLoaded(Ok(trash)) => app.trash = trash,
+        TrashLoaded(Err(e)) => app.trash_message = e,
+        RestoreFromTrash(tx_id) => {
             if let Some(uid) = app.user_id {
                 let pool = app.pool.clone();
                 return Command::perform(
-                    async move { handle_successful_login(&pool, uid).await },
-                    Message::CombinedLoaded,
+                    async move { restore_from_trash(&pool, uid, tx_id) },
+                    TransactionRestored,
                 );
             }
         }
-
-        Message::TransactionDeleted(Err(e)) => {
-            println!("Error: {}", e);
+        TransactionRestored(Ok(tx)) => {
+            app.trash_message.clear();
+            app.toast = Some(Toast::new(
+                format!("Restored {} '{}'", tx.tran_type.to_lowercase(), tx.tran_source),
+                true,
+            ));
+            app.history.record(Mutation::Added(tx));
+            return Command::batch([reload_trash(app), reload_transactions(app)]);
+        }
+        TransactionRestored(Err(e)) => app.trash_message = e,
+        PurgeTransaction(tx_id) => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                return Command::perform(
+                    async move { purge_transaction(&pool, uid, tx_id) },
+                    TrashPurged,
+                );
+            }
+        }
+        EmptyTrash => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                return Command::perform(
+                    async move { empty_trash(&pool, uid).map(|_| ()) },
+                    TrashPurged,
+                );
+            }
+        }
+        TrashPurged(Ok(())) => {
+            app.trash_message.clear();
+            return reload_trash(app);
+        }
+        TrashPurged(Err(e)) => {
+            app.trash_message = e;
+            // Leftover attachment files are reported after the purge went through.
+            return reload_trash(app);
+        }
+        ExpiredTrashPurged(Ok(_)) => {}
+        ExpiredTrashPurged(Err(e)) => {
+            app.toast = Some(Toast::new(format!("Expired trash: {}", e), false));
+        }
+        ChooseAudit => {
+            app.current_screen = Screen::Dashboard(DashboardViewMode::Audit);
+            return update(app, ApplyAuditFilter);
+        }
+        AuditFromChanged(v) => app.audit_from = v,
+        AuditToChanged(v) => app.audit_to = v,
+        AuditActionSelected(action) => {
+            app.audit_action = action;
+            return update(app, ApplyAuditFilter);
+        }
+        ApplyAuditFilter => {
+            if let Some(uid) = app.user_id {
+                let filter = match audit_filter(app) {
+                    Ok(f) => f,
+                    Err(e) => {
+                        app.audit_message = e;
+                        return Command::none();
+                    }
+                };
+                app.audit_message.clear();
+                let pool = app.pool.clone();
+                return Command::perform(
+                    async move { load_audit_log(&pool, uid, &filter) },
+                    AuditLoaded,
+                );
+            }
+        }
+        AuditLoaded(Ok(entries)) => app.audit_entries = entries,
+        AuditLoaded(Err(e)) => app.audit_message = e,
+        ChooseRecurring => {
+            app.recurring_message.clear();
+            app.clear_rule_form();
+            app.current_screen = Screen::Dashboard(DashboardViewMode::Recurring);
+            return reload_rules(app);
+        }
+        RecurringLoaded(Ok(rules)) => app.recurring_rules = rules,
+        RecurringLoaded(Err(e)) => app.recurring_message = e,
+        RuleTypeSelected(kind) => {
+            if app.rule_type != kind {
+                app.rule_category = None;
+            }
+            app.rule_type = kind;
+        }
+        RuleSourceChanged(v) => app.rule_source = v,
+        RuleAmountChanged(v) => app.rule_amount = v,
+        RuleCurrencySelected(code) => app.rule_currency = code,
+        RuleCategorySelected(category) => app.rule_category = category,
+        RuleAccountSelected(account) => app.rule_account = Some(account),
+        RuleFrequencySelected(frequency) => app.rule_frequency = frequency,
+        RuleStartChanged(v) => app.rule_start = v,
+        RuleEndChanged(v) => app.rule_end = v,
+        SaveRule => {
+            if let Some(uid) = app.user_id {
+                let rule = match rule_from_form(app, uid) {
+                    Ok(rule) => rule,
+                    Err(e) => {
+                        app.recurring_message = e;
+                        return Command::none();
+                    }
+                };
+                let pool = app.pool.clone();
+                return Command::perform(async move { create_rule(&pool, uid, &rule) }, RuleSaved);
+            }
+        }
+        RuleSaved(Ok(rule)) => {
+            app.recurring_message = format!("Saved '{}'", rule.source);
+            app.clear_rule_form();
+            // A rule starting in the past books its missed occurrences right away.
+            return update(app, RecurringTick);
+        }
+        RuleSaved(Err(e)) => app.recurring_message = e,
+        DeleteRule(rule_id) => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                return Command::perform(async move { delete_rule(&pool, uid, rule_id) }, RecurringLoaded);
+            }
+        }
+        RecurringTick => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                let today = chrono::Local::now().date_naive();
+                return Command::perform(
+                    async move { book_due_occurrences(&pool, uid, today) },
+                    RecurringBooked,
+                );
+            }
+        }
+        RecurringBooked(Ok(0)) => return reload_rules(app),
+        RecurringBooked(Ok(count)) => {
+            app.toast = Some(Toast::new(format!("Booked {} recurring transaction(s)", count), false));
+            return Command::batch([reload_transactions(app), reload_rules(app)]);
+        }
+        RecurringBooked(Err(e)) => {
+            app.toast = Some(Toast::new(format!("Recurring transactions: {}", e), false));
+            return reload_rules(app);
         }
         LoginResult(Ok(id)) => {
                 app.user_name = Some(app.login_username.clone());
@@ -81,7 +322,15 @@ pub fn update(app: &mut CombinedApp, message: Message) -> Command<Message> {
                 );
             }
         LoginResult(Err(e)) => app.login_message = e,
-        CombinedLoaded((txs, cats)) => app.apply_login_result(txs, cats),
+        CombinedLoaded(data) => {
+            app.apply_login_result(data);
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                let purge = Command::perform(async move { purge_expired_trash(&pool, uid) }, ExpiredTrashPurged);
+                // Occurrences due while the user was away are booked now and announced like hourly ones.
+                return Command::batch([purge, update(app, RecurringTick)]);
+            }
+        }
         RegUsernameChanged(v) => app.reg_username = v,
         RegPasswordChanged(v) => app.reg_password = v,
         RegConfirmChanged(v) => app.reg_confirm = v,
@@ -119,12 +368,39 @@ pub fn update(app: &mut CombinedApp, message: Message) -> Command<Message> {
                 app.user_id = None;
                 app.user_name = None;
                 app.transactions.clear();
+                app.history.clear();
+                app.toast = None;
+                app.trash.clear();
+                app.audit_entries.clear();
                 app.login_password.clear();
             }
         SwitchToRegistration => app.current_screen = Screen::Registration,
-        ChooseAddExpense => app.current_screen = Screen::Dashboard(DashboardViewMode::AddExpense),
-        ChooseAddIncome => app.current_screen = Screen::Dashboard(DashboardViewMode::AddIncome),
-        CancelDashboardAction => app.current_screen = Screen::Dashboard(DashboardViewMode::Main),
+        ChooseAddExpense => {
+                if app.editing_transaction.is_some() {
+                    app.clear_expense_form();
+                }
+                app.dashboard_message.clear();
+                app.current_screen = Screen::Dashboard(DashboardViewMode::AddExpense);
+            }
+        ChooseAddTransfer => {
+                app.dashboard_message.clear();
+                app.current_screen = Screen::Dashboard(DashboardViewMode::AddTransfer);
+            }
+        ChooseAddIncome => {
+                if app.editing_transaction.is_some() {
+                    app.clear_income_form();
+                }
+                app.dashboard_message.clear();
+                app.current_screen = Screen::Dashboard(DashboardViewMode::AddIncome);
+            }
+        CancelDashboardAction => {
+                if app.editing_transaction.is_some() {
+                    app.clear_expense_form();
+                    app.clear_income_form();
+                }
+                app.dashboard_message.clear();
+                app.current_screen = Screen::Dashboard(DashboardViewMode::Main);
+            }
         ChangeStoreName(v) => app.store_name = v,
 
         ChangeExpenseSum(v) => app.expense_sum = v,
@@ -136,43 +412,171 @@ pub fn update(app: &mut CombinedApp, message: Message) -> Command<Message> {
             },
         ChangeIncomeSum(v) => app.income_sum = v,
         CategorySelected(cat) => app.selected_category = cat,
+        IncomeCategorySelected(cat) => app.selected_income_category = cat,
         ConfirmAddExpense => {
                 if let Some(uid) = app.user_id {
-                    let store = app.store_name.clone();
-                    let date = chrono::Local::now().naive_local();
-
-                    let amt = app.expense_sum.parse().unwrap_or(0.0);
-                    let tag_id = app.categories.iter()
-                        .position(|c| Some(c.clone()) == app.selected_category)
-                        .map(|i| (i + 1) as i32);
+                    let amount = match positive_amount(&app.expense_sum) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            app.dashboard_message = e;
+                            return Command::none();
+                        }
+                    };
+                    let splits = match form_splits(app) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            app.dashboard_message = e;
+                            return Command::none();
+                        }
+                    };
+                    let date = match form_date(app, &app.expense_date_str) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            app.dashboard_message = e;
+                            return Command::none();
+                        }
+                    };
+                    let tag_id = app.selected_category.as_ref().map(|c| c.id).filter(|_| splits.is_empty());
+                    let draft = TransactionDraft {
+                        source: app.store_name.clone(),
+                        date,
+                        amount,
+                        currency: app.expense_currency.clone(),
+                        account_id: app.selected_account.as_ref().map(|a| a.id),
+                        tag_id,
+                        comment: optional_text(&app.expense_comment),
+                        splits,
+                    };
+                    if app.budget_warning.is_none() {
+                        let editing = app.editing_transaction.as_ref().map(|t| t.tran_id);
+                        let overruns = app.budget_overruns(&draft, editing);
+                        if !overruns.is_empty() {
+                            app.budget_warning = Some(format!("Over budget: {}", overruns.join(" ")));
+                            return Command::none();
+                        }
+                    }
+                    app.budget_warning = None;
                     let pool = app.pool.clone();
+                    let files = app.pending_attachments.clone();
+                    if let Some(tx_id) = app.editing_transaction.as_ref().map(|t| t.tran_id) {
+                        return Command::perform(
+                            async move {
+                                save_with_files(&pool, uid, &files, || update_transaction(&pool, uid, tx_id, &draft))
+                            },
+                            TransactionUpdated,
+                        );
+                    }
 
                     app.clear_expense_form();
 
                     return Command::perform(
                         async move {
-                            let _ = add_expense(&pool, uid, &store, date, amt, tag_id);
-                            handle_successful_login(&pool, uid).await.0
+                            save_with_files(&pool, uid, &files, || {
+                                add_expense(&pool, uid, &draft)
+                            })
                         },
-                        TransactionsLoaded,
+                        TransactionAdded,
                     );
                 }
             }
         ConfirmAddIncome => {
                 if let Some(uid) = app.user_id {
-                    let src = app.income_source.clone();
-                    let date = chrono::Local::now().naive_local();
-                    let amt = app.income_sum.parse().unwrap_or(0.0);
+                    let amount = match positive_amount(&app.income_sum) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            app.dashboard_message = e;
+                            return Command::none();
+                        }
+                    };
+                    let splits = match form_splits(app) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            app.dashboard_message = e;
+                            return Command::none();
+                        }
+                    };
+                    let date = match form_date(app, &app.income_date_str) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            app.dashboard_message = e;
+                            return Command::none();
+                        }
+                    };
+                    let draft = TransactionDraft {
+                        source: app.income_source.clone(),
+                        date,
+                        amount,
+                        currency: app.income_currency.clone(),
+                        account_id: app.selected_account.as_ref().map(|a| a.id),
+                        tag_id: app.selected_income_category.as_ref().map(|c| c.id).filter(|_| splits.is_empty()),
+                        comment: optional_text(&app.income_comment),
+                        splits,
+                    };
                     let pool = app.pool.clone();
+                    let files = app.pending_attachments.clone();
+                    if let Some(tx_id) = app.editing_transaction.as_ref().map(|t| t.tran_id) {
+                        return Command::perform(
+                            async move {
+                                save_with_files(&pool, uid, &files, || update_transaction(&pool, uid, tx_id, &draft))
+                            },
+                            TransactionUpdated,
+                        );
+                    }
 
                     app.clear_income_form();
 
                     return Command::perform(
                         async move {
-                            let _ = add_income(&pool, uid, &src, date, amt);
-                            handle_successful_login(&pool, uid).await.0
+                            save_with_files(&pool, uid, &files, || {
+                                add_income(&pool, uid, &draft)
+                            })
                         },
-                        TransactionsLoaded,
+                        TransactionAdded,
+                    );
+                }
+            }
+        ConfirmAddTransfer => {
+                if let Some(uid) = app.user_id {
+                    let (from, to) = match (&app.transfer_from, &app.transfer_to) {
+                        (Some(from), Some(to)) if from.id != to.id => (from.id, to.id),
+                        (Some(_), Some(_)) => {
+                            app.dashboard_message = "Choose two different accounts".into();
+                            return Command::none();
+                        }
+                        _ => {
+                            app.dashboard_message = "Choose both accounts".into();
+                            return Command::none();
+                        }
+                    };
+                    let amount = match positive_amount(&app.transfer_sum) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            app.dashboard_message = e;
+                            return Command::none();
+                        }
+                    };
+                    let source = if app.transfer_note.trim().is_empty() {
+                        "Transfer".to_string()
+                    } else {
+                        app.transfer_note.trim().to_string()
+                    };
+                    let draft = TransactionDraft {
+                        source,
+                        date: chrono::Local::now().naive_local(),
+                        amount,
+                        currency: app.transfer_currency.clone(),
+                        account_id: Some(from),
+                        tag_id: None,
+                        comment: None,
+                        splits: Vec::new(),
+                    };
+                    let pool = app.pool.clone();
+
+                    app.clear_transfer_form();
+
+                    return Command::perform(
+                        async move { add_transfer(&pool, uid, &draft, to) },
+                        TransactionAdded,
                     );
                 }
             }
@@ -202,25 +606,924 @@ pub fn update(app: &mut CombinedApp, message: Message) -> Command<Message> {
 
         ChangeIncomeDate(v) => {
             if let Ok(parsed) = NaiveDate::parse_from_str(&v, "%Y-%m-%d") {
-                app.income_date = parsed.and_hms_opt(0, 0, 0).unwrap();
+                app.income_date = parsed.and_time(form_time(app));
             }
             app.income_date_str = v;
         },
 
         ChangeExpenseDateString(v) => {
             if let Ok(parsed) = NaiveDate::parse_from_str(&v, "%Y-%m-%d") {
-                app.expense_date = parsed.and_hms_opt(0, 0, 0).unwrap();
+                app.expense_date = parsed.and_time(form_time(app));
             }
             app.expense_date_str = v;
         },
 
         SetExpenseDateToToday => {
-            let today = chrono::Local::now().naive_local().date().and_hms_opt(0, 0, 0).unwrap();
+            let today = chrono::Local::now().naive_local().date().and_time(form_time(app));
             app.expense_date = today;
             app.expense_date_str = today.format("%Y-%m-%d").to_string();
         }
 
+        SetIncomeDateToToday => {
+            let today = chrono::Local::now().naive_local().date().and_time(form_time(app));
+            app.income_date = today;
+            app.income_date_str = today.format("%Y-%m-%d").to_string();
+        }
+
+        ExpenseCurrencySelected(code) => app.expense_currency = code,
+        IncomeCurrencySelected(code) => app.income_currency = code,
+        ChooseCurrencies => {
+            app.currency_message.clear();
+            app.current_screen = Screen::Dashboard(DashboardViewMode::Currencies);
+        }
+        BaseCurrencySelected(code) => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                return Command::perform(
+                    async move { set_base_currency(&pool, uid, &code) },
+                    BaseCurrencySaved,
+                );
+            }
+        }
+        BaseCurrencySaved(Ok(code)) => {
+            app.currency_message = format!("Base currency set to {}", code);
+            app.base_currency = code;
+        }
+        BaseCurrencySaved(Err(e)) => app.currency_message = e,
+        RatesCsvPathChanged(v) => app.rates_csv_path = v,
+        ImportRatesPressed => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                let path = app.rates_csv_path.clone();
+                return Command::perform(
+                    async move { import_exchange_rates(&pool, uid, &path) },
+                    RatesImported,
+                );
+            }
+        }
+        RatesImported(Ok((count, rates))) => {
+            app.currency_message = format!("Imported {} exchange rates", count);
+            app.set_exchange_rates(rates);
+        }
+        RatesImported(Err(e)) => app.currency_message = e,
+
+        AccountSelected(account) => app.selected_account = Some(account),
+        NewAccountNameChanged(v) => app.new_account_name = v,
+        CreateAccountPressed => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                let name = app.new_account_name.clone();
+                return Command::perform(
+                    async move { create_account(&pool, uid, &name) },
+                    AccountsLoaded,
+                );
+            }
+        }
+        AccountsLoaded(Ok(accounts)) => {
+            app.new_account_name.clear();
+            app.dashboard_message.clear();
+            app.set_accounts(accounts);
+        }
+        AccountsLoaded(Err(e)) => app.dashboard_message = e,
+        TransferFromSelected(account) => app.transfer_from = Some(account),
+        TransferToSelected(account) => app.transfer_to = Some(account),
+        ChangeTransferSum(v) => app.transfer_sum = v,
+        TransferCurrencySelected(code) => app.transfer_currency = code,
+
+        ChooseCategories => {
+            app.category_message.clear();
+            app.start_new_category();
+            app.current_screen = Screen::Dashboard(DashboardViewMode::Categories);
+        }
+        EditCategory(category) => {
+            app.category_message.clear();
+            app.category_name_input = category.name.clone();
+            app.category_color_input = category.color.clone();
+            app.category_target = None;
+            app.category_parent_input = category.parent_id
+                .and_then(|p| app.category(p))
+                .cloned();
+            let budget = app.budgets.iter().find(|b| b.tag_id == category.id);
+            app.budget_limit_input = budget.map(|b| b.monthly_limit.to_string()).unwrap_or_default();
+            app.budget_rollover_input = budget.is_some_and(|b| b.rollover);
+            app.editing_category = Some(category);
+        }
+        BudgetLimitChanged(v) => app.budget_limit_input = v,
+        BudgetRolloverToggled(on) => app.budget_rollover_input = on,
+        SaveBudget => {
+            if let (Some(uid), Some(category)) = (app.user_id, app.editing_category.as_ref()) {
+                let limit = match positive_amount(&app.budget_limit_input) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        app.category_message = e;
+                        return Command::none();
+                    }
+                };
+                let (tag_id, rollover) = (category.id, app.budget_rollover_input);
+                let pool = app.pool.clone();
+                return Command::perform(
+                    async move { save_budget(&pool, uid, tag_id, limit, rollover) },
+                    BudgetsLoaded,
+                );
+            }
+        }
+        RemoveBudget => {
+            if let (Some(uid), Some(category)) = (app.user_id, app.editing_category.as_ref()) {
+                let tag_id = category.id;
+                app.budget_limit_input.clear();
+                app.budget_rollover_input = false;
+                let pool = app.pool.clone();
+                return Command::perform(async move { remove_budget(&pool, uid, tag_id) }, BudgetsLoaded);
+            }
+        }
+        BudgetsLoaded(Ok(budgets)) => app.budgets = budgets,
+        BudgetsLoaded(Err(e)) => app.category_message = e,
+        ChooseGoals => {
+            app.goal_message.clear();
+            app.clear_goal_form();
+            app.current_screen = Screen::Dashboard(DashboardViewMode::Goals);
+        }
+        GoalsLoaded(Ok(book)) => {
+            app.earmark_goal = app.earmark_goal
+                .as_ref()
+                .and_then(|goal| book.goals.iter().find(|g| g.id == goal.id).cloned());
+            app.savings_book = book;
+            app.earmark_source = None;
+        }
+        GoalsLoaded(Err(e)) => app.goal_message = e,
+        GoalNameChanged(v) => app.goal_name_input = v,
+        GoalTargetChanged(v) => app.goal_target_input = v,
+        GoalCurrencySelected(code) => app.goal_currency_input = code,
+        GoalDateChanged(v) => app.goal_date_input = v,
+        CreateGoal => {
+            if let Some(uid) = app.user_id {
+                let goal = match goal_from_form(app, uid) {
+                    Ok(goal) => goal,
+                    Err(e) => {
+                        app.goal_message = e;
+                        return Command::none();
+                    }
+                };
+                let pool = app.pool.clone();
+                let today = chrono::Local::now().date_naive();
+                return Command::perform(async move { create_goal(&pool, uid, &goal, today) }, GoalCreated);
+            }
+        }
+        GoalCreated(Ok(book)) => {
+            app.goal_message = format!("Saved '{}'", app.goal_name_input.trim());
+            app.clear_goal_form();
+            return update(app, GoalsLoaded(Ok(book)));
+        }
+        GoalCreated(Err(e)) => app.goal_message = e,
+        DeleteGoal(goal_id) => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                return Command::perform(async move { delete_goal(&pool, uid, goal_id) }, GoalsLoaded);
+            }
+        }
+        EarmarkGoalSelected(goal) => app.earmark_goal = Some(goal),
+        EarmarkSourceSelected(source) => {
+            if let Some(tx) = app.transactions.iter().find(|t| t.tran_id == source.tx_id) {
+                app.earmark_amount_input = app.unearmarked(tx).to_string();
+            }
+            app.earmark_source = Some(source);
+        }
+        EarmarkAmountChanged(v) => app.earmark_amount_input = v,
+        Earmark => {
+            if let Some(uid) = app.user_id {
+                let (goal_id, tx_id) = match (&app.earmark_goal, &app.earmark_source) {
+                    (Some(goal), Some(source)) => (goal.id, source.tx_id),
+                    _ => {
+                        app.goal_message = "Choose a goal and a transaction".into();
+                        return Command::none();
+                    }
+                };
+                let amount = match positive_amount(&app.earmark_amount_input) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        app.goal_message = e;
+                        return Command::none();
+                    }
+                };
+                app.goal_message.clear();
+                app.earmark_amount_input.clear();
+                let pool = app.pool.clone();
+                return Command::perform(async move { earmark(&pool, uid, goal_id, tx_id, amount) }, GoalsLoaded);
+            }
+        }
+        RemoveContribution(contribution_id) => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                return Command::perform(
+                    async move { remove_contribution(&pool, uid, contribution_id) },
+                    GoalsLoaded,
+                );
+            }
+        }
+        ChooseLoans => {
+            app.loan_message.clear();
+            app.clear_loan_form();
+            app.current_screen = Screen::Dashboard(DashboardViewMode::Loans);
+        }
+        LoansLoaded(Ok(book)) => {
+            if app.selected_loan.is_some_and(|id| !book.loans.iter().any(|l| l.id == id)) {
+                app.selected_loan = None;
+            }
+            app.loan_book = book;
+        }
+        LoansLoaded(Err(e)) => app.loan_message = e,
+        LoanNameChanged(v) => app.loan_name_input = v,
+        LoanDirectionSelected(direction) => {
+            if app.loan_direction != direction {
+                app.loan_interest_category = None;
+            }
+            app.loan_direction = direction;
+        }
+        LoanPrincipalChanged(v) => app.loan_principal_input = v,
+        LoanCurrencySelected(code) => app.loan_currency_input = code,
+        LoanRateChanged(v) => app.loan_rate_input = v,
+        LoanTermChanged(v) => app.loan_term_input = v,
+        LoanStartChanged(v) => app.loan_start_input = v,
+        LoanAccountSelected(account) => app.loan_account = Some(account),
+        LoanInterestCategorySelected(category) => app.loan_interest_category = category,
+        CreateLoan => {
+            if let Some(uid) = app.user_id {
+                let loan = match loan_from_form(app, uid) {
+                    Ok(loan) => loan,
+                    Err(e) => {
+                        app.loan_message = e;
+                        return Command::none();
+                    }
+                };
+                let pool = app.pool.clone();
+                return Command::perform(async move { create_loan(&pool, uid, &loan) }, LoanCreated);
+            }
+        }
+        LoanCreated(Ok(book)) => {
+            app.loan_message = format!("Saved '{}'", app.loan_name_input.trim());
+            app.clear_loan_form();
+            app.selected_loan = book.loans.last().map(|l| l.id);
+            return update(app, LoansLoaded(Ok(book)));
+        }
+        LoanCreated(Err(e)) => app.loan_message = e,
+        DeleteLoan(loan_id) => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                return Command::perform(async move { delete_loan(&pool, uid, loan_id) }, LoansLoaded);
+            }
+        }
+        ShowLoan(loan_id) => {
+            app.selected_loan = Some(loan_id);
+            app.repayment_amount_input = app.loan(loan_id)
+                .and_then(|loan| app.loan_projection(loan).first().map(|row| row.payment.to_string()))
+                .unwrap_or_default();
+            app.repayment_date_input = chrono::Local::now().date_naive().format("%Y-%m-%d").to_string();
+        }
+        RepaymentAmountChanged(v) => app.repayment_amount_input = v,
+        RepaymentDateChanged(v) => app.repayment_date_input = v,
+        RecordRepayment => {
+            if let (Some(uid), Some(loan_id)) = (app.user_id, app.selected_loan) {
+                let amount = match positive_amount(&app.repayment_amount_input) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        app.loan_message = e;
+                        return Command::none();
+                    }
+                };
+                let date = match optional_date(&app.repayment_date_input) {
+                    Ok(Some(date)) => date,
+                    Ok(None) => {
+                        app.loan_message = "Enter the date of the repayment".into();
+                        return Command::none();
+                    }
+                    Err(e) => {
+                        app.loan_message = e;
+                        return Command::none();
+                    }
+                };
+                let pool = app.pool.clone();
+                return Command::perform(
+                    async move { record_repayment(&pool, uid, loan_id, date, amount) },
+                    RepaymentRecorded,
+                );
+            }
+        }
+        RepaymentRecorded(Ok(book)) => {
+            app.loan_message = "Repayment booked".into();
+            app.loan_book = book;
+            app.repayment_amount_input.clear();
+            return reload_transactions(app);
+        }
+        RepaymentRecorded(Err(e)) => app.loan_message = e,
+        ChooseImport => {
+            app.import_message.clear();
+            app.import_currency = app.base_currency.clone();
+            app.import_account = app.selected_account.clone();
+            app.current_screen = Screen::Dashboard(DashboardViewMode::Import);
+        }
+        PickImportFile => {
+            return Command::perform(
+                async {
+                    let file = rfd::AsyncFileDialog::new()
+                        .set_title("Import a bank statement")
+                        .add_filter("Bank statements", &["csv", "txt", "ofx", "qfx", "qif", "xml", "sta", "mt940"])
+                        .add_filter("All files", &["*"])
+                        .pick_file()
+                        .await?;
+                    let bytes = file.read().await;
+                    Some((file.file_name(), String::from_utf8_lossy(&bytes).into_owned()))
+                },
+                ImportFileLoaded,
+            );
+        }
+        ImportFileLoaded(None) => {}
+        ImportFileLoaded(Some((name, text))) => {
+            app.import_format = detect_format(&name, &text);
+            app.import_rows.clear();
+            app.import_statements.clear();
+            match app.import_format {
+                ImportFormat::Csv => {
+                    let mut mapping = CsvMapping { delimiter: detect_delimiter(&text), ..CsvMapping::default() };
+                    match read_csv(&text, mapping.delimiter) {
+                        Ok(rows) => {
+                            guess_columns(&rows, &mut mapping);
+                            app.import_message = format!("{} rows read", rows.len());
+                            app.import_rows = rows;
+                        }
+                        Err(e) => app.import_message = e,
+                    }
+                    app.import_mapping = mapping;
+                }
+                _ => read_statements(app, &text),
+            }
+            app.import_file_name = name;
+            app.import_text = text;
+        }
+        ImportDelimiterSelected(delimiter) => {
+            app.import_mapping.delimiter = delimiter;
+            match read_csv(&app.import_text, delimiter) {
+                Ok(rows) => {
+                    guess_columns(&rows, &mut app.import_mapping);
+                    app.import_rows = rows;
+                }
+                Err(e) => {
+                    app.import_message = e;
+                    app.import_rows.clear();
+                }
+            }
+        }
+        ImportHeaderToggled(has_header) => app.import_mapping.has_header = has_header,
+        ImportColumnSelected(field, choice) => app.import_mapping.set_column(field, choice.index),
+        ImportDateFormatSelected(format) => app.import_mapping.date_format = format,
+        ImportDecimalSelected(decimal) => app.import_mapping.decimal = decimal,
+        ImportSignSelected(sign) => app.import_mapping.sign = sign,
+        ImportCurrencySelected(code) => app.import_currency = code,
+        ImportAccountSelected(account) => app.import_account = Some(account),
+        ImportDayFirstToggled(day_first) => {
+            app.import_day_first = day_first;
+            if app.import_format == ImportFormat::Qif {
+                let text = std::mem::take(&mut app.import_text);
+                read_statements(app, &text);
+                app.import_text = text;
+            }
+        }
+        ImportTransactions => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                let rows = app.importable_rows();
+                let currency = app.import_currency.clone();
+                let account_id = app.import_account.as_ref().map(|a| a.id);
+                return Command::perform(
+                    async move { import_transactions(&pool, uid, &rows, &currency, account_id) },
+                    TransactionsImported,
+                );
+            }
+        }
+        TransactionsImported(Ok(summary)) => {
+            app.import_message = format!("{} from {}", summary, app.import_file_name);
+            app.toast = Some(Toast::new(app.import_message.clone(), false));
+            app.import_file_name.clear();
+            app.import_text.clear();
+            app.import_rows.clear();
+            app.import_statements.clear();
+            return reload_transactions(app);
+        }
+        TransactionsImported(Err(e)) => app.import_message = e,
+        ExportQif => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                return Command::perform(
+                    async move {
+                        let file = rfd::AsyncFileDialog::new()
+                            .set_title("Export transactions as QIF")
+                            .add_filter("QIF files", &["qif"])
+                            .set_file_name("transactions.qif")
+                            .save_file()
+                            .await?;
+                        Some(export_qif_file(&pool, uid, file.path())
+                            .map(|count| format!("Exported {} transactions to {}", count, file.path().display())))
+                    },
+                    QifExported,
+                );
+            }
+        }
+        QifExported(None) => {}
+        QifExported(Some(result)) => {
+            app.import_message = match result {
+                Ok(message) | Err(message) => message,
+            };
+        }
+        ChooseEnvelopes => {
+            app.envelope_message.clear();
+            app.current_screen = Screen::Dashboard(DashboardViewMode::Envelopes);
+        }
+        EnvelopeModeToggled(enabled) => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                let today = chrono::Local::now().date_naive();
+                return Command::perform(
+                    async move { set_envelope_mode(&pool, uid, enabled, today) },
+                    EnvelopesLoaded,
+                );
+            }
+        }
+        EnvelopesLoaded(Ok(book)) => {
+            let keep = |choice: &Option<EnvelopeChoice>| {
+                choice.as_ref().filter(|c| c.id.is_none_or(|id| book.envelopes.iter().any(|e| e.id == id))).cloned()
+            };
+            app.move_from = keep(&app.move_from);
+            app.move_to = keep(&app.move_to);
+            app.envelope_book = book;
+            app.envelope_name_input.clear();
+            app.envelope_category_input = None;
+        }
+        EnvelopesLoaded(Err(e)) => app.envelope_message = e,
+        EnvelopeNameChanged(v) => app.envelope_name_input = v,
+        EnvelopeCategorySelected(category) => app.envelope_category_input = category,
+        CreateEnvelope => {
+            if let Some(uid) = app.user_id {
+                let name = app.envelope_name_input.clone();
+                let tag_id = app.envelope_category_input.as_ref().map(|c| c.id);
+                app.envelope_message.clear();
+                let pool = app.pool.clone();
+                return Command::perform(async move { create_envelope(&pool, uid, &name, tag_id) }, EnvelopesLoaded);
+            }
+        }
+        DeleteEnvelope(envelope_id) => {
+            if let Some(uid) = app.user_id {
+                app.envelope_message.clear();
+                let pool = app.pool.clone();
+                return Command::perform(async move { delete_envelope(&pool, uid, envelope_id) }, EnvelopesLoaded);
+            }
+        }
+        MoveFromSelected(choice) => app.move_from = Some(choice),
+        MoveToSelected(choice) => app.move_to = Some(choice),
+        MoveAmountChanged(v) => app.move_amount_input = v,
+        MoveMoney => {
+            if let Some(uid) = app.user_id {
+                let (from, to) = match (&app.move_from, &app.move_to) {
+                    (Some(from), Some(to)) if from != to => (from.clone(), to.clone()),
+                    (Some(_), Some(_)) => {
+                        app.envelope_message = "Choose two different envelopes".into();
+                        return Command::none();
+                    }
+                    _ => {
+                        app.envelope_message = "Choose where to move the money from and to".into();
+                        return Command::none();
+                    }
+                };
+                let amount = match positive_amount(&app.move_amount_input) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        app.envelope_message = e;
+                        return Command::none();
+                    }
+                };
+                let held = app.envelope_choice_balance(&from);
+                if amount > held {
+                    app.envelope_message = format!("Only {} {} left in {}", held, app.base_currency, from);
+                    return Command::none();
+                }
+                app.envelope_message = format!("Moved {} {} from {} to {}", amount, app.base_currency, from, to);
+                app.move_amount_input.clear();
+                let pool = app.pool.clone();
+                return Command::perform(
+                    async move { move_money(&pool, uid, from.id, to.id, amount) },
+                    EnvelopesLoaded,
+                );
+            }
+        }
+        NewCategoryPressed => {
+            app.category_message.clear();
+            app.start_new_category();
+        }
+        CategoryNameChanged(v) => app.category_name_input = v,
+        CategoryColorChanged(v) => app.category_color_input = v,
+        CategoryTargetSelected(category) => app.category_target = Some(category),
+        CategoryParentSelected(parent) => app.category_parent_input = parent,
+        CategoryKindSelected(kind) => {
+            app.category_kind_input = kind;
+            app.category_parent_input = None;
+        }
+        SaveCategory => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                let editing = app.editing_category.as_ref().map(|c| c.id);
+                let name = app.category_name_input.clone();
+                let color = app.category_color_input.clone();
+                let parent = app.category_parent_input.as_ref().map(|c| c.id);
+                let kind = app.category_kind_input;
+                return Command::perform(
+                    async move {
+                        match editing {
+                            Some(id) => update_category(&pool, uid, id, &name, &color, parent)?,
+                            None => create_category(&pool, uid, &name, &color, parent, kind)?,
+                        }
+                        reload_categories(&pool, uid)
+                    },
+                    CategoriesUpdated,
+                );
+            }
+        }
+        MergeCategory => {
+            if let (Some(uid), Some(from), Some(into)) =
+                (app.user_id, app.editing_category.as_ref(), app.category_target.as_ref())
+            {
+                let pool = app.pool.clone();
+                let (from, into) = (from.id, into.id);
+                return Command::perform(
+                    async move {
+                        merge_categories(&pool, uid, from, into)?;
+                        reload_categories(&pool, uid)
+                    },
+                    CategoriesUpdated,
+                );
+            } else {
+                app.category_message = "Choose the category to merge into".into();
+            }
+        }
+        DeleteCategory => {
+            if let (Some(uid), Some(category)) = (app.user_id, app.editing_category.as_ref()) {
+                let used = app.category_usage(category.id);
+                let target = app.category_target.as_ref().map(|c| c.id);
+                if used > 0 && target.is_none() {
+                    app.category_message = format!(
+                        "'{}' has {} transactions. Choose where to move them before deleting.",
+                        category.name, used
+                    );
+                    return Command::none();
+                }
+                let pool = app.pool.clone();
+                let id = category.id;
+                return Command::perform(
+                    async move {
+                        delete_category(&pool, uid, id, target)?;
+                        reload_categories(&pool, uid)
+                    },
+                    CategoriesUpdated,
+                );
+            }
+        }
+        CategoriesUpdated(Ok((categories, transactions))) => {
+            app.category_message = "Categories saved".into();
+            app.categories = categories;
+            app.transactions = transactions;
+            app.selected_category = None;
+            app.selected_income_category = None;
+            if app.chart_parent.is_some_and(|id| app.category(id).is_none()) {
+                app.chart_parent = None;
+            }
+            app.start_new_category();
+            // Deleting a category removes its budget and unlinks its envelope.
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                let envelope_pool = app.pool.clone();
+                return Command::batch([
+                    Command::perform(async move { load_budgets(&pool, uid) }, BudgetsLoaded),
+                    Command::perform(async move { load_envelope_book(&envelope_pool, uid) }, EnvelopesLoaded),
+                ]);
+            }
+        }
+        CategoriesUpdated(Err(e)) => app.category_message = e,
+        DrillIntoCategory(id) => app.chart_parent = Some(id),
+        ChartUp => {
+            app.chart_parent = app.chart_parent
+                .and_then(|id| app.category(id))
+                .and_then(|c| c.parent_id);
+        }
+        ChangeTransferNote(v) => app.transfer_note = v,
+        EditTransaction(tx) => {
+            if let (false, Some(uid)) = (tx.is_transfer(), app.user_id) {
+                let tx_id = tx.tran_id;
+                app.start_editing(tx);
+                let pool = app.pool.clone();
+                return Command::perform(
+                    async move { load_attachments(&pool, uid, tx_id) },
+                    AttachmentsLoaded,
+                );
+            }
+        }
+        PickAttachments => {
+            return Command::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Attach receipts or documents")
+                        .add_filter("Receipts and documents", &["png", "jpg", "jpeg", "gif", "webp", "bmp", "pdf", "txt"])
+                        .add_filter("All files", &["*"])
+                        .pick_files()
+                        .await
+                        .map(|files| files.iter().map(|f| f.path().to_path_buf()).collect())
+                        .unwrap_or_default()
+                },
+                AttachmentsPicked,
+            );
+        }
+        AttachmentsPicked(paths) => {
+            for path in paths {
+                if !app.pending_attachments.contains(&path) {
+                    app.pending_attachments.push(path);
+                }
+            }
+        }
+        RemovePendingAttachment(index) => {
+            if index < app.pending_attachments.len() {
+                app.pending_attachments.remove(index);
+            }
+        }
+        RemoveAttachment(attachment_id) => {
+            if let Some(uid) = app.user_id {
+                let pool = app.pool.clone();
+                return Command::perform(
+                    async move { remove_attachment(&pool, uid, attachment_id) },
+                    AttachmentRemoved,
+                );
+            }
+        }
+        AttachmentRemoved(Ok(attachments)) => return update(app, AttachmentsLoaded(Ok(attachments))),
+        AttachmentRemoved(Err(e)) => {
+            app.dashboard_message = e;
+            // The attachment may be gone even though its file stayed behind.
+            if let (Some(tx), Some(uid)) = (&app.editing_transaction, app.user_id) {
+                let (pool, tx_id) = (app.pool.clone(), tx.tran_id);
+                return Command::perform(async move { load_attachments(&pool, uid, tx_id) }, AttachmentsLoaded);
+            }
+        }
+        AttachmentsLoaded(Ok(attachments)) => {
+            if app.preview_attachment.is_some_and(|id| !attachments.iter().any(|a| a.id == id)) {
+                app.preview_attachment = None;
+            }
+            app.attachments = attachments;
+        }
+        AttachmentsLoaded(Err(e)) => app.dashboard_message = e,
+        PreviewAttachment(id) => app.preview_attachment = id,
+        SplitTransaction => {
+            // The current category and total become the first line; the second line takes the rest.
+            let (category, total) = match app.current_screen {
+                Screen::Dashboard(DashboardViewMode::AddIncome) => {
+                    (app.selected_income_category.take(), app.income_sum.clone())
+                }
+                _ => (app.selected_category.take(), app.expense_sum.clone()),
+            };
+            app.split_lines = vec![
+                SplitInput { category, amount: total.trim().to_string() },
+                SplitInput::default(),
+            ];
+        }
+        AddSplitLine => {
+            let total = match app.current_screen {
+                Screen::Dashboard(DashboardViewMode::AddIncome) => Money::parse(&app.income_sum),
+                _ => Money::parse(&app.expense_sum),
+            };
+            let assigned: Money = app.split_lines.iter().filter_map(|l| Money::parse(&l.amount).ok()).sum();
+            let remaining = total.ok().map(|t| t - assigned).filter(|r| *r > Money::ZERO);
+            app.split_lines.push(SplitInput {
+                category: None,
+                amount: remaining.map(|r| r.to_string()).unwrap_or_default(),
+            });
+        }
+        RemoveSplitLine(index) => {
+            if index < app.split_lines.len() {
+                app.split_lines.remove(index);
+            }
+            // A single remaining line is just an ordinary category again.
+            if app.split_lines.len() == 1 {
+                let line = app.split_lines.remove(0);
+                match app.current_screen {
+                    Screen::Dashboard(DashboardViewMode::AddIncome) => app.selected_income_category = line.category,
+                    _ => app.selected_category = line.category,
+                }
+            }
+        }
+        SplitCategorySelected(index, category) => {
+            if let Some(line) = app.split_lines.get_mut(index) {
+                line.category = Some(category);
+            }
+        }
+        SplitAmountChanged(index, amount) => {
+            if let Some(line) = app.split_lines.get_mut(index) {
+                line.amount = amount;
+            }
+        }
+        ChangeExpenseComment(v) => app.expense_comment = v,
+        ChangeIncomeComment(v) => app.income_comment = v,
+        ExpenseCommentNewLine(line) => {
+            app.expense_comment = insert_comment_line(&app.expense_comment, line);
+            return text_input::focus(text_input::Id::new(comment_line_id("expense", line + 1)));
+        }
+        IncomeCommentNewLine(line) => {
+            app.income_comment = insert_comment_line(&app.income_comment, line);
+            return text_input::focus(text_input::Id::new(comment_line_id("income", line + 1)));
+        }
+        SearchChanged(query) => app.search_query = query,
+        TransactionUpdated(Ok(after)) => {
+            if let Some(before) = app.editing_transaction.take() {
+                record_mutation(app, Mutation::Edited { before: Box::new(before), after: Box::new(after) });
+            }
+            app.clear_expense_form();
+            app.clear_income_form();
+            app.current_screen = Screen::Dashboard(DashboardViewMode::Main);
+            return reload_transactions(app);
+        }
+        TransactionUpdated(Err(e)) => app.dashboard_message = e,
+
     }
 
     Command::none()
 }
+
+/// Remembers a mutation for undo and offers it in a toast.
+fn record_mutation(app: &mut CombinedApp, mutation: Mutation) {
+    app.toast = Some(Toast::new(mutation.describe(), true));
+    app.history.record(mutation);
+}
+
+/// Reads the picked file into the import preview, for the formats that need no column
+/// mapping. The statement's currency becomes the import currency.
+fn read_statements(app: &mut CombinedApp, text: &str) {
+    let parsed = match app.import_format {
+        ImportFormat::Csv => return,
+        ImportFormat::Ofx => parse_ofx(text),
+        ImportFormat::Qif => parse_qif(text, app.import_day_first)
+            .map(|rows| vec![BankStatement { rows, ..BankStatement::default() }]),
+        ImportFormat::Camt053 => parse_camt053(text),
+        ImportFormat::Mt940 => parse_mt940(text),
+    };
+    match parsed {
+        Ok(statements) => {
+            if let Some(code) = statements.iter().find_map(|st| st.currency.clone()) {
+                if is_currency_code(&code) {
+                    app.import_currency = code;
+                }
+            }
+            app.import_message = format!("{} statement(s) read", statements.len());
+            app.import_statements = statements;
+        }
+        Err(e) => {
+            app.import_message = e;
+            app.import_statements.clear();
+        }
+    }
+}
+
+fn reload_transactions(app: &CombinedApp) -> Command<Message> {
+    match app.user_id {
+        Some(uid) => {
+            let pool = app.pool.clone();
+            Command::perform(
+                async move { handle_successful_login(&pool, uid).await.transactions },
+                Message::TransactionsLoaded,
+            )
+        }
+        None => Command::none(),
+    }
+}
+
+fn audit_filter(app: &CombinedApp) -> Result<AuditFilter, String> {
+    Ok(AuditFilter {
+        from: optional_date(&app.audit_from)?,
+        to: optional_date(&app.audit_to)?,
+        action: app.audit_action.map(str::to_string),
+    })
+}
+
+/// Parses a YYYY-MM-DD input; an empty input means no date.
+fn optional_date(input: &str) -> Result<Option<NaiveDate>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .map(Some)
+        .map_err(|_| format!("'{}' is not a date like 2024-01-31", input))
+}
+
+fn rule_from_form(app: &CombinedApp, uid: i32) -> Result<NewRecurringRule, String> {
+    let start_date = optional_date(&app.rule_start)?.ok_or_else(|| "Enter a start date".to_string())?;
+    Ok(NewRecurringRule {
+        user_id: uid,
+        tran_type: app.rule_type.to_string(),
+        source: app.rule_source.trim().to_string(),
+        amount: positive_amount(&app.rule_amount)?,
+        currency: app.rule_currency.clone(),
+        tag_id: app.rule_category.as_ref().map(|c| c.id),
+        account_id: app.rule_account.as_ref().map(|a| a.id),
+        frequency: app.rule_frequency.to_string(),
+        start_date,
+        end_date: optional_date(&app.rule_end)?,
+    })
+}
+
+fn goal_from_form(app: &CombinedApp, uid: i32) -> Result<NewSavingsGoal, String> {
+    let target_date = optional_date(&app.goal_date_input)?.ok_or_else(|| "Enter a target date".to_string())?;
+    Ok(NewSavingsGoal {
+        user_id: uid,
+        name: app.goal_name_input.trim().to_string(),
+        target_amount: positive_amount(&app.goal_target_input)?,
+        currency: app.goal_currency_input.clone(),
+        target_date,
+    })
+}
+
+fn loan_from_form(app: &CombinedApp, uid: i32) -> Result<NewLoan, String> {
+    let start_date = optional_date(&app.loan_start_input)?.ok_or_else(|| "Enter a start date".to_string())?;
+    let term_months = app.loan_term_input
+        .trim()
+        .parse::<i32>()
+        .map_err(|_| format!("'{}' is not a number of months", app.loan_term_input.trim()))?;
+    Ok(NewLoan {
+        user_id: uid,
+        name: app.loan_name_input.trim().to_string(),
+        direction: app.loan_direction.to_string(),
+        principal: positive_amount(&app.loan_principal_input)?,
+        currency: app.loan_currency_input.clone(),
+        annual_rate_bp: parse_rate(&app.loan_rate_input)?,
+        term_months,
+        start_date,
+        account_id: app.loan_account.as_ref().map(|a| a.id),
+        interest_tag_id: app.loan_interest_category.as_ref().map(|c| c.id),
+    })
+}
+
+fn reload_rules(app: &CombinedApp) -> Command<Message> {
+    match app.user_id {
+        Some(uid) => {
+            let pool = app.pool.clone();
+            Command::perform(async move { load_rules(&pool, uid) }, Message::RecurringLoaded)
+        }
+        None => Command::none(),
+    }
+}
+
+fn reload_trash(app: &CombinedApp) -> Command<Message> {
+    match app.user_id {
+        Some(uid) => {
+            let pool = app.pool.clone();
+            Command::perform(async move { load_trash(&pool, uid) }, Message::TrashLoaded)
+        }
+        None => Command::none(),
+    }
+}
+
+/// The date typed into the expense or income form. Left blank, a new entry is stamped
+/// with the current time and an edit keeps the transaction's date.
+fn form_date(app: &CombinedApp, input: &str) -> Result<NaiveDateTime, String> {
+    Ok(match optional_date(input)? {
+        Some(date) => date.and_time(form_time(app)),
+        None if app.editing_transaction.is_some() => app.expense_date,
+        None => chrono::Local::now().naive_local(),
+    })
+}
+
+/// Time of day for a date picked in the form: an edit keeps the transaction's own time,
+/// so changing its date doesn't move it within the day.
+fn form_time(app: &CombinedApp) -> NaiveTime {
+    match app.editing_transaction {
+        Some(_) => app.expense_date.time(),
+        None => NaiveTime::MIN,
+    }
+}
+
+fn optional_text(input: &str) -> Option<String> {
+    let trimmed = input.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+/// Split lines of the expense or income form; empty when the transaction is not split.
+fn form_splits(app: &CombinedApp) -> Result<Vec<SplitLine>, String> {
+    app.split_lines
+        .iter()
+        .map(|line| {
+            Ok(SplitLine {
+                tag_id: line.category.as_ref().map(|c| c.id),
+                amount: positive_amount(&line.amount)?,
+            })
+        })
+        .collect()
+}
+
+fn positive_amount(input: &str) -> Result<Money, String> {
+    match Money::parse(input)? {
+        v if v > Money::ZERO => Ok(v),
+        _ => Err("Amount must be greater than zero".into()),
+    }
+}
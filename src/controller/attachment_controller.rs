@@ -0,0 +1,140 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use diesel::result::Error as DieselError;
+use crate::controller::audit_controller::{audited, record};
+use crate::model::{Attachment, DbPool, NewAttachment, NewAuditEntry, StoredFile, Transaction};
+use crate::model::attachment::{content_hash, format_size, mime_type_for, stored_path, MAX_ATTACHMENT_BYTES};
+use crate::model::audit::{ACTION_DELETE, ACTION_INSERT, ENTITY_ATTACHMENT};
+
+/// Copies the files into the content-addressed store. Nothing is linked to a
+/// transaction yet, so this can run before the transaction is saved.
+pub fn store_files(paths: &[PathBuf]) -> Result<Vec<StoredFile>, String> {
+    let mut stored = Vec::with_capacity(paths.len());
+    for path in paths {
+        let size = fs::metadata(path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?
+            .len();
+        if size > MAX_ATTACHMENT_BYTES {
+            return Err(format!(
+                "{} is larger than {}",
+                path.display(),
+                format_size(MAX_ATTACHMENT_BYTES as i64),
+            ));
+        }
+        let bytes = fs::read(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let hash = content_hash(&bytes);
+        let target = stored_path(&hash);
+        if !target.exists() {
+            if let Some(dir) = target.parent() {
+                fs::create_dir_all(dir).map_err(|e| format!("Attachment store error: {}", e))?;
+            }
+            fs::write(&target, &bytes).map_err(|e| format!("Attachment store error: {}", e))?;
+        }
+        stored.push(StoredFile {
+            file_name: path.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| hash.clone()),
+            content_hash: hash,
+            mime_type: mime_type_for(path),
+            size_bytes: bytes.len() as i64,
+        });
+    }
+    Ok(stored)
+}
+
+/// Stores `files`, runs `save` and attaches the files to the transaction it returns.
+/// Files stored for a save that failed are removed again.
+pub fn save_with_files(
+    pool: &DbPool,
+    uid: i32,
+    files: &[PathBuf],
+    save: impl FnOnce() -> Result<Transaction, String>,
+) -> Result<Transaction, String> {
+    let stored = store_files(files)?;
+    let result = save().and_then(|tx| link_files(pool, uid, tx.tran_id, &stored).map(|_| tx));
+    if result.is_err() {
+        let hashes: Vec<String> = stored.into_iter().map(|f| f.content_hash).collect();
+        // The failed save is what the user needs to hear about; a leftover file only takes space.
+        let _ = remove_unused_files(pool, &hashes);
+    }
+    result
+}
+
+/// Links stored files to one of the user's transactions and returns its attachments.
+pub fn link_files(pool: &DbPool, uid: i32, tx_id: i32, files: &[StoredFile]) -> Result<Vec<Attachment>, String> {
+    if !files.is_empty() {
+        pool.find_transaction(uid, tx_id).map_err(|e| match e {
+            DieselError::NotFound => "Transaction not found".to_string(),
+            e => format!("Query error: {:?}", e),
+        })?;
+        let now = chrono::Local::now().naive_local();
+        let new_attachments: Vec<NewAttachment> = files
+            .iter()
+            .map(|f| NewAttachment {
+                transaction_id: tx_id,
+                file_name: &f.file_name,
+                content_hash: &f.content_hash,
+                mime_type: f.mime_type,
+                size_bytes: f.size_bytes,
+                created_at: now,
+            })
+            .collect();
+        audited(pool, |repo| {
+            repo.insert_attachments(&new_attachments)?;
+            for file in files {
+                record(repo, NewAuditEntry::new(uid, ACTION_INSERT, ENTITY_ATTACHMENT, Some(tx_id))
+                    .after(format!("'{}' ({})", file.file_name, format_size(file.size_bytes))))?;
+            }
+            Ok(())
+        })
+        .map_err(|e| format!("Attachment error: {:?}", e))?;
+    }
+    load_attachments(pool, uid, tx_id)
+}
+
+pub fn load_attachments(pool: &DbPool, uid: i32, tx_id: i32) -> Result<Vec<Attachment>, String> {
+    pool.load_attachments(uid, tx_id)
+        .map_err(|e| format!("Query error: {:?}", e))
+}
+
+/// Removes one attachment and returns the remaining ones of its transaction.
+pub fn remove_attachment(pool: &DbPool, uid: i32, attachment_id: i32) -> Result<Vec<Attachment>, String> {
+    let attachment = pool.find_attachment(uid, attachment_id).map_err(|e| match e {
+        DieselError::NotFound => "Attachment not found".to_string(),
+        e => format!("Query error: {:?}", e),
+    })?;
+    audited(pool, |repo| {
+        repo.delete_attachment(uid, attachment_id)?;
+        record(repo, NewAuditEntry::new(uid, ACTION_DELETE, ENTITY_ATTACHMENT, Some(attachment.transaction_id))
+            .before(format!("'{}' ({})", attachment.file_name, format_size(attachment.size_bytes))))
+    })
+    .map_err(|e| format!("Attachment error: {:?}", e))?;
+    remove_unused_files(pool, &[attachment.content_hash])?;
+    load_attachments(pool, uid, attachment.transaction_id)
+}
+
+/// Deletes stored files that no attachment refers to any more. Files are shared
+/// between identical attachments, so a file only goes once its last row is gone.
+/// Runs after the rows are deleted, so the error names the files left orphaned in the store.
+pub fn remove_unused_files(pool: &DbPool, hashes: &[String]) -> Result<(), String> {
+    let mut failed = Vec::new();
+    for hash in hashes {
+        let removed = match pool.count_attachments_with_hash(hash) {
+            Ok(0) => match fs::remove_file(stored_path(hash)) {
+                Err(e) if e.kind() != ErrorKind::NotFound => Err(e.to_string()),
+                _ => Ok(()),
+            },
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("{:?}", e)),
+        };
+        if let Err(e) = removed {
+            failed.push(format!("{}: {}", hash, e));
+        }
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Attachment files could not be removed ({})", failed.join("; ")))
+    }
+}
@@ -0,0 +1,27 @@
+use diesel::QueryResult;
+use crate::model::{AuditEntry, AuditFilter, DbPool, NewAuditEntry, Repository};
+
+/// Runs `change` in one database transaction. Changes write their audit entries with
+/// `record` on the repository they are given, so an entry is stored exactly when its
+/// change is, and a failed audit write rolls the change back.
+pub fn audited<T>(pool: &DbPool, change: impl FnOnce(&dyn Repository) -> QueryResult<T>) -> QueryResult<T> {
+    let mut change = Some(change);
+    let mut result = None;
+    pool.transaction(&mut |repo| {
+        if let Some(change) = change.take() {
+            result = Some(change(repo)?);
+        }
+        Ok(())
+    })?;
+    Ok(result.expect("the transaction ran its work"))
+}
+
+/// Appends an entry to the audit log; call it inside `audited`.
+pub fn record(repo: &dyn Repository, entry: NewAuditEntry) -> QueryResult<()> {
+    repo.insert_audit_entry(&entry)
+}
+
+pub fn load_audit_log(pool: &DbPool, uid: i32, filter: &AuditFilter) -> Result<Vec<AuditEntry>, String> {
+    pool.load_audit_log(uid, filter)
+        .map_err(|e| format!("Query error: {:?}", e))
+}
@@ -0,0 +1,56 @@
+use crate::controller::audit_controller::{audited, record};
+use crate::model::{Budget, DbPool, Money, NewAuditEntry, NewBudget};
+use crate::model::audit::{describe_budget, ACTION_DELETE, ACTION_INSERT, ACTION_UPDATE, ENTITY_BUDGET};
+use crate::model::budget::month_start;
+use crate::model::category::KIND_EXPENSE;
+
+pub fn load_budgets(pool: &DbPool, uid: i32) -> Result<Vec<Budget>, String> {
+    pool.load_budgets(uid)
+        .map_err(|e| format!("Query error: {:?}", e))
+}
+
+/// Sets the monthly limit of an expense category and returns all of the user's budgets.
+pub fn save_budget(pool: &DbPool, uid: i32, tag_id: i32, limit: Money, rollover: bool) -> Result<Vec<Budget>, String> {
+    if limit <= Money::ZERO {
+        return Err("The monthly limit must be greater than zero".into());
+    }
+    let categories = pool.load_categories(uid).map_err(|e| format!("Query error: {:?}", e))?;
+    if !categories.iter().any(|c| c.id == tag_id && c.kind == KIND_EXPENSE) {
+        return Err("Budgets can only be set on expense categories".into());
+    }
+    let before = load_budgets(pool, uid)?.into_iter().find(|b| b.tag_id == tag_id);
+    audited(pool, |repo| {
+        repo.save_budget(&NewBudget {
+            user_id: uid,
+            tag_id,
+            monthly_limit: limit,
+            rollover,
+            start_month: month_start(chrono::Local::now().date_naive()),
+        })?;
+        let budgets = repo.load_budgets(uid)?;
+        if let Some(after) = budgets.iter().find(|b| b.tag_id == tag_id) {
+            let entry = match &before {
+                Some(before) => NewAuditEntry::new(uid, ACTION_UPDATE, ENTITY_BUDGET, Some(after.id))
+                    .before(describe_budget(before)),
+                None => NewAuditEntry::new(uid, ACTION_INSERT, ENTITY_BUDGET, Some(after.id)),
+            };
+            record(repo, entry.after(describe_budget(after)))?;
+        }
+        Ok(budgets)
+    })
+    .map_err(|e| format!("Budget error: {:?}", e))
+}
+
+pub fn remove_budget(pool: &DbPool, uid: i32, tag_id: i32) -> Result<Vec<Budget>, String> {
+    let budget = load_budgets(pool, uid)?
+        .into_iter()
+        .find(|b| b.tag_id == tag_id)
+        .ok_or_else(|| "The category has no budget".to_string())?;
+    audited(pool, |repo| {
+        repo.delete_budget(uid, tag_id)?;
+        record(repo, NewAuditEntry::new(uid, ACTION_DELETE, ENTITY_BUDGET, Some(budget.id))
+            .before(describe_budget(&budget)))
+    })
+    .map_err(|e| format!("Budget error: {:?}", e))?;
+    load_budgets(pool, uid)
+}
@@ -1,14 +1,210 @@
-use diesel::prelude::*;
-use crate::model::Category;
-use crate::schema::expense_tags::dsl::*;
-use diesel::r2d2::{Pool, ConnectionManager};
-use diesel::PgConnection;
+use diesel::result::Error as DieselError;
+use crate::controller::audit_controller::{audited, record};
+use diesel::QueryResult;
+use crate::model::{Budget, Category, DbPool, Envelope, NewAuditEntry, NewCategory, Repository, Transaction};
+use crate::model::audit::{describe_budget, describe_category, describe_envelope, ACTION_DELETE, ACTION_INSERT, ACTION_UPDATE, ENTITY_BUDGET, ENTITY_CATEGORY, ENTITY_ENVELOPE};
+use crate::model::category::{parse_hex_color, subtree, KIND_EXPENSE, KIND_INCOME};
 
+/// Loads the user's categories, giving users without any a copy of the defaults.
 pub fn load_categories(
-    pool: &Pool<ConnectionManager<PgConnection>>
+    pool: &DbPool,
+    uid: i32,
 ) -> Result<Vec<Category>, String> {
-    let mut conn = pool.get().map_err(|e| format!("Pool error: {:?}", e))?;
-    expense_tags
-        .load::<Category>(&mut conn)
-        .map_err(|e| format!("Query error: {:?}", e))
-}
\ No newline at end of file
+    let categories = pool.load_categories(uid)
+        .map_err(|e| format!("Query error: {:?}", e))?;
+    if !categories.is_empty() {
+        return Ok(categories);
+    }
+
+    let defaults = pool.load_default_categories()
+        .map_err(|e| format!("Query error: {:?}", e))?;
+    let copies: Vec<NewCategory> = defaults
+        .iter()
+        .map(|c| NewCategory { name: &c.name, user_id: Some(uid), color: &c.color, parent_id: None, kind: &c.kind })
+        .collect();
+    audited(pool, |repo| {
+        repo.insert_categories(&copies)?;
+        let categories = repo.load_categories(uid)?;
+        for category in &categories {
+            record(repo, NewAuditEntry::new(uid, ACTION_INSERT, ENTITY_CATEGORY, Some(category.id))
+                .after(describe_category(category)))?;
+        }
+        Ok(categories)
+    })
+    .map_err(|e| format!("Category error: {:?}", e))
+}
+
+fn validate(
+    pool: &DbPool,
+    uid: i32,
+    id: Option<i32>,
+    name: &str,
+    color: &str,
+    parent: Option<i32>,
+    kind: &str,
+) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Category name is empty".into());
+    }
+    if parse_hex_color(color).is_none() {
+        return Err(format!("'{}' is not a colour like #ff9933", color));
+    }
+    let existing = pool.load_categories(uid)
+        .map_err(|e| format!("Query error: {:?}", e))?;
+    if existing.iter().any(|c| Some(c.id) != id && c.name.eq_ignore_ascii_case(name)) {
+        return Err(format!("Category '{}' already exists", name));
+    }
+    if let Some(parent) = parent {
+        match existing.iter().find(|c| c.id == parent) {
+            None => return Err("Parent category not found".into()),
+            Some(p) if p.kind != kind => {
+                return Err("Income and expense categories cannot be nested in each other".into())
+            }
+            Some(_) => {}
+        }
+        if let Some(id) = id {
+            if subtree(&existing, id).contains(&parent) {
+                return Err("A category cannot be placed under itself or its subcategories".into());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn find_category(pool: &DbPool, uid: i32, id: i32) -> Result<Category, String> {
+    pool.load_categories(uid)
+        .map_err(|e| format!("Query error: {:?}", e))?
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| "Category not found".to_string())
+}
+
+fn ensure_same_kind(pool: &DbPool, uid: i32, a: i32, b: i32) -> Result<(), String> {
+    if find_category(pool, uid, a)?.kind != find_category(pool, uid, b)?.kind {
+        return Err("Transactions cannot move between income and expense categories".into());
+    }
+    Ok(())
+}
+
+pub fn create_category(
+    pool: &DbPool,
+    uid: i32,
+    name: &str,
+    color: &str,
+    parent: Option<i32>,
+    kind: &str,
+) -> Result<(), String> {
+    let (name, color) = (name.trim(), color.trim());
+    if kind != KIND_EXPENSE && kind != KIND_INCOME {
+        return Err(format!("Unknown category kind '{}'", kind));
+    }
+    validate(pool, uid, None, name, color, parent, kind)?;
+    audited(pool, |repo| {
+        repo.insert_categories(&[NewCategory { name, user_id: Some(uid), color, parent_id: parent, kind }])?;
+        if let Some(created) = repo.load_categories(uid)?.iter().find(|c| c.name == name) {
+            record(repo, NewAuditEntry::new(uid, ACTION_INSERT, ENTITY_CATEGORY, Some(created.id))
+                .after(describe_category(created)))?;
+        }
+        Ok(())
+    })
+    .map_err(|e| format!("Category error: {:?}", e))
+}
+
+pub fn update_category(pool: &DbPool, uid: i32, id: i32, name: &str, color: &str, parent: Option<i32>) -> Result<(), String> {
+    let (name, color) = (name.trim(), color.trim());
+    let before = find_category(pool, uid, id)?;
+    validate(pool, uid, Some(id), name, color, parent, &before.kind)?;
+    audited(pool, |repo| {
+        if repo.update_category(uid, id, name, color, parent)? == 0 {
+            return Err(DieselError::NotFound);
+        }
+        let mut entry = NewAuditEntry::new(uid, ACTION_UPDATE, ENTITY_CATEGORY, Some(id))
+            .before(describe_category(&before));
+        if let Some(after) = repo.load_categories(uid)?.iter().find(|c| c.id == id) {
+            entry = entry.after(describe_category(after));
+        }
+        record(repo, entry)
+    })
+    .map_err(|e| match e {
+        DieselError::NotFound => "Category not found".to_string(),
+        e => format!("Category error: {:?}", e),
+    })
+}
+
+/// Moves every transaction of `from` into `into` and removes `from`.
+pub fn merge_categories(pool: &DbPool, uid: i32, from: i32, into: i32) -> Result<(), String> {
+    if from == into {
+        return Err("Cannot merge a category into itself".into());
+    }
+    ensure_same_kind(pool, uid, from, into)?;
+    remove_category(pool, uid, from, Some(into))
+}
+
+/// Deletes a category. Its transactions move to `reassign_to`, or become
+/// uncategorized when `None` is given.
+pub fn delete_category(pool: &DbPool, uid: i32, id: i32, reassign_to: Option<i32>) -> Result<(), String> {
+    if reassign_to == Some(id) {
+        return Err("Choose a different category for the transactions".into());
+    }
+    if let Some(target) = reassign_to {
+        ensure_same_kind(pool, uid, id, target)?;
+    }
+    remove_category(pool, uid, id, reassign_to)
+}
+
+fn remove_category(pool: &DbPool, uid: i32, id: i32, reassign_to: Option<i32>) -> Result<(), String> {
+    let before = find_category(pool, uid, id)?;
+    let moved = match reassign_to {
+        Some(target) => format!("transactions moved to category #{}", target),
+        None => "transactions left uncategorized".to_string(),
+    };
+    audited(pool, |repo| {
+        let budgets = repo.load_budgets(uid)?;
+        let envelopes = repo.load_envelopes(uid)?;
+        repo.reassign_and_delete_category(uid, id, reassign_to)?;
+        record(repo, NewAuditEntry::new(uid, ACTION_DELETE, ENTITY_CATEGORY, Some(id))
+            .before(describe_category(&before))
+            .after(moved))?;
+        audit_budget_changes(repo, uid, &budgets)?;
+        audit_envelope_changes(repo, uid, &envelopes)
+    })
+    .map_err(|e| format!("Category error: {:?}", e))
+}
+
+/// Records the budgets a category removal moved, merged or deleted.
+fn audit_budget_changes(repo: &dyn Repository, uid: i32, before: &[Budget]) -> QueryResult<()> {
+    let after = repo.load_budgets(uid)?;
+    for old in before {
+        let entry = match after.iter().find(|b| b.id == old.id) {
+            None => NewAuditEntry::new(uid, ACTION_DELETE, ENTITY_BUDGET, Some(old.id))
+                .before(describe_budget(old)),
+            Some(new) if describe_budget(new) != describe_budget(old) =>
+                NewAuditEntry::new(uid, ACTION_UPDATE, ENTITY_BUDGET, Some(old.id))
+                    .before(describe_budget(old))
+                    .after(describe_budget(new)),
+            Some(_) => continue,
+        };
+        record(repo, entry)?;
+    }
+    Ok(())
+}
+
+/// Records the envelopes a category removal relinked or unlinked.
+fn audit_envelope_changes(repo: &dyn Repository, uid: i32, before: &[Envelope]) -> QueryResult<()> {
+    for new in repo.load_envelopes(uid)? {
+        if let Some(old) = before.iter().find(|e| e.id == new.id && e.tag_id != new.tag_id) {
+            record(repo, NewAuditEntry::new(uid, ACTION_UPDATE, ENTITY_ENVELOPE, Some(new.id))
+                .before(describe_envelope(old))
+                .after(describe_envelope(&new)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Categories and transactions after a category change; merges and deletes move transactions.
+pub fn reload_categories(pool: &DbPool, uid: i32) -> Result<(Vec<Category>, Vec<Transaction>), String> {
+    let categories = load_categories(pool, uid)?;
+    let transactions = pool.load_transactions(uid)
+        .map_err(|e| format!("Query error: {:?}", e))?;
+    Ok((categories, transactions))
+}
@@ -0,0 +1,40 @@
+use std::fs::File;
+use crate::controller::audit_controller::{audited, record};
+use crate::model::{DbPool, ExchangeRate, NewAuditEntry};
+use crate::model::audit::{ACTION_INSERT, ACTION_UPDATE, ENTITY_EXCHANGE_RATE, ENTITY_USER};
+use crate::model::currency::{is_currency_code, parse_rates_csv};
+
+pub fn set_base_currency(pool: &DbPool, uid: i32, code: &str) -> Result<String, String> {
+    if !is_currency_code(code) {
+        return Err(format!("'{}' is not a currency code", code));
+    }
+    let before = pool.find_user_by_id(uid).map(|u| u.base_currency).ok();
+    audited(pool, |repo| {
+        repo.set_base_currency(uid, code)?;
+        let mut entry = NewAuditEntry::new(uid, ACTION_UPDATE, ENTITY_USER, Some(uid))
+            .after(format!("base currency {}", code));
+        if let Some(before) = before {
+            entry = entry.before(format!("base currency {}", before));
+        }
+        record(repo, entry)
+    })
+    .map_err(|e| format!("Update error: {:?}", e))?;
+    Ok(code.to_string())
+}
+
+/// Imports a `date,currency,quote_currency,rate` CSV file into the local rate table
+/// and returns the number of rates read together with the refreshed table.
+pub fn import_exchange_rates(pool: &DbPool, uid: i32, path: &str) -> Result<(usize, Vec<ExchangeRate>), String> {
+    let file = File::open(path.trim()).map_err(|e| format!("Cannot open {}: {}", path.trim(), e))?;
+    let rates = parse_rates_csv(file)?;
+    let count = audited(pool, |repo| {
+        let count = repo.save_exchange_rates(&rates)?;
+        record(repo, NewAuditEntry::new(uid, ACTION_INSERT, ENTITY_EXCHANGE_RATE, None)
+            .after(format!("{} rates imported from {}", count, path.trim())))?;
+        Ok(count)
+    })
+    .map_err(|e| format!("Import error: {:?}", e))?;
+    let all = pool.load_exchange_rates()
+        .map_err(|e| format!("Query error: {:?}", e))?;
+    Ok((count, all))
+}
@@ -0,0 +1,121 @@
+use chrono::NaiveDate;
+use crate::controller::audit_controller::{audited, record};
+use crate::model::{DbPool, EnvelopeBook, Money, NewAuditEntry, NewEnvelope, NewEnvelopeMove};
+use crate::model::audit::{describe_envelope, describe_envelope_move, ACTION_DELETE, ACTION_INSERT, ACTION_UPDATE, ENTITY_ENVELOPE, ENTITY_USER};
+use crate::model::budget::month_start;
+use crate::model::category::{subtree, KIND_EXPENSE};
+
+pub fn load_envelope_book(pool: &DbPool, uid: i32) -> Result<EnvelopeBook, String> {
+    let user = pool.find_user_by_id(uid).map_err(|e| format!("Query error: {:?}", e))?;
+    let envelopes = pool.load_envelopes(uid).map_err(|e| format!("Query error: {:?}", e))?;
+    let moves = pool.load_envelope_moves(uid).map_err(|e| format!("Query error: {:?}", e))?;
+    Ok(EnvelopeBook {
+        enabled: user.envelope_mode,
+        start: user.envelope_start,
+        envelopes,
+        moves,
+    })
+}
+
+/// Turns envelope budgeting on or off. The first time it is turned on, income from
+/// the start of the current month becomes available to assign.
+pub fn set_envelope_mode(pool: &DbPool, uid: i32, enabled: bool, today: NaiveDate) -> Result<EnvelopeBook, String> {
+    let (before, after) = if enabled { ("off", "on") } else { ("on", "off") };
+    audited(pool, |repo| {
+        repo.set_envelope_mode(uid, enabled, month_start(today))?;
+        record(repo, NewAuditEntry::new(uid, ACTION_UPDATE, ENTITY_USER, Some(uid))
+            .before(format!("envelope budgeting {}", before))
+            .after(format!("envelope budgeting {}", after)))
+    })
+    .map_err(|e| format!("Update error: {:?}", e))?;
+    load_envelope_book(pool, uid)
+}
+
+/// Creates an envelope, optionally drawn down by an expense category. A category can
+/// feed only one envelope, counting its subcategories, so no expense is taken twice.
+pub fn create_envelope(pool: &DbPool, uid: i32, name: &str, tag_id: Option<i32>) -> Result<EnvelopeBook, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Enter a name for the envelope".into());
+    }
+    let book = load_envelope_book(pool, uid)?;
+    if book.envelopes.iter().any(|e| e.name.eq_ignore_ascii_case(name)) {
+        return Err(format!("An envelope named '{}' already exists", name));
+    }
+    if let Some(tag) = tag_id {
+        let categories = pool.load_categories(uid).map_err(|e| format!("Query error: {:?}", e))?;
+        if !categories.iter().any(|c| c.id == tag && c.kind == KIND_EXPENSE) {
+            return Err("Envelopes can only be linked to expense categories".into());
+        }
+        let covered = subtree(&categories, tag);
+        let overlaps = book.envelopes.iter().filter_map(|e| e.tag_id).any(|other| {
+            covered.contains(&other) || subtree(&categories, other).contains(&tag)
+        });
+        if overlaps {
+            return Err("Another envelope already covers this category".into());
+        }
+    }
+    audited(pool, |repo| {
+        let created = repo.insert_envelope(&NewEnvelope { user_id: uid, name: name.to_string(), tag_id })?;
+        record(repo, NewAuditEntry::new(uid, ACTION_INSERT, ENTITY_ENVELOPE, Some(created.id))
+            .after(describe_envelope(&created)))
+    })
+    .map_err(|e| format!("Envelope error: {:?}", e))?;
+    load_envelope_book(pool, uid)
+}
+
+/// Deletes an envelope that never held money. Envelopes with moves stay, so that the
+/// money spent from them is still accounted for.
+pub fn delete_envelope(pool: &DbPool, uid: i32, envelope_id: i32) -> Result<EnvelopeBook, String> {
+    let book = load_envelope_book(pool, uid)?;
+    let envelope = book.envelopes
+        .iter()
+        .find(|e| e.id == envelope_id)
+        .ok_or_else(|| "Envelope not found".to_string())?;
+    let used = book.moves
+        .iter()
+        .any(|m| m.from_envelope == Some(envelope_id) || m.to_envelope == Some(envelope_id));
+    if used {
+        return Err(format!("'{}' already held money and can't be deleted", envelope.name));
+    }
+    audited(pool, |repo| {
+        repo.delete_envelope(uid, envelope_id)?;
+        record(repo, NewAuditEntry::new(uid, ACTION_DELETE, ENTITY_ENVELOPE, Some(envelope_id))
+            .before(describe_envelope(envelope)))
+    })
+    .map_err(|e| format!("Envelope error: {:?}", e))?;
+    load_envelope_book(pool, uid)
+}
+
+/// Moves money between two envelopes, or between an envelope and the income still
+/// available to assign (`None`). The caller checks that the source holds enough.
+pub fn move_money(pool: &DbPool, uid: i32, from: Option<i32>, to: Option<i32>, amount: Money) -> Result<EnvelopeBook, String> {
+    if from == to {
+        return Err("Choose two different places to move money between".into());
+    }
+    if amount <= Money::ZERO {
+        return Err("The amount must be greater than zero".into());
+    }
+    let book = load_envelope_book(pool, uid)?;
+    if !book.enabled {
+        return Err("Turn on envelope budgeting first".into());
+    }
+    let known = |side: Option<i32>| side.is_none_or(|id| book.envelopes.iter().any(|e| e.id == id));
+    if !known(from) || !known(to) {
+        return Err("Envelope not found".into());
+    }
+    let envelope_move = NewEnvelopeMove {
+        user_id: uid,
+        from_envelope: from,
+        to_envelope: to,
+        amount,
+        moved_at: chrono::Local::now().naive_local(),
+    };
+    audited(pool, |repo| {
+        repo.insert_envelope_move(&envelope_move)?;
+        record(repo, NewAuditEntry::new(uid, ACTION_UPDATE, ENTITY_ENVELOPE, to.or(from))
+            .after(describe_envelope_move(&envelope_move)))
+    })
+    .map_err(|e| format!("Envelope error: {:?}", e))?;
+    load_envelope_book(pool, uid)
+}
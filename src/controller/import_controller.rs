@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use crate::controller::audit_controller::{audited, record};
+use crate::controller::category_controller::create_category;
+use crate::model::{Category, DbPool, NewAuditEntry, NewTransaction, SplitLine};
+use crate::model::audit::{describe_transaction, ACTION_INSERT, ENTITY_TRANSACTION};
+use crate::model::account::check_account;
+use crate::model::category::DEFAULT_CATEGORY_COLOR;
+use crate::model::currency::is_currency_code;
+use crate::model::import::{category_path_names, skip_duplicates, ImportSummary, ImportedRow};
+use crate::model::qif::export_qif;
+
+/// Finds the category for every `Parent:Child` path used by the rows and their split
+/// lines, creating the ones that don't exist yet. Names are matched without regard to
+/// case, as category names are unique per user, and must be of the row's kind: income
+/// rows go under income categories and expenses under expense categories. Rows whose
+/// paths name a category of the other kind, or one under a different parent, get no
+/// entries and are imported uncategorized.
+fn resolve_categories(pool: &DbPool, uid: i32, rows: &[&ImportedRow]) -> Result<HashMap<(String, &'static str), i32>, String> {
+    let mut resolved = HashMap::new();
+    let mut categories = pool.load_categories(uid).map_err(|e| format!("Query error: {:?}", e))?;
+    for row in rows {
+        if row.category_conflict(&categories).is_some() {
+            continue;
+        }
+        let kind = row.category_kind();
+        for path in row.category_paths() {
+            let key = (path.to_string(), kind);
+            if resolved.contains_key(&key) {
+                continue;
+            }
+            let mut parent: Option<i32> = None;
+            for name in category_path_names(path) {
+                let in_place = |c: &&Category| c.name.eq_ignore_ascii_case(name) && c.parent_id == parent;
+                if !categories.iter().any(|c| in_place(&c)) {
+                    create_category(pool, uid, name, DEFAULT_CATEGORY_COLOR, parent, kind)?;
+                    categories = pool.load_categories(uid).map_err(|e| format!("Query error: {:?}", e))?;
+                }
+                let category = categories
+                    .iter()
+                    .find(in_place)
+                    .ok_or_else(|| format!("Category '{}' could not be created", name))?;
+                parent = Some(category.id);
+            }
+            if let Some(id) = parent {
+                resolved.insert(key, id);
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// Books the rows as incomes and expenses, each with its audit entry, in one database
+/// transaction. Rows whose external id was imported before, or appears earlier in `rows`,
+/// are skipped, so an overlapping statement can be imported again safely. Nothing is stored
+/// if any row fails, though categories created for the rows are kept.
+pub fn import_transactions(
+    pool: &DbPool,
+    uid: i32,
+    rows: &[ImportedRow],
+    currency: &str,
+    account_id: Option<i32>,
+) -> Result<ImportSummary, String> {
+    if rows.is_empty() {
+        return Err("There is nothing to import".into());
+    }
+    if let Some(code) = rows.iter().map(|r| r.currency.as_deref().unwrap_or(currency)).find(|c| !is_currency_code(c)) {
+        return Err(format!("Unknown currency '{}'", code));
+    }
+    if account_id.is_some() {
+        let accounts = pool.load_accounts(uid).map_err(|e| format!("Query error: {:?}", e))?;
+        check_account(&accounts, account_id)?;
+    }
+    let mut seen: HashSet<String> = pool.load_external_ids(uid)
+        .map_err(|e| format!("Query error: {:?}", e))?
+        .into_iter()
+        .collect();
+    let (kept, duplicates) = skip_duplicates(rows, &mut seen);
+    let categories = resolve_categories(pool, uid, &kept)?;
+    let new_txs: Vec<(NewTransaction, Vec<SplitLine>)> = kept
+        .iter()
+        .map(|row| {
+            let category = |path: &str| categories.get(&(path.to_string(), row.category_kind())).copied();
+            let mut new_tx = row.to_new(uid, currency, account_id);
+            new_tx.tag_id = row.category.as_deref().and_then(category);
+            // Split lines whose categories were left out make the whole row uncategorized.
+            let splits: Option<Vec<SplitLine>> = row.splits
+                .iter()
+                .map(|s| category(&s.category).map(|tag| SplitLine { tag_id: Some(tag), amount: s.amount.abs() }))
+                .collect();
+            (new_tx, splits.unwrap_or_default())
+        })
+        .collect();
+    let imported = audited(pool, |repo| {
+        for (new_tx, splits) in &new_txs {
+            let tx = repo.insert_transaction(new_tx, splits)?;
+            record(repo, NewAuditEntry::new(uid, ACTION_INSERT, ENTITY_TRANSACTION, Some(tx.tran_id))
+                .after(describe_transaction(&tx)))?;
+        }
+        Ok(new_txs.len())
+    })
+    .map_err(|e| format!("Import error: {:?}", e))?;
+    Ok(ImportSummary { imported, duplicates })
+}
+
+/// Writes the user's transactions, trash excluded, to `path` as QIF and returns how many
+/// were written.
+pub fn export_qif_file(pool: &DbPool, uid: i32, path: &Path) -> Result<usize, String> {
+    let transactions = pool.load_transactions(uid).map_err(|e| format!("Query error: {:?}", e))?;
+    let categories = pool.load_categories(uid).map_err(|e| format!("Query error: {:?}", e))?;
+    let accounts = pool.load_accounts(uid).map_err(|e| format!("Query error: {:?}", e))?;
+    fs::write(path, export_qif(&transactions, &categories, &accounts))
+        .map_err(|e| format!("Export error: {}", e))?;
+    Ok(transactions.len())
+}
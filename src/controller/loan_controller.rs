@@ -0,0 +1,129 @@
+use chrono::{NaiveDate, NaiveTime};
+use crate::controller::audit_controller::{audited, record};
+use crate::controller::transaction_controller::insert_recorded;
+use crate::model::{DbPool, LoanBook, Money, NewAuditEntry, NewLoan, NewLoanPayment, TransactionDraft};
+use crate::model::audit::{describe_loan, ACTION_DELETE, ACTION_INSERT, ACTION_UPDATE, ENTITY_LOAN};
+use crate::model::account::check_account;
+use crate::model::category::{KIND_EXPENSE, KIND_INCOME};
+use crate::model::currency::is_currency_code;
+use crate::model::loan::{DIRECTIONS, DIRECTION_LENT};
+
+const MAX_TERM_MONTHS: i32 = 600;
+
+pub fn load_loan_book(pool: &DbPool, uid: i32) -> Result<LoanBook, String> {
+    let loans = pool.load_loans(uid).map_err(|e| format!("Query error: {:?}", e))?;
+    let payments = pool.load_loan_payments(uid).map_err(|e| format!("Query error: {:?}", e))?;
+    Ok(LoanBook { loans, payments })
+}
+
+pub fn create_loan(pool: &DbPool, uid: i32, loan: &NewLoan) -> Result<LoanBook, String> {
+    if loan.user_id != uid {
+        return Err("Loan belongs to another user".into());
+    }
+    if loan.name.trim().is_empty() {
+        return Err("Enter a name for the loan".into());
+    }
+    if !DIRECTIONS.contains(&loan.direction.as_str()) {
+        return Err(format!("Unknown loan direction '{}'", loan.direction));
+    }
+    if loan.principal <= Money::ZERO {
+        return Err("The principal must be greater than zero".into());
+    }
+    if !is_currency_code(&loan.currency) {
+        return Err(format!("Unknown currency '{}'", loan.currency));
+    }
+    if !(0..=10_000).contains(&loan.annual_rate_bp) {
+        return Err("The interest rate must be between 0 and 100 %".into());
+    }
+    if !(1..=MAX_TERM_MONTHS).contains(&loan.term_months) {
+        return Err(format!("The term must be between 1 and {} months", MAX_TERM_MONTHS));
+    }
+    if loan.account_id.is_some() {
+        let accounts = pool.load_accounts(uid).map_err(|e| format!("Query error: {:?}", e))?;
+        check_account(&accounts, loan.account_id)?;
+    }
+    if let Some(tag) = loan.interest_tag_id {
+        // Interest paid is an expense; interest received on money lent is income.
+        let kind = if loan.direction == DIRECTION_LENT { KIND_INCOME } else { KIND_EXPENSE };
+        let categories = pool.load_categories(uid).map_err(|e| format!("Query error: {:?}", e))?;
+        if !categories.iter().any(|c| c.id == tag && c.kind == kind) {
+            return Err(format!("Choose an {} category for the interest", kind));
+        }
+    }
+    audited(pool, |repo| {
+        let created = repo.insert_loan(loan)?;
+        record(repo, NewAuditEntry::new(uid, ACTION_INSERT, ENTITY_LOAN, Some(created.id))
+            .after(describe_loan(&created)))
+    })
+    .map_err(|e| format!("Loan error: {:?}", e))?;
+    load_loan_book(pool, uid)
+}
+
+/// Removes a loan. Its repayments stay as ordinary transactions, with their whole
+/// amount counting as expense or income from then on.
+pub fn delete_loan(pool: &DbPool, uid: i32, loan_id: i32) -> Result<LoanBook, String> {
+    let loan = pool.load_loans(uid)
+        .map_err(|e| format!("Query error: {:?}", e))?
+        .into_iter()
+        .find(|l| l.id == loan_id)
+        .ok_or_else(|| "Loan not found".to_string())?;
+    audited(pool, |repo| {
+        repo.delete_loan(uid, loan_id)?;
+        record(repo, NewAuditEntry::new(uid, ACTION_DELETE, ENTITY_LOAN, Some(loan_id))
+            .before(describe_loan(&loan)))
+    })
+    .map_err(|e| format!("Loan error: {:?}", e))?;
+    load_loan_book(pool, uid)
+}
+
+/// Books a repayment of `amount` on `date`: an expense for borrowed money or income for
+/// money lent. A month of interest on the outstanding balance is the interest part; the
+/// rest pays down the principal and may not exceed what is still owed.
+pub fn record_repayment(pool: &DbPool, uid: i32, loan_id: i32, date: NaiveDate, amount: Money) -> Result<LoanBook, String> {
+    if amount <= Money::ZERO {
+        return Err("The amount must be greater than zero".into());
+    }
+    let book = load_loan_book(pool, uid)?;
+    let loan = book.loans
+        .iter()
+        .find(|l| l.id == loan_id)
+        .ok_or_else(|| "Loan not found".to_string())?;
+    let transactions = pool.load_transactions(uid).map_err(|e| format!("Query error: {:?}", e))?;
+    let (outstanding, _) = book.outstanding(loan, &transactions);
+    if outstanding <= Money::ZERO {
+        return Err(format!("'{}' is already paid off", loan.name));
+    }
+    let interest = loan.interest_on(outstanding);
+    if amount > outstanding + interest {
+        return Err(format!("Only {} {} is left to pay, interest included", outstanding + interest, loan.currency));
+    }
+
+    let draft = TransactionDraft {
+        source: loan.name.clone(),
+        date: date.and_time(NaiveTime::MIN),
+        amount,
+        currency: loan.currency.clone(),
+        account_id: loan.account_id,
+        tag_id: loan.interest_tag_id,
+        comment: None,
+        splits: Vec::new(),
+    };
+    let kind = if loan.is_lent() { "Income" } else { "Expense" };
+    let interest = interest.min(amount);
+    // The payment row goes with its transaction; without it the transaction would count
+    // in full as expense or income.
+    audited(pool, |repo| {
+        let tx = insert_recorded(repo, uid, &draft.to_new(kind, uid), &draft.splits)?;
+        repo.insert_loan_payment(&NewLoanPayment { loan_id, transaction_id: tx.tran_id, interest })?;
+        record(repo, NewAuditEntry::new(uid, ACTION_UPDATE, ENTITY_LOAN, Some(loan_id))
+            .after(format!(
+                "repayment {} {}: interest {}, principal {}",
+                amount,
+                loan.currency,
+                interest,
+                amount - interest,
+            )))
+    })
+    .map_err(|e| format!("Loan error: {:?}", e))?;
+    load_loan_book(pool, uid)
+}
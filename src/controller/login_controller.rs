@@ -1,20 +1,29 @@
-use crate::model::{AuthData, Transaction};
+use crate::model::{AuthData, DashboardData, NewAuditEntry};
+use crate::model::audit::{ACTION_UPDATE, ENTITY_USER};
+use crate::model::currency::DEFAULT_CURRENCY;
 use crate::model::user::User;
 use crate::model::db::DbPool;
 use crate::controller::transaction_controller::load_transactions;
 use crate::controller::category_controller::load_categories;
+use crate::controller::account_controller::load_accounts;
+use crate::controller::budget_controller::load_budgets;
+use crate::controller::envelope_controller::load_envelope_book;
+use crate::controller::savings_controller::load_savings_book;
+use crate::controller::loan_controller::load_loan_book;
+use crate::controller::audit_controller::{audited, record};
 
 use bcrypt::{hash, verify};
-use diesel::prelude::*;
-use crate::schema::users::dsl::*;
+use diesel::result::Error as DieselError;
 
-pub fn attempt_login(pool: &DbPool, auth_data: &AuthData) -> Result<i32, String> {
-    let mut conn = pool.get().map_err(|e| format!("DB connection error: {:?}", e))?;
+fn find_user(pool: &DbPool, name: &str) -> Result<User, String> {
+    pool.find_user_by_name(name).map_err(|e| match e {
+        DieselError::NotFound => "User not found".to_string(),
+        other => format!("DB connection error: {:?}", other),
+    })
+}
 
-    let user: User = users
-        .filter(username.eq(&auth_data.username))
-        .first(&mut conn)
-        .map_err(|_| "User not found".to_string())?;
+pub fn attempt_login(pool: &DbPool, auth_data: &AuthData) -> Result<i32, String> {
+    let user = find_user(pool, &auth_data.username)?;
 
     if verify(&auth_data.password, &user.password).unwrap_or(false) {
         Ok(user.id)
@@ -23,12 +32,26 @@ pub fn attempt_login(pool: &DbPool, auth_data: &AuthData) -> Result<i32, String>
     }
 }
 
-pub async fn handle_successful_login(pool: &DbPool, user_id: i32) -> (Vec<Transaction>, Vec<String>) {
+pub async fn handle_successful_login(pool: &DbPool, user_id: i32) -> DashboardData {
     let txs = load_transactions(pool, user_id).unwrap_or_default();
-    let cats = load_categories(pool)
-        .map(|items| items.into_iter().map(|c| c.name).collect())
-        .unwrap_or_default();
-    (txs, cats)
+    let cats = load_categories(pool, user_id).unwrap_or_default();
+    let base_currency = pool
+        .find_user_by_id(user_id)
+        .map(|u| u.base_currency)
+        .unwrap_or_else(|_| DEFAULT_CURRENCY.to_string());
+    let exchange_rates = pool.load_exchange_rates().unwrap_or_default();
+    let accounts = load_accounts(pool, user_id).unwrap_or_default();
+    DashboardData {
+        transactions: txs,
+        accounts,
+        categories: cats,
+        base_currency,
+        exchange_rates,
+        budgets: load_budgets(pool, user_id).unwrap_or_default(),
+        envelope_book: load_envelope_book(pool, user_id).unwrap_or_default(),
+        savings_book: load_savings_book(pool, user_id).unwrap_or_default(),
+        loan_book: load_loan_book(pool, user_id).unwrap_or_default(),
+    }
 }
 
 pub async fn attempt_password_reset(
@@ -37,11 +60,7 @@ pub async fn attempt_password_reset(
     secret_str: &str,
     new_password: &str,
 ) -> Result<(), String> {
-    let mut conn = pool.get().map_err(|e| format!("DB error: {:?}", e))?;
-    let user: User = users
-        .filter(username.eq(username_str))
-        .first(&mut conn)
-        .map_err(|_| "User not found".to_string())?;
+    let user = find_user(pool, username_str)?;
 
     if user.secret_pass != secret_str {
         return Err("Wrong answer to secret question".into());
@@ -49,10 +68,13 @@ pub async fn attempt_password_reset(
     let hashed = hash(new_password, bcrypt::DEFAULT_COST)
         .map_err(|e| format!("Hash error: {:?}", e))?;
 
-    diesel::update(users.filter(id.eq(user.id)))
-        .set(password.eq(hashed))
-        .execute(&mut conn)
-        .map_err(|e| format!("Update error: {:?}", e))?;
+    audited(pool, |repo| {
+        repo.update_password(user.id, &hashed)?;
+        // Only the fact of the change is logged, never password hashes.
+        record(repo, NewAuditEntry::new(user.id, ACTION_UPDATE, ENTITY_USER, Some(user.id))
+            .after("password reset"))
+    })
+    .map_err(|e| format!("Update error: {:?}", e))?;
 
     Ok(())
-}
\ No newline at end of file
+}
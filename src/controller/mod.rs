@@ -2,4 +2,14 @@ pub mod login_controller;
 pub mod registration_controller;
 pub mod transaction_controller;
 pub mod category_controller;
-pub mod app_controller;
\ No newline at end of file
+pub mod account_controller;
+pub mod currency_controller;
+pub mod audit_controller;
+pub mod attachment_controller;
+pub mod recurring_controller;
+pub mod budget_controller;
+pub mod envelope_controller;
+pub mod savings_controller;
+pub mod loan_controller;
+pub mod import_controller;
+pub mod app_controller;
@@ -0,0 +1,99 @@
+use chrono::NaiveDate;
+use crate::controller::audit_controller::{audited, record};
+use crate::controller::transaction_controller::insert_recorded;
+use crate::model::{DbPool, NewAuditEntry, NewRecurringRule, RecurringRule};
+use crate::model::audit::{describe_recurring_rule, ACTION_DELETE, ACTION_INSERT, ACTION_UPDATE, ENTITY_RECURRING_RULE};
+use crate::model::account::check_account;
+use crate::model::category::{KIND_EXPENSE, KIND_INCOME};
+use crate::model::recurring::{is_frequency, RULE_TYPES};
+
+pub fn load_rules(pool: &DbPool, uid: i32) -> Result<Vec<RecurringRule>, String> {
+    pool.load_recurring_rules(uid)
+        .map_err(|e| format!("Query error: {:?}", e))
+}
+
+pub fn create_rule(pool: &DbPool, uid: i32, rule: &NewRecurringRule) -> Result<RecurringRule, String> {
+    if rule.user_id != uid {
+        return Err("Rule belongs to another user".into());
+    }
+    if !RULE_TYPES.contains(&rule.tran_type.as_str()) {
+        return Err(format!("Unknown transaction type '{}'", rule.tran_type));
+    }
+    if !is_frequency(&rule.frequency) {
+        return Err(format!("Unknown frequency '{}'", rule.frequency));
+    }
+    if rule.source.trim().is_empty() {
+        return Err("Enter a source for the rule".into());
+    }
+    if rule.end_date.is_some_and(|end| end < rule.start_date) {
+        return Err("The end date is before the start date".into());
+    }
+    if rule.account_id.is_some() {
+        let accounts = pool.load_accounts(uid).map_err(|e| format!("Query error: {:?}", e))?;
+        check_account(&accounts, rule.account_id)?;
+    }
+    if let Some(tag) = rule.tag_id {
+        let kind = if rule.tran_type == "Income" { KIND_INCOME } else { KIND_EXPENSE };
+        let categories = pool.load_categories(uid).map_err(|e| format!("Query error: {:?}", e))?;
+        if !categories.iter().any(|c| c.id == tag && c.kind == kind) {
+            return Err("Category not found".into());
+        }
+    }
+    audited(pool, |repo| {
+        let created = repo.insert_recurring_rule(rule)?;
+        record(repo, NewAuditEntry::new(uid, ACTION_INSERT, ENTITY_RECURRING_RULE, Some(created.id))
+            .after(describe_recurring_rule(&created)))?;
+        Ok(created)
+    })
+    .map_err(|e| format!("Rule error: {:?}", e))
+}
+
+/// Removes a rule. Transactions it already booked stay.
+pub fn delete_rule(pool: &DbPool, uid: i32, rule_id: i32) -> Result<Vec<RecurringRule>, String> {
+    let rule = load_rules(pool, uid)?
+        .into_iter()
+        .find(|r| r.id == rule_id)
+        .ok_or_else(|| "Rule not found".to_string())?;
+    audited(pool, |repo| {
+        repo.delete_recurring_rule(uid, rule_id)?;
+        record(repo, NewAuditEntry::new(uid, ACTION_DELETE, ENTITY_RECURRING_RULE, Some(rule_id))
+            .before(describe_recurring_rule(&rule)))
+    })
+    .map_err(|e| format!("Rule error: {:?}", e))?;
+    load_rules(pool, uid)
+}
+
+/// Books every occurrence due by `today` and returns how many transactions were created.
+/// Each occurrence is marked as booked in the same database transaction that saves it,
+/// and one that another run marked first is left out, so nothing is booked twice.
+pub fn book_due_occurrences(pool: &DbPool, uid: i32, today: NaiveDate) -> Result<usize, String> {
+    let mut booked = 0;
+    for rule in load_rules(pool, uid)? {
+        let kind = if rule.tran_type == "Income" { "Income" } else { "Expense" };
+        let mut last_run = rule.last_run;
+        for date in rule.due(today) {
+            let draft = rule.draft(date);
+            let claimed = audited(pool, |repo| {
+                if repo.advance_rule_last_run(uid, rule.id, date)? == 0 {
+                    return Ok(false);
+                }
+                record(repo, NewAuditEntry::new(uid, ACTION_UPDATE, ENTITY_RECURRING_RULE, Some(rule.id))
+                    .before(describe_last_run(last_run))
+                    .after(describe_last_run(Some(date))))?;
+                insert_recorded(repo, uid, &draft.to_new(kind, uid), &draft.splits)?;
+                Ok(true)
+            })
+            .map_err(|e| format!("Rule error: {:?}", e))?;
+            booked += usize::from(claimed);
+            last_run = Some(date);
+        }
+    }
+    Ok(booked)
+}
+
+fn describe_last_run(last_run: Option<NaiveDate>) -> String {
+    match last_run {
+        Some(date) => format!("booked up to {}", date.format("%Y-%m-%d")),
+        None => "nothing booked yet".to_string(),
+    }
+}
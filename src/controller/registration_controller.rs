@@ -1,14 +1,12 @@
-use diesel::r2d2::{Pool, ConnectionManager};
-use diesel::PgConnection;
-use diesel::prelude::*;
 use bcrypt::{hash, DEFAULT_COST};
-use crate::model::{AuthData, NewUser};
+use crate::controller::audit_controller::{audited, record};
+use crate::model::{AuthData, DbPool, NewAuditEntry, NewUser};
+use crate::model::audit::{ACTION_INSERT, ENTITY_USER};
 
 pub fn attempt_register(
-    pool: &Pool<ConnectionManager<PgConnection>>,
+    pool: &DbPool,
     auth_data: &AuthData,
 ) -> Result<(), String> {
-    let mut conn = pool.get().map_err(|e| format!("DB connection error: {:?}", e))?;
     let hashed = hash(&auth_data.password, DEFAULT_COST)
         .map_err(|e| format!("Password error: {:?}", e))?;
     let new_user = NewUser {
@@ -16,9 +14,11 @@ pub fn attempt_register(
         password: hashed,
         secret_pass:auth_data.secret_pass.clone(),
     };
-    diesel::insert_into(crate::schema::users::table)
-        .values(new_user)
-        .execute(&mut conn)
-        .map_err(|e| format!("Registration error: {:?}", e))?;
-    Ok(())
-}
\ No newline at end of file
+    audited(pool, |repo| {
+        repo.insert_user(&new_user)?;
+        let user = repo.find_user_by_name(&new_user.username)?;
+        record(repo, NewAuditEntry::new(user.id, ACTION_INSERT, ENTITY_USER, Some(user.id))
+            .after(format!("registered as '{}'", user.username)))
+    })
+    .map_err(|e| format!("Registration error: {:?}", e))
+}
@@ -0,0 +1,103 @@
+use chrono::NaiveDate;
+use diesel::result::Error as DieselError;
+use crate::controller::audit_controller::{audited, record};
+use crate::model::{DbPool, Money, NewAuditEntry, NewGoalContribution, NewSavingsGoal, SavingsBook};
+use crate::model::audit::{describe_savings_goal, ACTION_DELETE, ACTION_INSERT, ACTION_UPDATE, ENTITY_SAVINGS_GOAL};
+use crate::model::currency::is_currency_code;
+
+pub fn load_savings_book(pool: &DbPool, uid: i32) -> Result<SavingsBook, String> {
+    let goals = pool.load_savings_goals(uid).map_err(|e| format!("Query error: {:?}", e))?;
+    let contributions = pool.load_goal_contributions(uid).map_err(|e| format!("Query error: {:?}", e))?;
+    Ok(SavingsBook { goals, contributions })
+}
+
+pub fn create_goal(pool: &DbPool, uid: i32, goal: &NewSavingsGoal, today: NaiveDate) -> Result<SavingsBook, String> {
+    if goal.user_id != uid {
+        return Err("Goal belongs to another user".into());
+    }
+    if goal.name.trim().is_empty() {
+        return Err("Enter a name for the goal".into());
+    }
+    if goal.target_amount <= Money::ZERO {
+        return Err("The target amount must be greater than zero".into());
+    }
+    if !is_currency_code(&goal.currency) {
+        return Err(format!("Unknown currency '{}'", goal.currency));
+    }
+    if goal.target_date < today {
+        return Err("The target date is in the past".into());
+    }
+    audited(pool, |repo| {
+        let created = repo.insert_savings_goal(goal)?;
+        record(repo, NewAuditEntry::new(uid, ACTION_INSERT, ENTITY_SAVINGS_GOAL, Some(created.id))
+            .after(describe_savings_goal(&created)))
+    })
+    .map_err(|e| format!("Goal error: {:?}", e))?;
+    load_savings_book(pool, uid)
+}
+
+/// Removes a goal with its earmarks; the transactions themselves are untouched.
+pub fn delete_goal(pool: &DbPool, uid: i32, goal_id: i32) -> Result<SavingsBook, String> {
+    let goal = pool.load_savings_goals(uid)
+        .map_err(|e| format!("Query error: {:?}", e))?
+        .into_iter()
+        .find(|g| g.id == goal_id)
+        .ok_or_else(|| "Goal not found".to_string())?;
+    audited(pool, |repo| {
+        repo.delete_savings_goal(uid, goal_id)?;
+        record(repo, NewAuditEntry::new(uid, ACTION_DELETE, ENTITY_SAVINGS_GOAL, Some(goal_id))
+            .before(describe_savings_goal(&goal)))
+    })
+    .map_err(|e| format!("Goal error: {:?}", e))?;
+    load_savings_book(pool, uid)
+}
+
+/// Earmarks part of an income or transfer for a goal. `amount` is in the transaction's
+/// currency, and a transaction can never be earmarked for more than its amount in total.
+pub fn earmark(pool: &DbPool, uid: i32, goal_id: i32, tx_id: i32, amount: Money) -> Result<SavingsBook, String> {
+    if amount <= Money::ZERO {
+        return Err("The amount must be greater than zero".into());
+    }
+    let book = load_savings_book(pool, uid)?;
+    if !book.goals.iter().any(|g| g.id == goal_id) {
+        return Err("Goal not found".into());
+    }
+    let tx = pool.find_transaction(uid, tx_id).map_err(|e| match e {
+        DieselError::NotFound => "Transaction not found".to_string(),
+        e => format!("Query error: {:?}", e),
+    })?;
+    if tx.tran_type == "Expense" {
+        return Err("Only income and transfers can be earmarked".into());
+    }
+    let earmarked: Money = book.contributions
+        .iter()
+        .filter(|c| c.transaction_id == tx_id)
+        .map(|c| c.amount)
+        .sum();
+    let free = tx.tran_amount - earmarked;
+    if amount > free {
+        return Err(format!("Only {} {} of this transaction is still free", free, tx.currency));
+    }
+    audited(pool, |repo| {
+        repo.insert_goal_contribution(&NewGoalContribution { goal_id, transaction_id: tx_id, amount })?;
+        record(repo, NewAuditEntry::new(uid, ACTION_UPDATE, ENTITY_SAVINGS_GOAL, Some(goal_id))
+            .after(format!("earmarked {} {} from transaction #{}", amount, tx.currency, tx_id)))
+    })
+    .map_err(|e| format!("Goal error: {:?}", e))?;
+    load_savings_book(pool, uid)
+}
+
+pub fn remove_contribution(pool: &DbPool, uid: i32, contribution_id: i32) -> Result<SavingsBook, String> {
+    let book = load_savings_book(pool, uid)?;
+    let contribution = book.contributions
+        .iter()
+        .find(|c| c.id == contribution_id)
+        .ok_or_else(|| "Contribution not found".to_string())?;
+    audited(pool, |repo| {
+        repo.delete_goal_contribution(uid, contribution_id)?;
+        record(repo, NewAuditEntry::new(uid, ACTION_UPDATE, ENTITY_SAVINGS_GOAL, Some(contribution.goal_id))
+            .before(format!("earmarked {} from transaction #{}", contribution.amount, contribution.transaction_id)))
+    })
+    .map_err(|e| format!("Goal error: {:?}", e))?;
+    load_savings_book(pool, uid)
+}
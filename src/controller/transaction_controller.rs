@@ -1,79 +1,264 @@
 // src/controller/transaction_controller.rs
-use diesel::{prelude::*, r2d2};
-use diesel::r2d2::{Pool, ConnectionManager};
-use diesel::PgConnection;
+use diesel::QueryResult;
 use diesel::result::Error as DieselError;
-use crate::schema::transactions::dsl::*;
-use crate::model::{Transaction, NewTransaction};
-use chrono::NaiveDateTime;
+use chrono::Duration;
+use crate::controller::attachment_controller::remove_unused_files;
+use crate::controller::audit_controller::{audited, record};
+use crate::model::{Transaction, DbPool, Money, Mutation, NewAuditEntry, NewTransaction, Repository, SplitLine, TransactionDraft};
+use crate::model::audit::{describe_transaction, ACTION_DELETE, ACTION_INSERT, ACTION_UPDATE, ENTITY_TRANSACTION};
+use crate::model::account::{check_account, check_transfer};
+use crate::model::transaction::{check_splits, trash_retention_days};
 
 pub fn load_transactions(
-    pool: &Pool<ConnectionManager<PgConnection>>,
+    pool: &DbPool,
     uid: i32
 ) -> Result<Vec<Transaction>, DieselError> {
-    let mut conn = pool.get().map_err(|_| DieselError::NotFound)?;
-    transactions
-        .filter(user_id.eq(uid))
-        .order(date.desc())
-        .load::<Transaction>(&mut conn)
+    pool.load_transactions(uid)
 }
 
 pub fn add_expense(
-    pool: &Pool<ConnectionManager<PgConnection>>,
+    pool: &DbPool,
     uid: i32,
-    source_str: &str,
-    date_str: NaiveDateTime,
-    amount_val: f64,
-    tag_id_val: Option<i32>,
-) -> Result<(), DieselError> {
-    let mut conn = pool.get().map_err(|_| DieselError::NotFound)?;
-
-
-    let new_tx = NewTransaction {
-        tran_type: "Expense",
-        user_id: uid,
-        tran_source: source_str,
-        date: date_str,
-        tran_amount: amount_val,
-        tag_id: tag_id_val,
-        tran_comment: None,
-    };
-
-    diesel::insert_into(transactions)
-        .values(&new_tx)
-        .execute(&mut conn)?;
-
-    Ok(())
+    draft: &TransactionDraft,
+) -> Result<Transaction, String> {
+    check_draft(pool, uid, draft)?;
+    insert_audited(pool, uid, &draft.to_new("Expense", uid), &draft.splits)
 }
 
 pub fn add_income(
-    pool: &Pool<ConnectionManager<PgConnection>>,
+    pool: &DbPool,
+    uid: i32,
+    draft: &TransactionDraft,
+) -> Result<Transaction, String> {
+    check_draft(pool, uid, draft)?;
+    insert_audited(pool, uid, &draft.to_new("Income", uid), &draft.splits)
+}
+
+/// Moves money from `draft.account_id` to `to_account`. Transfers are neither income nor expense.
+pub fn add_transfer(
+    pool: &DbPool,
     uid: i32,
-    source_str: &str,
-    date_str: NaiveDateTime,
-    amount_val: f64,
-) -> Result<usize, DieselError> {
-    let mut conn = pool.get().map_err(|_| DieselError::NotFound)?;
-    diesel::insert_into(transactions)
-        .values((
-            tran_type.eq("Income"),
-            user_id.eq(uid),
-            tran_source.eq(source_str),
-            date.eq(date_str),
-            tran_amount.eq(amount_val),
-        ))
-        .execute(&mut conn)
+    draft: &TransactionDraft,
+    to_account: i32,
+) -> Result<Transaction, String> {
+    let accounts = pool.load_accounts(uid).map_err(|e| format!("Query error: {:?}", e))?;
+    check_transfer(&accounts, draft.account_id, to_account)?;
+    let mut new_tx = draft.to_new("Transfer", uid);
+    new_tx.tag_id = None;
+    new_tx.transfer_account_id = Some(to_account);
+    insert_audited(pool, uid, &new_tx, &[])
 }
 
+/// Saves changes to an expense or income and returns the updated row.
+/// Fails unless the transaction belongs to `uid`.
+pub fn update_transaction(
+    pool: &DbPool,
+    uid: i32,
+    tx_id: i32,
+    draft: &TransactionDraft,
+) -> Result<Transaction, String> {
+    let existing = find_transaction(pool, uid, tx_id)?;
+    if existing.is_transfer() {
+        return Err("Transfers cannot be edited".into());
+    }
+    check_draft(pool, uid, draft)?;
+    // Savings goals may not hold more of a transaction than it brings in.
+    let earmarked: Money = pool.load_goal_contributions(uid)
+        .map_err(|e| format!("Query error: {:?}", e))?
+        .iter()
+        .filter(|c| c.transaction_id == tx_id)
+        .map(|c| c.amount)
+        .sum();
+    if draft.amount < earmarked {
+        return Err(format!(
+            "{} {} of this transaction is earmarked for savings goals; release it there before lowering the amount",
+            earmarked, existing.currency
+        ));
+    }
+    if earmarked > Money::ZERO && draft.currency != existing.currency {
+        return Err("The currency of a transaction earmarked for savings goals cannot change".into());
+    }
+    audited(pool, |repo| {
+        if repo.update_transaction(uid, tx_id, draft)? == 0 {
+            return Err(DieselError::NotFound);
+        }
+        let updated = repo.find_transaction(uid, tx_id)?;
+        audit(repo, uid, ACTION_UPDATE, Some(&existing), Some(&updated))?;
+        Ok(updated)
+    })
+    .map_err(|e| transaction_error(e, "Transaction not found"))
+}
 
+/// Moves one of the user's transactions to the trash and returns it so the delete can be undone.
 pub fn delete_transaction(
-    pool: &r2d2::Pool<ConnectionManager<PgConnection>>,
+    pool: &DbPool,
+    uid: i32,
     tx_id: i32
-) -> Result<(), diesel::result::Error> {
-    let mut conn = match pool.get() {
-    Ok(c) => c,
-    Err(_) => return Err(diesel::result::Error::NotFound),
-};
-    diesel::delete(transactions.filter(tran_id.eq(tx_id))).execute(&mut conn)?;
+) -> Result<Transaction, String> {
+    let existing = find_transaction(pool, uid, tx_id)?;
+    audited(pool, |repo| {
+        repo.delete_transaction(uid, tx_id)?;
+        audit(repo, uid, ACTION_DELETE, Some(&existing), None)
+    })
+    .map_err(|e| format!("Transaction error: {:?}", e))?;
+    Ok(existing)
+}
+
+pub fn load_trash(pool: &DbPool, uid: i32) -> Result<Vec<Transaction>, String> {
+    pool.load_trash(uid).map_err(|e| format!("Query error: {:?}", e))
+}
+
+/// Takes a transaction out of the trash and returns it.
+pub fn restore_from_trash(pool: &DbPool, uid: i32, tx_id: i32) -> Result<Transaction, String> {
+    let trashed = find_trashed(pool, uid, tx_id)?;
+    audited(pool, |repo| {
+        if repo.restore_transaction(uid, tx_id)? == 0 {
+            return Err(DieselError::NotFound);
+        }
+        let restored = repo.find_transaction(uid, tx_id)?;
+        audit(repo, uid, ACTION_UPDATE, Some(&trashed), Some(&restored))?;
+        Ok(restored)
+    })
+    .map_err(|e| transaction_error(e, "Transaction is not in the trash"))
+}
+
+pub fn purge_transaction(pool: &DbPool, uid: i32, tx_id: i32) -> Result<(), String> {
+    let trashed = find_trashed(pool, uid, tx_id)?;
+    let files = attachment_hashes(pool, std::slice::from_ref(&trashed))?;
+    audited(pool, |repo| {
+        if repo.purge_transaction(uid, tx_id)? == 0 {
+            return Err(DieselError::NotFound);
+        }
+        audit(repo, uid, ACTION_DELETE, Some(&trashed), None)
+    })
+    .map_err(|e| transaction_error(e, "Transaction is not in the trash"))?;
+    remove_unused_files(pool, &files)?;
     Ok(())
 }
+
+/// Permanently removes everything in the user's trash.
+pub fn empty_trash(pool: &DbPool, uid: i32) -> Result<usize, String> {
+    let trash = load_trash(pool, uid)?;
+    let files = attachment_hashes(pool, &trash)?;
+    let count = audited(pool, |repo| {
+        let count = repo.purge_trash(uid, None)?;
+        for tx in &trash {
+            audit(repo, uid, ACTION_DELETE, Some(tx), None)?;
+        }
+        Ok(count)
+    })
+    .map_err(|e| format!("Transaction error: {:?}", e))?;
+    remove_unused_files(pool, &files)?;
+    Ok(count)
+}
+
+/// Purges transactions that have been in the trash longer than the retention period.
+pub fn purge_expired_trash(pool: &DbPool, uid: i32) -> Result<usize, String> {
+    let cutoff = chrono::Local::now().naive_local() - Duration::days(trash_retention_days());
+    let expired: Vec<Transaction> = load_trash(pool, uid)?
+        .into_iter()
+        .filter(|t| t.deleted_at.is_some_and(|d| d < cutoff))
+        .collect();
+    if expired.is_empty() {
+        return Ok(0);
+    }
+    let files = attachment_hashes(pool, &expired)?;
+    let count = audited(pool, |repo| {
+        let count = repo.purge_trash(uid, Some(cutoff))?;
+        for tx in &expired {
+            audit(repo, uid, ACTION_DELETE, Some(tx), None)?;
+        }
+        Ok(count)
+    })
+    .map_err(|e| format!("Transaction error: {:?}", e))?;
+    remove_unused_files(pool, &files)?;
+    Ok(count)
+}
+
+/// Applies a recorded mutation to the database. Undo replays the inverse mutation,
+/// redo replays the original one.
+pub fn replay_mutation(pool: &DbPool, uid: i32, mutation: &Mutation) -> Result<(), String> {
+    match mutation {
+        Mutation::Added(tx) => restore_from_trash(pool, uid, tx.tran_id).map(|_| ()),
+        Mutation::Deleted(tx) => delete_transaction(pool, uid, tx.tran_id).map(|_| ()),
+        Mutation::Edited { after, .. } => {
+            update_transaction(pool, uid, after.tran_id, &TransactionDraft::from(&**after)).map(|_| ())
+        }
+    }
+}
+
+/// Every category and account the draft refers to must belong to the user, and split
+/// lines must add up.
+fn check_draft(pool: &DbPool, uid: i32, draft: &TransactionDraft) -> Result<(), String> {
+    check_splits(draft)?;
+    if draft.account_id.is_some() {
+        let accounts = pool.load_accounts(uid).map_err(|e| format!("Query error: {:?}", e))?;
+        check_account(&accounts, draft.account_id)?;
+    }
+    let tags: Vec<i32> = draft.tag_id
+        .into_iter()
+        .chain(draft.splits.iter().filter_map(|s| s.tag_id))
+        .collect();
+    if !tags.is_empty() {
+        let categories = pool.load_categories(uid).map_err(|e| format!("Query error: {:?}", e))?;
+        if tags.iter().any(|tag| !categories.iter().any(|c| c.id == *tag)) {
+            return Err("Category not found".into());
+        }
+    }
+    Ok(())
+}
+
+fn insert_audited(pool: &DbPool, uid: i32, new_tx: &NewTransaction, splits: &[SplitLine]) -> Result<Transaction, String> {
+    audited(pool, |repo| insert_recorded(repo, uid, new_tx, splits))
+        .map_err(|e| format!("Transaction error: {:?}", e))
+}
+
+/// Inserts the transaction with its audit entry; for changes that book a transaction
+/// as part of a larger `audited` unit.
+pub fn insert_recorded(repo: &dyn Repository, uid: i32, new_tx: &NewTransaction, splits: &[SplitLine]) -> QueryResult<Transaction> {
+    let tx = repo.insert_transaction(new_tx, splits)?;
+    audit(repo, uid, ACTION_INSERT, None, Some(&tx))?;
+    Ok(tx)
+}
+
+fn audit(repo: &dyn Repository, uid: i32, action: &str, before: Option<&Transaction>, after: Option<&Transaction>) -> QueryResult<()> {
+    let id = before.or(after).map(|t| t.tran_id);
+    let mut entry = NewAuditEntry::new(uid, action, ENTITY_TRANSACTION, id);
+    if let Some(tx) = before {
+        entry = entry.before(describe_transaction(tx));
+    }
+    if let Some(tx) = after {
+        entry = entry.after(describe_transaction(tx));
+    }
+    record(repo, entry)
+}
+
+/// `NotFound` stands for a row the change expected but didn't touch.
+fn transaction_error(e: DieselError, not_found: &str) -> String {
+    match e {
+        DieselError::NotFound => not_found.to_string(),
+        e => format!("Transaction error: {:?}", e),
+    }
+}
+
+/// Stored files of the given transactions; purging removes their attachment rows
+/// through the foreign key, the files are cleaned up afterwards.
+fn attachment_hashes(pool: &DbPool, txs: &[Transaction]) -> Result<Vec<String>, String> {
+    let ids: Vec<i32> = txs.iter().map(|t| t.tran_id).collect();
+    pool.attachment_hashes(&ids)
+        .map_err(|e| format!("Query error: {:?}", e))
+}
+
+fn find_trashed(pool: &DbPool, uid: i32, tx_id: i32) -> Result<Transaction, String> {
+    load_trash(pool, uid)?
+        .into_iter()
+        .find(|t| t.tran_id == tx_id)
+        .ok_or_else(|| "Transaction is not in the trash".to_string())
+}
+
+fn find_transaction(pool: &DbPool, uid: i32, tx_id: i32) -> Result<Transaction, String> {
+    pool.find_transaction(uid, tx_id).map_err(|e| match e {
+        DieselError::NotFound => "Transaction not found".to_string(),
+        e => format!("Query error: {:?}", e),
+    })
+}
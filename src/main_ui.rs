@@ -3,11 +3,19 @@ mod model;
 mod controller;
 mod view;
 
+use dotenv::dotenv;
 use iced::Application;
 use iced::Settings;
 use model::CombinedApp;
 
 fn main() {
+    dotenv().ok();
+
+    if let Err(e) = model::db::run_migrations() {
+        eprintln!("Database error: {e}");
+        std::process::exit(1);
+    }
+
     if let Err(e) = CombinedApp::run(Settings::default()) {
         eprintln!("Application error: {e}");
         std::process::exit(1);
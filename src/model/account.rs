@@ -0,0 +1,71 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::schema::accounts;
+
+pub const DEFAULT_ACCOUNT_NAME: &str = "Main";
+
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Serialize, Deserialize)]
+pub struct Account {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+}
+
+impl std::fmt::Display for Account {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// `None` is no account; any other id must be one of the user's `accounts`.
+pub fn check_account(accounts: &[Account], account_id: Option<i32>) -> Result<(), String> {
+    match account_id {
+        Some(id) if !accounts.iter().any(|a| a.id == id) => Err("Account not found".into()),
+        _ => Ok(()),
+    }
+}
+
+/// A transfer needs two different accounts of the user.
+pub fn check_transfer(accounts: &[Account], from: Option<i32>, to: i32) -> Result<(), String> {
+    let from = from.ok_or("Choose the account to transfer from")?;
+    if from == to {
+        return Err("Choose two different accounts".into());
+    }
+    check_account(accounts, Some(from))?;
+    check_account(accounts, Some(to))
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = accounts)]
+pub struct NewAccount<'a> {
+    pub user_id: i32,
+    pub name: &'a str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accounts() -> Vec<Account> {
+        vec![
+            Account { id: 1, user_id: 7, name: "Main".into() },
+            Account { id: 2, user_id: 7, name: "Savings".into() },
+        ]
+    }
+
+    #[test]
+    fn accounts_must_be_the_users() {
+        assert_eq!(check_account(&accounts(), None), Ok(()));
+        assert_eq!(check_account(&accounts(), Some(2)), Ok(()));
+        assert_eq!(check_account(&accounts(), Some(3)), Err("Account not found".into()));
+    }
+
+    #[test]
+    fn transfers_need_two_different_accounts() {
+        assert_eq!(check_transfer(&accounts(), Some(1), 2), Ok(()));
+        assert_eq!(check_transfer(&accounts(), Some(2), 2), Err("Choose two different accounts".into()));
+        assert_eq!(check_transfer(&accounts(), None, 2), Err("Choose the account to transfer from".into()));
+        assert_eq!(check_transfer(&accounts(), Some(1), 3), Err("Account not found".into()));
+        assert_eq!(check_transfer(&accounts(), Some(3), 1), Err("Account not found".into()));
+    }
+}
@@ -0,0 +1,94 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::path::{Path, PathBuf};
+use crate::schema::attachments;
+
+const DEFAULT_ATTACHMENTS_DIR: &str = "attachments";
+/// Largest file accepted as an attachment.
+pub const MAX_ATTACHMENT_BYTES: u64 = 20 * 1024 * 1024;
+
+/// A receipt or document kept for a transaction. The file itself lives in the
+/// content-addressed store, so identical files are stored once.
+#[derive(Debug, Clone, Queryable)]
+pub struct Attachment {
+    pub id: i32,
+    pub transaction_id: i32,
+    pub file_name: String,
+    pub content_hash: String,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    pub created_at: NaiveDateTime,
+}
+
+impl Attachment {
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+
+    pub fn path(&self) -> PathBuf {
+        stored_path(&self.content_hash)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = attachments)]
+pub struct NewAttachment<'a> {
+    pub transaction_id: i32,
+    pub file_name: &'a str,
+    pub content_hash: &'a str,
+    pub mime_type: &'a str,
+    pub size_bytes: i64,
+    pub created_at: NaiveDateTime,
+}
+
+/// A file copied into the store but not yet linked to a transaction.
+#[derive(Debug, Clone)]
+pub struct StoredFile {
+    pub file_name: String,
+    pub content_hash: String,
+    pub mime_type: &'static str,
+    pub size_bytes: i64,
+}
+
+/// Root of the attachment store, from `ATTACHMENTS_DIR` (default `attachments`).
+pub fn attachments_dir() -> PathBuf {
+    env::var("ATTACHMENTS_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_ATTACHMENTS_DIR))
+}
+
+/// Files are stored as `<dir>/<first two hex digits>/<sha256>`.
+pub fn stored_path(hash: &str) -> PathBuf {
+    attachments_dir().join(&hash[..2.min(hash.len())]).join(hash)
+}
+
+pub fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+pub fn mime_type_for(path: &Path) -> &'static str {
+    let extension = path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "pdf" => "application/pdf",
+        "txt" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+pub fn format_size(bytes: i64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.0} kB", b as f64 / 1024.0),
+        b => format!("{} B", b),
+    }
+}
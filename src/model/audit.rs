@@ -0,0 +1,200 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use crate::model::{Account, Budget, Category, Envelope, Loan, NewEnvelopeMove, RecurringRule, SavingsGoal, Transaction};
+use crate::schema::audit_log;
+
+pub const ACTION_INSERT: &str = "insert";
+pub const ACTION_UPDATE: &str = "update";
+pub const ACTION_DELETE: &str = "delete";
+pub const AUDIT_ACTIONS: [&str; 3] = [ACTION_INSERT, ACTION_UPDATE, ACTION_DELETE];
+
+pub const ENTITY_TRANSACTION: &str = "transaction";
+pub const ENTITY_CATEGORY: &str = "category";
+pub const ENTITY_ACCOUNT: &str = "account";
+pub const ENTITY_USER: &str = "user";
+pub const ENTITY_EXCHANGE_RATE: &str = "exchange_rate";
+pub const ENTITY_ATTACHMENT: &str = "attachment";
+pub const ENTITY_RECURRING_RULE: &str = "recurring_rule";
+pub const ENTITY_BUDGET: &str = "budget";
+pub const ENTITY_ENVELOPE: &str = "envelope";
+pub const ENTITY_SAVINGS_GOAL: &str = "savings_goal";
+pub const ENTITY_LOAN: &str = "loan";
+
+/// One row of the append-only audit log: who changed what, when, and how.
+#[derive(Debug, Clone, Queryable)]
+pub struct AuditEntry {
+    pub id: i32,
+    pub user_id: i32,
+    pub created_at: NaiveDateTime,
+    pub action: String,
+    pub entity: String,
+    pub entity_id: Option<i32>,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = audit_log)]
+pub struct NewAuditEntry<'a> {
+    pub user_id: i32,
+    pub created_at: NaiveDateTime,
+    pub action: &'a str,
+    pub entity: &'a str,
+    pub entity_id: Option<i32>,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+impl<'a> NewAuditEntry<'a> {
+    pub fn new(uid: i32, action: &'a str, entity: &'a str, entity_id: Option<i32>) -> Self {
+        NewAuditEntry {
+            user_id: uid,
+            created_at: chrono::Local::now().naive_local(),
+            action,
+            entity,
+            entity_id,
+            old_value: None,
+            new_value: None,
+        }
+    }
+
+    pub fn before(mut self, value: impl Into<String>) -> Self {
+        self.old_value = Some(value.into());
+        self
+    }
+
+    pub fn after(mut self, value: impl Into<String>) -> Self {
+        self.new_value = Some(value.into());
+        self
+    }
+}
+
+/// Restricts the audit viewer to a date range (inclusive) and one action.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub action: Option<String>,
+}
+
+pub fn describe_transaction(tx: &Transaction) -> String {
+    let mut text = format!(
+        "{} '{}' {} {} on {}",
+        tx.tran_type,
+        tx.tran_source,
+        tx.tran_amount,
+        tx.currency,
+        tx.date.format("%Y-%m-%d %H:%M:%S"),
+    );
+    if let Some(tag) = tx.tag_id {
+        text.push_str(&format!(", category #{}", tag));
+    }
+    if let Some(account) = tx.account_id {
+        text.push_str(&format!(", account #{}", account));
+    }
+    if let Some(to) = tx.transfer_account_id {
+        text.push_str(&format!(" → account #{}", to));
+    }
+    if tx.is_split() {
+        let lines: Vec<String> = tx.splits
+            .iter()
+            .map(|s| match s.tag_id {
+                Some(tag) => format!("#{} {}", tag, s.amount),
+                None => format!("uncategorized {}", s.amount),
+            })
+            .collect();
+        text.push_str(&format!(", split {}", lines.join(" / ")));
+    }
+    if let Some(comment) = &tx.tran_comment {
+        text.push_str(&format!(", comment '{}'", comment));
+    }
+    if tx.deleted_at.is_some() {
+        text.push_str(", in trash");
+    }
+    text
+}
+
+pub fn describe_category(category: &Category) -> String {
+    let parent = category.parent_id
+        .map(|p| format!(", parent #{}", p))
+        .unwrap_or_default();
+    format!("{} '{}' {}{}", category.kind, category.name, category.color, parent)
+}
+
+pub fn describe_account(account: &Account) -> String {
+    format!("'{}'", account.name)
+}
+
+pub fn describe_recurring_rule(rule: &RecurringRule) -> String {
+    let mut text = format!(
+        "{} '{}' {} {} {} from {}",
+        rule.tran_type,
+        rule.source,
+        rule.amount,
+        rule.currency,
+        rule.frequency,
+        rule.start_date.format("%Y-%m-%d"),
+    );
+    if let Some(end) = rule.end_date {
+        text.push_str(&format!(" until {}", end.format("%Y-%m-%d")));
+    }
+    if let Some(tag) = rule.tag_id {
+        text.push_str(&format!(", category #{}", tag));
+    }
+    if let Some(account) = rule.account_id {
+        text.push_str(&format!(", account #{}", account));
+    }
+    text
+}
+
+pub fn describe_budget(budget: &Budget) -> String {
+    format!(
+        "category #{} limit {} per month{}",
+        budget.tag_id,
+        budget.monthly_limit,
+        if budget.rollover { ", rollover" } else { "" },
+    )
+}
+
+pub fn describe_envelope(envelope: &Envelope) -> String {
+    match envelope.tag_id {
+        Some(tag) => format!("'{}' for category #{}", envelope.name, tag),
+        None => format!("'{}'", envelope.name),
+    }
+}
+
+pub fn describe_envelope_move(envelope_move: &NewEnvelopeMove) -> String {
+    let side = |envelope: Option<i32>| match envelope {
+        Some(id) => format!("envelope #{}", id),
+        None => "available to assign".to_string(),
+    };
+    format!(
+        "moved {} from {} to {}",
+        envelope_move.amount,
+        side(envelope_move.from_envelope),
+        side(envelope_move.to_envelope),
+    )
+}
+
+pub fn describe_savings_goal(goal: &SavingsGoal) -> String {
+    format!(
+        "'{}' {} {} by {}",
+        goal.name,
+        goal.target_amount,
+        goal.currency,
+        goal.target_date.format("%Y-%m-%d"),
+    )
+}
+
+pub fn describe_loan(loan: &Loan) -> String {
+    format!(
+        "'{}' {} {} {} at {} bp over {} months from {}",
+        loan.name,
+        loan.direction,
+        loan.principal,
+        loan.currency,
+        loan.annual_rate_bp,
+        loan.term_months,
+        loan.start_date.format("%Y-%m-%d"),
+    )
+}
@@ -0,0 +1,170 @@
+use chrono::{Datelike, Months, NaiveDate};
+use diesel::prelude::*;
+use crate::model::Money;
+use crate::schema::budgets;
+
+/// Monthly spending limit for an expense category and its subcategories, in the
+/// user's base currency.
+#[derive(Debug, Clone, Queryable)]
+pub struct Budget {
+    pub id: i32,
+    pub user_id: i32,
+    pub tag_id: i32,
+    pub monthly_limit: Money,
+    /// Unused budget carries over into the following month.
+    pub rollover: bool,
+    /// First month the budget applies to; rollover starts counting here.
+    pub start_month: NaiveDate,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = budgets)]
+pub struct NewBudget {
+    pub user_id: i32,
+    pub tag_id: i32,
+    pub monthly_limit: Money,
+    pub rollover: bool,
+    pub start_month: NaiveDate,
+}
+
+/// Where a budget stands in one month.
+#[derive(Debug, Clone, Copy)]
+pub struct BudgetStatus {
+    pub limit: Money,
+    /// Unused budget brought over from earlier months.
+    pub carried: Money,
+    pub spent: Money,
+}
+
+impl BudgetStatus {
+    pub fn available(&self) -> Money {
+        self.limit + self.carried
+    }
+
+    pub fn remaining(&self) -> Money {
+        self.available() - self.spent
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.spent > self.available()
+    }
+}
+
+impl Budget {
+    /// Status for `month` given the spending per month. With rollover, whatever was left
+    /// in a month is added to the next one; overspending is not carried as debt.
+    pub fn status(&self, month: NaiveDate, spent_in: impl Fn(NaiveDate) -> Money) -> BudgetStatus {
+        let month = month_start(month);
+        let mut carried = Money::ZERO;
+        if self.rollover {
+            let mut current = month_start(self.start_month);
+            while current < month {
+                let left = self.monthly_limit + carried - spent_in(current);
+                carried = left.max(Money::ZERO);
+                current = next_month(current);
+            }
+        }
+        BudgetStatus { limit: self.monthly_limit, carried, spent: spent_in(month) }
+    }
+}
+
+pub fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+pub fn next_month(month: NaiveDate) -> NaiveDate {
+    month_start(month).checked_add_months(Months::new(1)).unwrap_or(month)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn budget(rollover: bool) -> Budget {
+        Budget {
+            id: 1,
+            user_id: 1,
+            tag_id: 1,
+            monthly_limit: Money::from_minor(10_000),
+            rollover,
+            start_month: date("2024-01-01"),
+        }
+    }
+
+    /// Spending per month, keyed by the month's first day.
+    fn spending<'a>(months: &'a [(&'a str, i64)]) -> impl Fn(NaiveDate) -> Money + 'a {
+        move |month| {
+            months
+                .iter()
+                .find(|(m, _)| date(m) == month)
+                .map_or(Money::ZERO, |(_, minor)| Money::from_minor(*minor))
+        }
+    }
+
+    #[test]
+    fn unused_budget_rolls_over_month_after_month() {
+        let spent = [("2024-01-01", 6_000), ("2024-02-01", 3_000), ("2024-03-01", 15_000)];
+        let budget = budget(true);
+
+        let january = budget.status(date("2024-01-20"), spending(&spent));
+        assert_eq!(january.carried, Money::ZERO);
+        assert_eq!(january.remaining(), Money::from_minor(4_000));
+
+        let february = budget.status(date("2024-02-01"), spending(&spent));
+        assert_eq!(february.carried, Money::from_minor(4_000));
+        assert_eq!(february.remaining(), Money::from_minor(11_000));
+
+        // March spends more than its own limit but stays within what was carried in.
+        let march = budget.status(date("2024-03-31"), spending(&spent));
+        assert_eq!(march.carried, Money::from_minor(11_000));
+        assert_eq!(march.available(), Money::from_minor(21_000));
+        assert!(!march.is_over());
+
+        let april = budget.status(date("2024-04-01"), spending(&spent));
+        assert_eq!(april.carried, Money::from_minor(6_000));
+        assert_eq!(april.spent, Money::ZERO);
+    }
+
+    #[test]
+    fn an_overspent_month_carries_nothing() {
+        let spent = [("2024-01-01", 13_000), ("2024-02-01", 2_500)];
+        let budget = budget(true);
+
+        let january = budget.status(date("2024-01-01"), spending(&spent));
+        assert!(january.is_over());
+        assert_eq!(january.remaining(), Money::from_minor(-3_000));
+
+        // The overspending is not taken out of February's limit.
+        let february = budget.status(date("2024-02-01"), spending(&spent));
+        assert_eq!(february.carried, Money::ZERO);
+        assert_eq!(february.remaining(), Money::from_minor(7_500));
+
+        let march = budget.status(date("2024-03-01"), spending(&spent));
+        assert_eq!(march.carried, Money::from_minor(7_500));
+    }
+
+    #[test]
+    fn without_rollover_each_month_starts_fresh() {
+        let spent = [("2024-01-01", 1_000), ("2024-02-01", 12_000)];
+        let budget = budget(false);
+
+        let february = budget.status(date("2024-02-01"), spending(&spent));
+        assert_eq!(february.carried, Money::ZERO);
+        assert!(february.is_over());
+
+        let march = budget.status(date("2024-03-01"), spending(&spent));
+        assert_eq!(march.carried, Money::ZERO);
+        assert_eq!(march.remaining(), Money::from_minor(10_000));
+    }
+
+    #[test]
+    fn months_before_the_start_carry_nothing() {
+        let status = budget(true).status(date("2023-11-01"), spending(&[]));
+        assert_eq!(status.carried, Money::ZERO);
+        assert_eq!(next_month(date("2024-12-15")), date("2025-01-01"));
+    }
+}
@@ -0,0 +1,275 @@
+use chrono::NaiveDate;
+use crate::model::Money;
+use crate::model::import::{parse_statement_amount, BankStatement, ImportedRow, StatementBalance};
+use crate::model::xml::{parse_xml, XmlElement};
+
+/// Balance types read as the opening balance: opening booked, or the previous
+/// statement's closing balance.
+const OPENING_BALANCE_CODES: [&str; 2] = ["OPBD", "PRCD"];
+const CLOSING_BALANCE_CODE: &str = "CLBD";
+
+/// Reads an ISO date or datetime element such as `<Dt>2026-03-01</Dt>` or
+/// `<DtTm>2026-03-01T10:00:00+01:00</DtTm>`.
+fn camt_date(element: &XmlElement) -> Option<NaiveDate> {
+    let text = element.text_at(&["Dt"]).or_else(|| element.text_at(&["DtTm"]))?;
+    NaiveDate::parse_from_str(text.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// The amount with its sign from `CdtDbtInd`: credits are positive, debits negative.
+fn signed_amount(element: &XmlElement, amount: &XmlElement) -> Result<Money, String> {
+    let value = parse_statement_amount(&amount.text, '.')?;
+    match element.text_at(&["CdtDbtInd"]) {
+        Some("DBIT") => Ok(-value),
+        Some("CRDT") => Ok(value),
+        other => Err(format!("Unknown credit/debit indicator {:?}", other.unwrap_or(""))),
+    }
+}
+
+fn parse_balance(balance: &XmlElement) -> Result<Option<(String, StatementBalance)>, String> {
+    let code = balance
+        .text_at(&["Tp", "CdOrPrtry", "Cd"])
+        .or_else(|| balance.text_at(&["Tp", "CdOrPrtry", "Prtry"]))
+        .unwrap_or("")
+        .to_string();
+    let amount = match balance.child("Amt") {
+        Some(amount) => signed_amount(balance, amount)?,
+        None => return Ok(None),
+    };
+    Ok(balance.child("Dt").and_then(camt_date).map(|date| (code, StatementBalance { amount, date })))
+}
+
+/// Name of the other party: the debtor of a credit, the creditor of a debit. camt.053
+/// versions from 08 on wrap the name in a `Pty` element.
+fn counterparty(details: &XmlElement, incoming: bool) -> Option<String> {
+    let roles: [&str; 2] = if incoming { ["Dbtr", "UltmtDbtr"] } else { ["Cdtr", "UltmtCdtr"] };
+    roles.iter().find_map(|role| {
+        details
+            .text_at(&["RltdPties", role, "Nm"])
+            .or_else(|| details.text_at(&["RltdPties", role, "Pty", "Nm"]))
+            .map(str::to_string)
+    })
+}
+
+/// Unstructured remittance lines joined, or the structured creditor reference.
+fn remittance(details: &XmlElement) -> Option<String> {
+    let info = details.child("RmtInf")?;
+    let lines: Vec<&str> = info.children_named("Ustrd").map(|u| u.text.as_str()).filter(|t| !t.is_empty()).collect();
+    if !lines.is_empty() {
+        return Some(lines.join(" "));
+    }
+    info.children_named("Strd")
+        .find_map(|s| s.text_at(&["CdtrRefInf", "Ref"]))
+        .map(str::to_string)
+}
+
+fn parse_entry(entry: &XmlElement, account: Option<&str>, currency: Option<&str>) -> Result<Vec<ImportedRow>, String> {
+    let amount = signed_amount(entry, entry.child("Amt").ok_or("Entry without an amount")?)?;
+    let date = entry.child("BookgDt").and_then(camt_date)
+        .or_else(|| entry.child("ValDt").and_then(camt_date))
+        .ok_or("Entry without a booking date")?;
+    let reference = entry.text_at(&["AcctSvcrRef"]).or_else(|| entry.text_at(&["NtryRef"]));
+    let entry_currency = entry.child("Amt").and_then(|a| a.attribute("Ccy")).or(currency);
+    let external_id = |suffix: Option<usize>| {
+        reference.map(|r| {
+            let id = match suffix {
+                Some(i) => format!("{}/{}", r, i),
+                None => r.to_string(),
+            };
+            match account {
+                Some(account) => format!("{}/{}", account, id),
+                None => id,
+            }
+        })
+    };
+    let fallback_text = entry.text_at(&["AddtlNtryInf"]).map(str::to_string);
+    let row = |details: Option<&XmlElement>, amount: Money, id: Option<String>| {
+        let incoming = !amount.is_negative();
+        let name = details.and_then(|d| counterparty(d, incoming));
+        let info = details.and_then(remittance)
+            .or_else(|| details.and_then(|d| d.text_at(&["AddtlTxInf"])).map(str::to_string))
+            .or_else(|| fallback_text.clone());
+        let (payee, comment) = match (name, info) {
+            (Some(name), info) => (name, info),
+            (None, Some(info)) => (info, None),
+            (None, None) => ("Imported".to_string(), None),
+        };
+        ImportedRow {
+            date,
+            amount,
+            payee,
+            comment,
+            category: None,
+            splits: Vec::new(),
+            external_id: id,
+            currency: entry_currency.map(str::to_string),
+        }
+    };
+
+    let details: Vec<&XmlElement> = entry
+        .children_named("NtryDtls")
+        .flat_map(|d| d.children_named("TxDtls"))
+        .collect();
+    // A batch booking lists its payments as separate details; book those separately
+    // when each names its own amount.
+    if details.len() > 1 {
+        let amounts: Option<Vec<&XmlElement>> = details
+            .iter()
+            .map(|d| d.at(&["AmtDtls", "TxAmt", "Amt"]).or_else(|| d.child("Amt")))
+            .collect();
+        if let Some(amounts) = amounts {
+            let mut rows = Vec::new();
+            for (i, (detail, amount)) in details.iter().zip(amounts).enumerate() {
+                let mut value = parse_statement_amount(&amount.text, '.')?;
+                let indicator = detail.text_at(&["CdtDbtInd"]).or_else(|| entry.text_at(&["CdtDbtInd"]));
+                if indicator == Some("DBIT") {
+                    value = -value;
+                }
+                rows.push(row(Some(detail), value, external_id(Some(i + 1))));
+            }
+            return Ok(rows);
+        }
+    }
+    Ok(vec![row(details.first().copied(), amount, external_id(None))])
+}
+
+fn parse_statement(statement: &XmlElement) -> Result<BankStatement, String> {
+    let account = statement
+        .text_at(&["Acct", "Id", "IBAN"])
+        .or_else(|| statement.text_at(&["Acct", "Id", "Othr", "Id"]))
+        .map(str::to_string);
+    let mut result = BankStatement {
+        currency: statement.text_at(&["Acct", "Ccy"]).map(str::to_string),
+        account,
+        ..BankStatement::default()
+    };
+    for balance in statement.children_named("Bal") {
+        if let Some((code, balance)) = parse_balance(balance)? {
+            if OPENING_BALANCE_CODES.contains(&code.as_str()) && result.opening.is_none() {
+                result.opening = Some(balance);
+            } else if code == CLOSING_BALANCE_CODE {
+                result.closing = Some(balance);
+            }
+        }
+    }
+    if result.currency.is_none() {
+        result.currency = statement
+            .children_named("Bal")
+            .find_map(|b| b.child("Amt").and_then(|a| a.attribute("Ccy")))
+            .map(str::to_string);
+    }
+    for (i, entry) in statement.children_named("Ntry").enumerate() {
+        // Pending entries are not booked yet and are not part of the balances.
+        let status = entry.text_at(&["Sts"]).or_else(|| entry.text_at(&["Sts", "Cd"]));
+        if status == Some("PDNG") {
+            continue;
+        }
+        let rows = parse_entry(entry, result.account.as_deref(), result.currency.as_deref())
+            .map_err(|e| format!("Entry {}: {}", i + 1, e))?;
+        result.rows.extend(rows.into_iter().filter(|r| !r.amount.is_zero()));
+    }
+    Ok(result)
+}
+
+/// Reads the statements of an ISO 20022 camt.053 (bank to customer statement) file.
+/// The counterparty's name becomes the payee and the remittance information the
+/// comment; the bank's entry reference (`AcctSvcrRef`) is the external id.
+pub fn parse_camt053(text: &str) -> Result<Vec<BankStatement>, String> {
+    let document = parse_xml(text)?;
+    let report = document
+        .find("BkToCstmrStmt")
+        .ok_or("This is not a camt.053 statement")?;
+    let statements = report
+        .children_named("Stmt")
+        .map(parse_statement)
+        .collect::<Result<Vec<_>, _>>()?;
+    if statements.is_empty() {
+        return Err("The camt.053 file holds no statement".into());
+    }
+    Ok(statements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATEMENT: &str = include_str!("../../tests/data/camt053-2026-03.xml");
+
+    fn statement() -> BankStatement {
+        let mut statements = parse_camt053(STATEMENT).unwrap();
+        assert_eq!(statements.len(), 1);
+        statements.remove(0)
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn reads_account_and_balances() {
+        let statement = statement();
+        assert_eq!(statement.account.as_deref(), Some("DE89370400440532013000"));
+        assert_eq!(statement.currency.as_deref(), Some("EUR"));
+        assert_eq!(statement.opening, Some(StatementBalance { amount: Money::from_minor(100000), date: date(2026, 2, 28) }));
+        assert_eq!(statement.closing, Some(StatementBalance { amount: Money::from_minor(313510), date: date(2026, 3, 31) }));
+    }
+
+    #[test]
+    fn complete_statement_balances() {
+        assert_eq!(statement().balance_difference(), Some(Money::ZERO));
+    }
+
+    #[test]
+    fn reads_entries() {
+        let rows = statement().rows;
+        assert_eq!(rows[0].date, date(2026, 3, 2));
+        assert_eq!(rows[0].amount, Money::from_minor(-4990));
+        assert_eq!(rows[0].payee, "Stadtwerke");
+        assert_eq!(rows[0].comment.as_deref(), Some("Strom März 2026"));
+        assert_eq!(rows[0].external_id.as_deref(), Some("DE89370400440532013000/REF-0001"));
+        assert_eq!(rows[0].currency.as_deref(), Some("EUR"));
+
+        // camt.053.001.08 names the party in `Pty`; the date comes from `DtTm`.
+        assert_eq!(rows[1].date, date(2026, 3, 25));
+        assert_eq!(rows[1].amount, Money::from_minor(250000));
+        assert_eq!(rows[1].payee, "ACME GmbH");
+        assert_eq!(rows[1].comment.as_deref(), Some("RF18539007547034"));
+    }
+
+    #[test]
+    fn splits_batches_whose_details_carry_amounts() {
+        let rows = statement().rows;
+        let batch: Vec<_> = rows.iter().filter(|r| r.date == date(2026, 3, 27)).collect();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[0].amount, Money::from_minor(-10000));
+        assert_eq!(batch[0].payee, "Landlord");
+        assert_eq!(batch[0].comment.as_deref(), Some("Garage"));
+        assert_eq!(batch[0].external_id.as_deref(), Some("DE89370400440532013000/REF-0003/1"));
+        assert_eq!(batch[1].amount, Money::from_minor(-20000));
+        assert_eq!(batch[1].payee, "Insurer");
+        assert_eq!(batch[1].external_id.as_deref(), Some("DE89370400440532013000/REF-0003/2"));
+    }
+
+    #[test]
+    fn keeps_batches_without_detail_amounts_whole() {
+        let rows = statement().rows;
+        let fees: Vec<_> = rows.iter().filter(|r| r.date == date(2026, 3, 30)).collect();
+        assert_eq!(fees.len(), 1);
+        assert_eq!(fees[0].amount, Money::from_minor(-1500));
+        assert_eq!(fees[0].payee, "Card fees");
+        assert_eq!(fees[0].comment.as_deref(), Some("Fees March"));
+        assert_eq!(fees[0].external_id.as_deref(), Some("DE89370400440532013000/REF-0004"));
+    }
+
+    #[test]
+    fn skips_pending_entries() {
+        let rows = statement().rows;
+        assert_eq!(rows.len(), 5);
+        assert!(rows.iter().all(|r| r.date < date(2026, 3, 31)));
+    }
+
+    #[test]
+    fn rejects_other_xml() {
+        assert!(parse_camt053("<Document><BkToCstmrDbtCdtNtfctn/></Document>").is_err());
+        assert!(parse_camt053("<Document><BkToCstmrStmt><GrpHdr/></BkToCstmrStmt></Document>").is_err());
+    }
+}
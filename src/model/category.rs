@@ -1,8 +1,168 @@
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use crate::schema::expense_tags;
 
-#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
+pub const DEFAULT_CATEGORY_COLOR: &str = "#888888";
+
+pub const KIND_EXPENSE: &str = "expense";
+pub const KIND_INCOME: &str = "income";
+pub const CATEGORY_KINDS: [&str; 2] = [KIND_EXPENSE, KIND_INCOME];
+
+/// Colours offered in the category editor; the same palette the pie chart used to cycle through.
+pub const CATEGORY_PALETTE: [&str; 8] = [
+    "#cc1a66", "#1acc66", "#6666ff", "#ff9933", "#9933ff", "#33ffcc", "#cccc33", "#4d4d4d",
+];
+
+/// A category owned by one user. Rows with `user_id = NULL` are the defaults
+/// copied to every new user.
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Serialize, Deserialize)]
 pub struct Category {
     pub id: i32,
     pub name: String,
-}
\ No newline at end of file
+    pub user_id: Option<i32>,
+    pub color: String,
+    pub parent_id: Option<i32>,
+    pub kind: String,
+}
+
+impl Category {
+    pub fn is_income(&self) -> bool {
+        self.kind == KIND_INCOME
+    }
+
+    pub fn rgb(&self) -> [f32; 3] {
+        parse_hex_color(&self.color).unwrap_or([0.5, 0.5, 0.5])
+    }
+}
+
+impl std::fmt::Display for Category {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = expense_tags)]
+pub struct NewCategory<'a> {
+    pub name: &'a str,
+    pub user_id: Option<i32>,
+    pub color: &'a str,
+    pub parent_id: Option<i32>,
+    pub kind: &'a str,
+}
+
+/// A category as shown in tree pickers: indented by its depth.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryOption {
+    pub category: Category,
+    pub depth: usize,
+}
+
+impl std::fmt::Display for CategoryOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", "    ".repeat(self.depth), self.category.name)
+    }
+}
+
+fn find(categories: &[Category], id: i32) -> Option<&Category> {
+    categories.iter().find(|c| c.id == id)
+}
+
+/// Parent of a category, ignoring references to categories that no longer exist.
+fn parent_of(categories: &[Category], category: &Category) -> Option<i32> {
+    category.parent_id.filter(|p| find(categories, *p).is_some())
+}
+
+/// Categories in depth-first order together with their depth.
+pub fn tree_order(categories: &[Category]) -> Vec<CategoryOption> {
+    fn visit(
+        all: &[Category],
+        parent: Option<i32>,
+        depth: usize,
+        seen: &mut HashSet<i32>,
+        out: &mut Vec<CategoryOption>,
+    ) {
+        for c in all.iter().filter(|c| parent_of(all, c) == parent) {
+            if seen.insert(c.id) {
+                out.push(CategoryOption { category: c.clone(), depth });
+                visit(all, Some(c.id), depth + 1, seen, out);
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut out = Vec::with_capacity(categories.len());
+    visit(categories, None, 0, &mut seen, &mut out);
+    // Anything caught in a parent cycle is still listed, at the top level.
+    for c in categories {
+        if seen.insert(c.id) {
+            out.push(CategoryOption { category: c.clone(), depth: 0 });
+        }
+    }
+    out
+}
+
+/// Walks up from `category_id` and returns the ancestor (or the category itself) whose
+/// parent is `level`, where `None` is the top level. `None` if it is not below `level`.
+pub fn child_under(categories: &[Category], category_id: i32, level: Option<i32>) -> Option<i32> {
+    let mut current = find(categories, category_id)?;
+    for _ in 0..=categories.len() {
+        let parent = parent_of(categories, current);
+        if parent == level {
+            return Some(current.id);
+        }
+        current = find(categories, parent?)?;
+    }
+    None
+}
+
+/// The category and its parents, top level first. Empty if the category does not exist.
+pub fn ancestry(categories: &[Category], category_id: i32) -> Vec<&Category> {
+    let mut chain = Vec::new();
+    let mut current = find(categories, category_id);
+    while let Some(category) = current {
+        if chain.len() > categories.len() {
+            break;
+        }
+        chain.push(category);
+        current = parent_of(categories, category).and_then(|p| find(categories, p));
+    }
+    chain.reverse();
+    chain
+}
+
+pub fn has_children(categories: &[Category], category_id: i32) -> bool {
+    categories.iter().any(|c| parent_of(categories, c) == Some(category_id))
+}
+
+/// The category and everything below it.
+pub fn subtree(categories: &[Category], category_id: i32) -> HashSet<i32> {
+    let mut found = HashSet::from([category_id]);
+    loop {
+        let before = found.len();
+        for c in categories {
+            if c.parent_id.is_some_and(|p| found.contains(&p)) {
+                found.insert(c.id);
+            }
+        }
+        if found.len() == before {
+            return found;
+        }
+    }
+}
+
+/// Categories of one kind (`KIND_EXPENSE` or `KIND_INCOME`).
+pub fn of_kind(categories: &[Category], kind: &str) -> Vec<Category> {
+    categories.iter().filter(|c| c.kind == kind).cloned().collect()
+}
+
+/// Parses `#rrggbb` into RGB components in `0.0..=1.0`.
+pub fn parse_hex_color(input: &str) -> Option<[f32; 3]> {
+    let hex = input.trim().strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok().map(|v| v as f32 / 255.0);
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
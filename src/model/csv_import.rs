@@ -0,0 +1,344 @@
+use chrono::NaiveDate;
+use crate::model::import::{parse_statement_amount, ImportedRow};
+
+pub const DATE_FORMATS: [&str; 6] = ["%Y-%m-%d", "%d.%m.%Y", "%d/%m/%Y", "%m/%d/%Y", "%Y/%m/%d", "%d-%m-%Y"];
+pub const DECIMAL_SEPARATORS: [char; 2] = ['.', ','];
+pub const DELIMITERS: [Delimiter; 3] = [Delimiter::Comma, Delimiter::Semicolon, Delimiter::Tab];
+pub const SIGN_CONVENTIONS: [SignConvention; 2] = [SignConvention::NegativeIsExpense, SignConvention::PositiveIsExpense];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delimiter {
+    Comma,
+    Semicolon,
+    Tab,
+}
+
+impl Delimiter {
+    pub fn byte(self) -> u8 {
+        match self {
+            Delimiter::Comma => b',',
+            Delimiter::Semicolon => b';',
+            Delimiter::Tab => b'\t',
+        }
+    }
+}
+
+impl std::fmt::Display for Delimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Delimiter::Comma => write!(f, "Comma"),
+            Delimiter::Semicolon => write!(f, "Semicolon"),
+            Delimiter::Tab => write!(f, "Tab"),
+        }
+    }
+}
+
+/// How the statement signs its amounts. Bank accounts usually show payments as
+/// negative numbers; credit card statements often show them as positive charges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignConvention {
+    NegativeIsExpense,
+    PositiveIsExpense,
+}
+
+impl std::fmt::Display for SignConvention {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignConvention::NegativeIsExpense => write!(f, "Negative amounts are expenses"),
+            SignConvention::PositiveIsExpense => write!(f, "Positive amounts are expenses"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvField {
+    Date,
+    Amount,
+    Payee,
+    Comment,
+}
+
+/// A column as offered in the mapping pickers; `index` is `None` for "not in the file".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnChoice {
+    pub index: Option<usize>,
+    pub name: String,
+}
+
+impl std::fmt::Display for ColumnChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Which CSV column holds what, and how to read the values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvMapping {
+    pub delimiter: Delimiter,
+    pub has_header: bool,
+    pub date_column: Option<usize>,
+    pub amount_column: Option<usize>,
+    pub payee_column: Option<usize>,
+    pub comment_column: Option<usize>,
+    pub date_format: &'static str,
+    pub decimal: char,
+    pub sign: SignConvention,
+}
+
+impl Default for CsvMapping {
+    fn default() -> Self {
+        CsvMapping {
+            delimiter: Delimiter::Comma,
+            has_header: true,
+            date_column: None,
+            amount_column: None,
+            payee_column: None,
+            comment_column: None,
+            date_format: DATE_FORMATS[0],
+            decimal: '.',
+            sign: SignConvention::NegativeIsExpense,
+        }
+    }
+}
+
+impl CsvMapping {
+    pub fn column(&self, field: CsvField) -> Option<usize> {
+        match field {
+            CsvField::Date => self.date_column,
+            CsvField::Amount => self.amount_column,
+            CsvField::Payee => self.payee_column,
+            CsvField::Comment => self.comment_column,
+        }
+    }
+
+    pub fn set_column(&mut self, field: CsvField, column: Option<usize>) {
+        match field {
+            CsvField::Date => self.date_column = column,
+            CsvField::Amount => self.amount_column = column,
+            CsvField::Payee => self.payee_column = column,
+            CsvField::Comment => self.comment_column = column,
+        }
+    }
+}
+
+/// Splits the file into rows of fields. Rows may differ in length.
+pub fn read_csv(text: &str, delimiter: Delimiter) -> Result<Vec<Vec<String>>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(delimiter.byte())
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(text.trim_start_matches('\u{feff}').as_bytes());
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("CSV error: {}", e))?;
+        if record.iter().any(|f| !f.is_empty()) {
+            rows.push(record.iter().map(str::to_string).collect());
+        }
+    }
+    Ok(rows)
+}
+
+/// Guesses the delimiter from the first line: whichever of `;`, tab and `,` occurs most.
+pub fn detect_delimiter(text: &str) -> Delimiter {
+    let first = text.lines().next().unwrap_or("");
+    let count = |c: char| first.matches(c).count();
+    if count(';') > count(',') && count(';') >= count('\t') {
+        Delimiter::Semicolon
+    } else if count('\t') > count(',') {
+        Delimiter::Tab
+    } else {
+        Delimiter::Comma
+    }
+}
+
+/// The data rows with the header left out, numbered from 1 as rows of the file.
+/// Blank lines are not counted.
+pub fn data_rows<'a>(rows: &'a [Vec<String>], mapping: &CsvMapping) -> impl Iterator<Item = (usize, &'a Vec<String>)> {
+    let skip = usize::from(mapping.has_header);
+    rows.iter().enumerate().skip(skip).map(|(i, row)| (i + 1, row))
+}
+
+/// Reads one data row with the mapping. Rows with a zero amount are reported as errors
+/// so they show up in the preview instead of being booked as empty transactions.
+pub fn parse_row(row: &[String], mapping: &CsvMapping) -> Result<ImportedRow, String> {
+    let field = |column: Option<usize>, name: &str| -> Result<&str, String> {
+        let column = column.ok_or_else(|| format!("Choose the {} column", name))?;
+        row.get(column)
+            .map(String::as_str)
+            .ok_or_else(|| format!("No {} in column {}", name, column + 1))
+    };
+    let optional = |column: Option<usize>| {
+        column
+            .and_then(|c| row.get(c))
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    let date_text = field(mapping.date_column, "date")?;
+    let date = NaiveDate::parse_from_str(date_text.trim(), mapping.date_format)
+        .map_err(|_| format!("'{}' does not match the date format {}", date_text, mapping.date_format))?;
+    let mut amount = parse_statement_amount(field(mapping.amount_column, "amount")?, mapping.decimal)?;
+    if amount.is_zero() {
+        return Err("Amount is zero".into());
+    }
+    if mapping.sign == SignConvention::PositiveIsExpense {
+        amount = -amount;
+    }
+    let payee = optional(mapping.payee_column).unwrap_or_else(|| "Imported".to_string());
+    Ok(ImportedRow {
+        date,
+        amount,
+        payee,
+        comment: optional(mapping.comment_column),
+        category: None,
+        splits: Vec::new(),
+        external_id: None,
+        currency: None,
+    })
+}
+
+/// Every data row parsed with the mapping, with the file's line number for error messages.
+pub fn parse_rows(rows: &[Vec<String>], mapping: &CsvMapping) -> Vec<(usize, Result<ImportedRow, String>)> {
+    data_rows(rows, mapping)
+        .map(|(line, row)| (line, parse_row(row, mapping)))
+        .collect()
+}
+
+/// Choices for the mapping pickers: "(none)", then each column by its header value,
+/// or as "Column N" when the file has no header.
+pub fn column_choices(rows: &[Vec<String>], has_header: bool) -> Vec<ColumnChoice> {
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let columns = (0..width).map(|i| ColumnChoice {
+        index: Some(i),
+        name: match rows.first().and_then(|r| r.get(i)) {
+            Some(name) if has_header && !name.is_empty() => format!("{}: {}", i + 1, name),
+            _ => format!("Column {}", i + 1),
+        },
+    });
+    std::iter::once(ColumnChoice { index: None, name: "(none)".into() })
+        .chain(columns)
+        .collect()
+}
+
+/// Picks columns whose header names look like a date, amount, payee and comment.
+pub fn guess_columns(rows: &[Vec<String>], mapping: &mut CsvMapping) {
+    let header = match rows.first() {
+        Some(header) if mapping.has_header => header,
+        _ => return,
+    };
+    let find = |names: &[&str]| {
+        header.iter().position(|h| {
+            let h = h.to_lowercase();
+            names.iter().any(|n| h.contains(n))
+        })
+    };
+    mapping.date_column = find(&["date", "datum", "booking"]);
+    mapping.amount_column = find(&["amount", "betrag", "value", "sum"]);
+    mapping.payee_column = find(&["payee", "name", "merchant", "counterparty", "description", "empfänger", "auftraggeber"]);
+    mapping.comment_column = find(&["memo", "comment", "note", "reference", "purpose", "verwendungszweck"])
+        .filter(|c| Some(*c) != mapping.payee_column);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Money;
+
+    fn mapping() -> CsvMapping {
+        CsvMapping {
+            date_column: Some(0),
+            amount_column: Some(1),
+            payee_column: Some(2),
+            comment_column: Some(3),
+            ..CsvMapping::default()
+        }
+    }
+
+    fn row(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn detects_the_delimiter_from_the_first_line() {
+        assert_eq!(detect_delimiter("Date,Amount,Payee\n1;2;3"), Delimiter::Comma);
+        assert_eq!(detect_delimiter("Datum;Betrag;Empfänger\n01.02.2026;-1,50;Bäcker"), Delimiter::Semicolon);
+        assert_eq!(detect_delimiter("Date\tAmount\tPayee"), Delimiter::Tab);
+        assert_eq!(detect_delimiter(""), Delimiter::Comma);
+    }
+
+    #[test]
+    fn strips_the_byte_order_mark() {
+        let text = "\u{feff}Date;Amount;Payee\n2026-02-01;-4,20;Bakery\n";
+        let rows = read_csv(text, detect_delimiter(text)).unwrap();
+        assert_eq!(rows[0][0], "Date");
+        let mut mapping = CsvMapping { delimiter: Delimiter::Semicolon, ..CsvMapping::default() };
+        guess_columns(&rows, &mut mapping);
+        assert_eq!(mapping.date_column, Some(0));
+    }
+
+    #[test]
+    fn guesses_columns_from_the_header() {
+        let rows = vec![row(&["Buchungstag Datum", "Empfänger", "Verwendungszweck", "Betrag"])];
+        let mut mapping = CsvMapping::default();
+        guess_columns(&rows, &mut mapping);
+        assert_eq!(mapping.date_column, Some(0));
+        assert_eq!(mapping.payee_column, Some(1));
+        assert_eq!(mapping.comment_column, Some(2));
+        assert_eq!(mapping.amount_column, Some(3));
+    }
+
+    #[test]
+    fn guesses_nothing_without_a_header() {
+        let rows = vec![row(&["2026-02-01", "-4.20", "Bakery"])];
+        let mut mapping = CsvMapping { has_header: false, ..CsvMapping::default() };
+        guess_columns(&rows, &mut mapping);
+        assert_eq!(mapping, CsvMapping { has_header: false, ..CsvMapping::default() });
+    }
+
+    #[test]
+    fn parses_a_row() {
+        let parsed = parse_row(&row(&["2026-02-01", "-4.20", "Bakery", "Rolls"]), &mapping()).unwrap();
+        assert_eq!(parsed.date, NaiveDate::from_ymd_opt(2026, 2, 1).unwrap());
+        assert_eq!(parsed.amount, Money::from_minor(-420));
+        assert_eq!(parsed.payee, "Bakery");
+        assert_eq!(parsed.comment.as_deref(), Some("Rolls"));
+        assert_eq!(parsed.kind(), "Expense");
+    }
+
+    #[test]
+    fn parses_a_row_with_decimal_comma() {
+        let mapping = CsvMapping { decimal: ',', date_format: "%d.%m.%Y", ..mapping() };
+        let parsed = parse_row(&row(&["01.02.2026", "1.250,00", "Employer", ""]), &mapping).unwrap();
+        assert_eq!(parsed.amount, Money::from_minor(125000));
+        assert_eq!(parsed.comment, None);
+        assert_eq!(parsed.kind(), "Income");
+    }
+
+    #[test]
+    fn flips_positive_charges() {
+        let mapping = CsvMapping { sign: SignConvention::PositiveIsExpense, ..mapping() };
+        let charge = parse_row(&row(&["2026-02-01", "30.00", "Shop"]), &mapping).unwrap();
+        assert_eq!(charge.amount, Money::from_minor(-3000));
+        let refund = parse_row(&row(&["2026-02-01", "(30.00)", "Shop"]), &mapping).unwrap();
+        assert_eq!(refund.amount, Money::from_minor(3000));
+    }
+
+    #[test]
+    fn reports_bad_rows() {
+        assert!(parse_row(&row(&["2026-02-01", "0.00", "Shop"]), &mapping()).is_err());
+        assert!(parse_row(&row(&["02/01/2026", "1.00", "Shop"]), &mapping()).is_err());
+        assert!(parse_row(&row(&["2026-02-01"]), &mapping()).is_err());
+        let unmapped = CsvMapping { amount_column: None, ..mapping() };
+        assert_eq!(
+            parse_row(&row(&["2026-02-01", "1.00"]), &unmapped).err().as_deref(),
+            Some("Choose the amount column")
+        );
+    }
+
+    #[test]
+    fn payee_defaults_when_missing() {
+        let parsed = parse_row(&row(&["2026-02-01", "5.00", " "]), &mapping()).unwrap();
+        assert_eq!(parsed.payee, "Imported");
+    }
+}
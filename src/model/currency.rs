@@ -0,0 +1,266 @@
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use crate::model::Money;
+use crate::schema::exchange_rates;
+
+pub const DEFAULT_CURRENCY: &str = "EUR";
+pub const CURRENCIES: [&str; 6] = ["EUR", "CZK", "USD", "GBP", "PLN", "CHF"];
+
+const MICROS: i128 = 1_000_000;
+
+/// One unit of `currency` is worth `rate_micros / 1_000_000` units of `quote_currency`
+/// on `rate_date`.
+#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
+pub struct ExchangeRate {
+    pub id: i32,
+    pub rate_date: NaiveDate,
+    pub currency: String,
+    pub quote_currency: String,
+    pub rate_micros: i64,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = exchange_rates)]
+pub struct NewExchangeRate {
+    pub rate_date: NaiveDate,
+    pub currency: String,
+    pub quote_currency: String,
+    pub rate_micros: i64,
+}
+
+/// In-memory lookup over the locally stored exchange rates.
+#[derive(Debug, Clone, Default)]
+pub struct RateTable {
+    // (currency, quote) -> rates sorted by date
+    rates: HashMap<(String, String), Vec<(NaiveDate, i64)>>,
+}
+
+impl RateTable {
+    pub fn new(rates: &[ExchangeRate]) -> Self {
+        let mut table: HashMap<(String, String), Vec<(NaiveDate, i64)>> = HashMap::new();
+        for r in rates {
+            table
+                .entry((r.currency.clone(), r.quote_currency.clone()))
+                .or_default()
+                .push((r.rate_date, r.rate_micros));
+        }
+        for series in table.values_mut() {
+            series.sort_by_key(|(d, _)| *d);
+        }
+        RateTable { rates: table }
+    }
+
+    /// Rate valid on `on`: the latest one published on or before that date,
+    /// falling back to the earliest known rate for older transactions.
+    fn direct(&self, from: &str, to: &str, on: NaiveDate) -> Option<i64> {
+        let series = self.rates.get(&(from.to_string(), to.to_string()))?;
+        series
+            .iter()
+            .rev()
+            .find(|(d, _)| *d <= on)
+            .or_else(|| series.first())
+            .map(|(_, r)| *r)
+    }
+
+    fn convert_one_step(&self, amount: i128, from: &str, to: &str, on: NaiveDate) -> Option<i128> {
+        if let Some(rate) = self.direct(from, to, on) {
+            return Some(round_div(amount * rate as i128, MICROS));
+        }
+        self.direct(to, from, on)
+            .map(|rate| round_div(amount * MICROS, rate as i128))
+    }
+
+    /// Converts `amount` from one currency to another as of the given date. When there is
+    /// no direct rate, a single intermediate currency is tried (e.g. CZK -> EUR -> USD).
+    pub fn convert(&self, amount: Money, from: &str, to: &str, on: NaiveDate) -> Option<Money> {
+        if from == to {
+            return Some(amount);
+        }
+        let minor = amount.minor() as i128;
+        if let Some(v) = self.convert_one_step(minor, from, to, on) {
+            return i64::try_from(v).ok().map(Money::from_minor);
+        }
+
+        let mut via: Vec<&str> = self
+            .rates
+            .keys()
+            .flat_map(|(a, b)| [a.as_str(), b.as_str()])
+            .filter(|c| *c != from && *c != to)
+            .collect();
+        via.sort_unstable();
+        via.dedup();
+
+        via.into_iter()
+            .find_map(|mid| {
+                let step = self.convert_one_step(minor, from, mid, on)?;
+                self.convert_one_step(step, mid, to, on)
+            })
+            .and_then(|v| i64::try_from(v).ok())
+            .map(Money::from_minor)
+    }
+}
+
+fn round_div(n: i128, d: i128) -> i128 {
+    let half = d / 2;
+    if n >= 0 {
+        (n + half) / d
+    } else {
+        (n - half) / d
+    }
+}
+
+/// Parses a positive decimal rate such as `25.123` into millionths.
+pub fn parse_rate(input: &str) -> Result<i64, String> {
+    let s = input.trim().replace(',', ".");
+    let (whole, fraction) = s.split_once('.').unwrap_or((s.as_str(), ""));
+    let valid = |p: &str| p.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !valid(whole) || !valid(fraction) {
+        return Err(format!("'{}' is not a valid rate", input.trim()));
+    }
+    if fraction.len() > 6 {
+        return Err(format!("Rate '{}' has more than six decimal places", input.trim()));
+    }
+    let whole_val: i64 = if whole.is_empty() { 0 } else {
+        whole.parse().map_err(|_| format!("Rate '{}' is too large", input.trim()))?
+    };
+    let fraction_val: i64 = format!("{:0<6}", fraction).parse().unwrap_or(0);
+    let micros = whole_val
+        .checked_mul(MICROS as i64)
+        .and_then(|v| v.checked_add(fraction_val))
+        .ok_or_else(|| format!("Rate '{}' is too large", input.trim()))?;
+    if micros <= 0 {
+        return Err("Exchange rates must be greater than zero".into());
+    }
+    Ok(micros)
+}
+
+pub fn format_rate(micros: i64) -> String {
+    format!("{}.{:06}", micros / MICROS as i64, micros % MICROS as i64)
+}
+
+pub fn is_currency_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase())
+}
+
+/// Reads `date,currency,quote_currency,rate` lines, e.g. `2025-03-01,EUR,CZK,25.02`.
+/// A header line is allowed.
+pub fn parse_rates_csv<R: Read>(reader: R) -> Result<Vec<NewExchangeRate>, String> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(reader);
+
+    let mut out = Vec::new();
+    for (line, record) in csv_reader.records().enumerate() {
+        let record = record.map_err(|e| format!("CSV error: {}", e))?;
+        if record.iter().all(|f| f.is_empty()) {
+            continue;
+        }
+        if record.len() < 4 {
+            return Err(format!("Line {}: expected date,currency,quote_currency,rate", line + 1));
+        }
+
+        let rate_date = match NaiveDate::parse_from_str(&record[0], "%Y-%m-%d") {
+            Ok(d) => d,
+            Err(_) if line == 0 => continue, // header
+            Err(_) => return Err(format!("Line {}: invalid date '{}'", line + 1, &record[0])),
+        };
+        let currency = record[1].to_uppercase();
+        let quote_currency = record[2].to_uppercase();
+        if !is_currency_code(&currency) || !is_currency_code(&quote_currency) {
+            return Err(format!("Line {}: invalid currency code", line + 1));
+        }
+        let rate_micros = parse_rate(&record[3]).map_err(|e| format!("Line {}: {}", line + 1, e))?;
+
+        out.push(NewExchangeRate { rate_date, currency, quote_currency, rate_micros });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn table(rates: &[(&str, &str, &str, i64)]) -> RateTable {
+        let rates: Vec<ExchangeRate> = rates
+            .iter()
+            .enumerate()
+            .map(|(i, (day, currency, quote, micros))| ExchangeRate {
+                id: i as i32,
+                rate_date: date(day),
+                currency: currency.to_string(),
+                quote_currency: quote.to_string(),
+                rate_micros: *micros,
+            })
+            .collect();
+        RateTable::new(&rates)
+    }
+
+    fn rates() -> RateTable {
+        table(&[
+            ("2025-03-01", "EUR", "CZK", 25_500_000),
+            ("2025-01-01", "EUR", "CZK", 25_020_000),
+            ("2025-01-01", "EUR", "USD", 1_080_000),
+        ])
+    }
+
+    fn convert(table: &RateTable, minor: i64, from: &str, to: &str, on: &str) -> Option<i64> {
+        table.convert(Money::from_minor(minor), from, to, date(on)).map(Money::minor)
+    }
+
+    #[test]
+    fn same_currency_needs_no_rate() {
+        let empty = RateTable::default();
+        assert_eq!(convert(&empty, 1_234, "GBP", "GBP", "2025-01-01"), Some(1_234));
+        assert_eq!(convert(&empty, -5, "EUR", "EUR", "2025-01-01"), Some(-5));
+    }
+
+    #[test]
+    fn missing_rates_give_nothing() {
+        assert_eq!(convert(&RateTable::default(), 100, "EUR", "CZK", "2025-01-01"), None);
+        assert_eq!(convert(&rates(), 100, "EUR", "PLN", "2025-01-01"), None);
+        assert_eq!(convert(&rates(), 100, "GBP", "CZK", "2025-01-01"), None);
+    }
+
+    #[test]
+    fn uses_the_rate_valid_on_the_date() {
+        let rates = rates();
+        assert_eq!(convert(&rates, 1_000, "EUR", "CZK", "2025-02-15"), Some(25_020));
+        assert_eq!(convert(&rates, 1_000, "EUR", "CZK", "2025-03-01"), Some(25_500));
+        // Older than every stored rate: the earliest one is used.
+        assert_eq!(convert(&rates, 1_000, "EUR", "CZK", "2024-06-30"), Some(25_020));
+    }
+
+    #[test]
+    fn converts_backwards_and_through_another_currency() {
+        let rates = rates();
+        // 100 CZK / 25.02 = 3.9968 EUR
+        assert_eq!(convert(&rates, 10_000, "CZK", "EUR", "2025-02-01"), Some(400));
+        // 4.00 EUR * 1.08 = 4.32 USD
+        assert_eq!(convert(&rates, 10_000, "CZK", "USD", "2025-02-01"), Some(432));
+    }
+
+    #[test]
+    fn rounds_half_away_from_zero_to_minor_units() {
+        let half = table(&[("2025-01-01", "EUR", "CZK", 24_500_000)]);
+        assert_eq!(convert(&half, 1, "EUR", "CZK", "2025-01-01"), Some(25));
+        assert_eq!(convert(&half, -1, "EUR", "CZK", "2025-01-01"), Some(-25));
+
+        let below_half = table(&[("2025-01-01", "EUR", "CZK", 24_499_999)]);
+        assert_eq!(convert(&below_half, 1, "EUR", "CZK", "2025-01-01"), Some(24));
+        assert_eq!(convert(&below_half, -1, "EUR", "CZK", "2025-01-01"), Some(-24));
+    }
+
+    #[test]
+    fn results_too_large_for_money_give_nothing() {
+        assert_eq!(convert(&rates(), i64::MAX, "EUR", "CZK", "2025-01-01"), None);
+    }
+}
@@ -1,11 +1,905 @@
-use diesel::r2d2::{ConnectionManager, Pool};
-use diesel::PgConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool, PooledConnection};
+use diesel::result::Error as DieselError;
+use diesel::connection::SimpleConnection;
+use diesel::backend::Backend as DieselBackend;
+use diesel::{PgConnection, SqliteConnection};
+use diesel::migration::MigrationSource;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use std::env;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard};
 
-pub type DbPool = Pool<ConnectionManager<PgConnection>>;
+use crate::model::{Account, Attachment, AuditEntry, AuditFilter, Budget, Category, Envelope, EnvelopeMove, NewAccount, NewAttachment, NewBudget, NewAuditEntry, NewCategory, NewEnvelope, NewEnvelopeMove, ExchangeRate, NewExchangeRate, NewGoalContribution, NewSavingsGoal, GoalContribution, SavingsGoal, Loan, LoanPayment, NewLoan, NewLoanPayment, NewRecurringRule, NewSplit, NewTransaction, NewUser, RecurringRule, Repository, SplitLine, Transaction, TransactionDraft};
+use crate::model::category::subtree;
+use crate::model::user::User;
+
+pub type DbPool = Arc<dyn Repository>;
+
+const DEFAULT_SQLITE_FILE: &str = "finance_manager.db";
+
+const PG_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/postgres");
+const SQLITE_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/sqlite");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Backend {
+    Postgres(String),
+    Sqlite(String),
+}
+
+impl Backend {
+    /// `postgres://` / `postgresql://` URLs select Postgres. Anything else is treated as a
+    /// SQLite file path (an optional `sqlite://` prefix is stripped). Without
+    /// `DATABASE_URL` the app keeps its ledger in a local SQLite file.
+    pub fn from_env() -> Self {
+        match env::var("DATABASE_URL") {
+            Ok(url) => Self::from_url(&url),
+            Err(_) => Backend::Sqlite(DEFAULT_SQLITE_FILE.to_string()),
+        }
+    }
+
+    pub fn from_url(url: &str) -> Self {
+        if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+            Backend::Postgres(url.to_string())
+        } else {
+            let path = url.strip_prefix("sqlite://").unwrap_or(url);
+            Backend::Sqlite(path.to_string())
+        }
+    }
+}
+
+pub struct PgRepository {
+    pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+pub struct SqliteRepository {
+    pool: Pool<ConnectionManager<SqliteConnection>>,
+}
+
+/// The repository handed to the work of `Repository::transaction`. It runs every query on
+/// the one connection that holds the open database transaction.
+pub struct TransactionRepository<'c, C> {
+    conn: Mutex<&'c mut C>,
+}
+
+/// The locked connection of a `TransactionRepository`.
+struct ConnGuard<'g, 'c, C>(MutexGuard<'g, &'c mut C>);
+
+impl<C> Deref for ConnGuard<'_, '_, C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.0
+    }
+}
+
+impl<C> DerefMut for ConnGuard<'_, '_, C> {
+    fn deref_mut(&mut self) -> &mut C {
+        &mut self.0
+    }
+}
+
+#[derive(Debug)]
+struct SqlitePragmas;
+
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for SqlitePragmas {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+        conn.batch_execute("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+/// Brings the configured database up to the schema this binary was built with.
+/// Must run before `create_pool`; a database migrated by a newer build is rejected
+/// rather than being read with an outdated schema.
+pub fn run_migrations() -> Result<(), String> {
+    match Backend::from_env() {
+        Backend::Postgres(url) => {
+            let mut conn = PgConnection::establish(&url)
+                .map_err(|e| format!("DB connection error: {:?}", e))?;
+            apply_migrations(&mut conn, PG_MIGRATIONS)
+        }
+        Backend::Sqlite(path) => {
+            let mut conn = SqliteConnection::establish(&path)
+                .map_err(|e| format!("DB connection error: {:?}", e))?;
+            apply_migrations(&mut conn, SQLITE_MIGRATIONS)
+        }
+    }
+}
+
+fn apply_migrations<DB: DieselBackend>(
+    conn: &mut impl MigrationHarness<DB>,
+    source: EmbeddedMigrations,
+) -> Result<(), String> {
+    let known = MigrationSource::<DB>::migrations(&source)
+        .map_err(|e| format!("Migration error: {}", e))?;
+    let latest_known = known.iter().map(|m| m.name().version()).max();
+    let applied = conn
+        .applied_migrations()
+        .map_err(|e| format!("Migration error: {}", e))?;
+
+    if let Some(newest) = applied.iter().max() {
+        if latest_known.as_ref().is_none_or(|latest| newest > latest) {
+            return Err(format!(
+                "Database schema version {} is newer than this build supports ({}). Please update the application.",
+                newest,
+                latest_known.map(|v| v.to_string()).unwrap_or_else(|| "none".into()),
+            ));
+        }
+    }
+
+    conn.run_pending_migrations(source)
+        .map_err(|e| format!("Migration error: {}", e))?;
+    Ok(())
+}
 
 pub fn create_pool() -> DbPool {
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
-    let manager = ConnectionManager::<PgConnection>::new(database_url);
-    Pool::builder().build(manager).expect("Failed to create pool")
+    match Backend::from_env() {
+        Backend::Postgres(url) => {
+            let manager = ConnectionManager::<PgConnection>::new(url);
+            let pool = Pool::builder().build(manager).expect("Failed to create pool");
+            Arc::new(PgRepository { pool })
+        }
+        Backend::Sqlite(path) => {
+            let manager = ConnectionManager::<SqliteConnection>::new(path);
+            let pool = Pool::builder()
+                .connection_customizer(Box::new(SqlitePragmas))
+                .build(manager)
+                .expect("Failed to create pool");
+            Arc::new(SqliteRepository { pool })
+        }
+    }
+}
+
+// Both backends run the same diesel queries; only the connection type differs. Each
+// backend gets the queries twice: on pooled connections, and on the connection of an open
+// database transaction.
+macro_rules! impl_repository {
+    ($repo:ty, $conn:ty) => {
+        impl $repo {
+            fn conn(&self) -> QueryResult<PooledConnection<ConnectionManager<$conn>>> {
+                self.pool
+                    .get()
+                    .map_err(|e| DieselError::QueryBuilderError(Box::new(e)))
+            }
+        }
+
+        impl<'c> TransactionRepository<'c, $conn> {
+            fn conn(&self) -> QueryResult<ConnGuard<'_, 'c, $conn>> {
+                self.conn
+                    .lock()
+                    .map(ConnGuard)
+                    .map_err(|_| DieselError::BrokenTransactionManager)
+            }
+        }
+
+        impl_repository!(@queries [] $repo, $conn);
+        impl_repository!(@queries ['c] TransactionRepository<'c, $conn>, $conn);
+    };
+    (@queries [$($lt:lifetime)?] $repo:ty, $conn:ty) => {
+        impl<$($lt)?> $repo {
+            /// Fills in the split lines of the loaded transactions.
+            fn with_splits(conn: &mut $conn, mut txs: Vec<Transaction>) -> QueryResult<Vec<Transaction>> {
+                use crate::schema::transaction_splits::dsl::*;
+                let ids: Vec<i32> = txs.iter().map(|t| t.tran_id).collect();
+                let lines = transaction_splits
+                    .filter(transaction_id.eq_any(&ids))
+                    .order(id.asc())
+                    .select((transaction_id, tag_id, amount))
+                    .load::<(i32, Option<i32>, crate::model::Money)>(conn)?;
+                for (tx_id, tag, line_amount) in lines {
+                    if let Some(tx) = txs.iter_mut().find(|t| t.tran_id == tx_id) {
+                        tx.splits.push(SplitLine { tag_id: tag, amount: line_amount });
+                    }
+                }
+                Ok(txs)
+            }
+
+            fn replace_splits(conn: &mut $conn, tx_id: i32, lines: &[SplitLine]) -> QueryResult<()> {
+                use crate::schema::transaction_splits::dsl::*;
+                diesel::delete(transaction_splits.filter(transaction_id.eq(tx_id))).execute(conn)?;
+                if !lines.is_empty() {
+                    diesel::insert_into(transaction_splits)
+                        .values(NewSplit::for_transaction(tx_id, lines))
+                        .execute(conn)?;
+                }
+                Ok(())
+            }
+        }
+
+        impl<$($lt)?> Repository for $repo {
+            fn transaction(&self, work: &mut dyn FnMut(&dyn Repository) -> QueryResult<()>) -> QueryResult<()> {
+                let mut guard = self.conn()?;
+                let conn: &mut $conn = &mut guard;
+                // Inside an open transaction diesel makes this a savepoint.
+                conn.transaction(|conn| {
+                    let repo = TransactionRepository { conn: Mutex::new(conn) };
+                    work(&repo)
+                })
+            }
+
+            fn find_user_by_name(&self, name: &str) -> QueryResult<User> {
+                use crate::schema::users::dsl::*;
+                let mut conn = self.conn()?;
+                users.filter(username.eq(name)).first(&mut *conn)
+            }
+
+            fn find_user_by_id(&self, uid: i32) -> QueryResult<User> {
+                use crate::schema::users::dsl::*;
+                let mut conn = self.conn()?;
+                users.filter(id.eq(uid)).first(&mut *conn)
+            }
+
+            fn insert_user(&self, new_user: &NewUser) -> QueryResult<()> {
+                let mut conn = self.conn()?;
+                diesel::insert_into(crate::schema::users::table)
+                    .values(new_user)
+                    .execute(&mut *conn)?;
+                Ok(())
+            }
+
+            fn update_password(&self, uid: i32, hashed: &str) -> QueryResult<()> {
+                use crate::schema::users::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::update(users.filter(id.eq(uid)))
+                    .set(password.eq(hashed))
+                    .execute(&mut *conn)?;
+                Ok(())
+            }
+
+            fn set_base_currency(&self, uid: i32, code: &str) -> QueryResult<()> {
+                use crate::schema::users::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::update(users.filter(id.eq(uid)))
+                    .set(base_currency.eq(code))
+                    .execute(&mut *conn)?;
+                Ok(())
+            }
+
+            fn set_envelope_mode(&self, uid: i32, enabled: bool, start: chrono::NaiveDate) -> QueryResult<()> {
+                use crate::schema::users::dsl::*;
+                let mut conn = self.conn()?;
+                conn.transaction(|conn| {
+                    diesel::update(users.filter(id.eq(uid)))
+                        .set(envelope_mode.eq(enabled))
+                        .execute(conn)?;
+                    diesel::update(users.filter(id.eq(uid)).filter(envelope_start.is_null()))
+                        .set(envelope_start.eq(Some(start)))
+                        .execute(conn)?;
+                    Ok(())
+                })
+            }
+
+            fn load_accounts(&self, uid: i32) -> QueryResult<Vec<Account>> {
+                use crate::schema::accounts::dsl::*;
+                let mut conn = self.conn()?;
+                accounts
+                    .filter(user_id.eq(uid))
+                    .order(id.asc())
+                    .load::<Account>(&mut *conn)
+            }
+
+            fn insert_account(&self, new_account: &NewAccount) -> QueryResult<()> {
+                let mut conn = self.conn()?;
+                diesel::insert_into(crate::schema::accounts::table)
+                    .values(new_account)
+                    .execute(&mut *conn)?;
+                Ok(())
+            }
+
+            fn load_transactions(&self, uid: i32) -> QueryResult<Vec<Transaction>> {
+                use crate::schema::transactions::dsl::*;
+                let mut conn = self.conn()?;
+                let txs = transactions
+                    .filter(user_id.eq(uid))
+                    .filter(deleted_at.is_null())
+                    .order(date.desc())
+                    .load::<Transaction>(&mut *conn)?;
+                Self::with_splits(&mut *conn, txs)
+            }
+
+            fn find_transaction(&self, uid: i32, tx_id: i32) -> QueryResult<Transaction> {
+                use crate::schema::transactions::dsl::*;
+                let mut conn = self.conn()?;
+                let tx = transactions
+                    .filter(tran_id.eq(tx_id))
+                    .filter(user_id.eq(uid))
+                    .filter(deleted_at.is_null())
+                    .first::<Transaction>(&mut *conn)?;
+                Self::with_splits(&mut *conn, vec![tx]).map(|mut txs| txs.remove(0))
+            }
+
+            fn insert_transaction(&self, new_tx: &NewTransaction, splits: &[SplitLine]) -> QueryResult<Transaction> {
+                use crate::schema::transactions::dsl::*;
+                let mut conn = self.conn()?;
+                conn.transaction(|conn| {
+                    // RETURNING gives this insert's own row even while other inserts run.
+                    let mut tx = diesel::insert_into(transactions)
+                        .values(new_tx)
+                        .get_result::<Transaction>(conn)?;
+                    Self::replace_splits(conn, tx.tran_id, splits)?;
+                    tx.splits = splits.to_vec();
+                    Ok(tx)
+                })
+            }
+
+            fn load_external_ids(&self, uid: i32) -> QueryResult<Vec<String>> {
+                use crate::schema::transactions::dsl::*;
+                let mut conn = self.conn()?;
+                let ids = transactions
+                    .filter(user_id.eq(uid))
+                    .select(external_id)
+                    .load::<Option<String>>(&mut *conn)?;
+                Ok(ids.into_iter().flatten().collect())
+            }
+
+            fn update_transaction(&self, uid: i32, tx_id: i32, draft: &TransactionDraft) -> QueryResult<usize> {
+                use crate::schema::transactions::dsl::*;
+                let mut conn = self.conn()?;
+                conn.transaction(|conn| {
+                    let updated = diesel::update(
+                        transactions
+                            .filter(tran_id.eq(tx_id))
+                            .filter(user_id.eq(uid))
+                            .filter(deleted_at.is_null()),
+                    )
+                    .set((
+                        tran_source.eq(&draft.source),
+                        date.eq(draft.date),
+                        tran_amount.eq(draft.amount),
+                        currency.eq(&draft.currency),
+                        account_id.eq(draft.account_id),
+                        tag_id.eq(draft.tag_id),
+                        tran_comment.eq(draft.comment.as_deref()),
+                    ))
+                    .execute(conn)?;
+                    if updated > 0 {
+                        Self::replace_splits(conn, tx_id, &draft.splits)?;
+                    }
+                    Ok(updated)
+                })
+            }
+
+            fn delete_transaction(&self, uid: i32, tx_id: i32) -> QueryResult<usize> {
+                use crate::schema::transactions::dsl::*;
+                let mut conn = self.conn()?;
+                let now = chrono::Local::now().naive_local();
+                diesel::update(
+                    transactions
+                        .filter(tran_id.eq(tx_id))
+                        .filter(user_id.eq(uid))
+                        .filter(deleted_at.is_null()),
+                )
+                .set(deleted_at.eq(Some(now)))
+                .execute(&mut *conn)
+            }
+
+            fn load_trash(&self, uid: i32) -> QueryResult<Vec<Transaction>> {
+                use crate::schema::transactions::dsl::*;
+                let mut conn = self.conn()?;
+                let txs = transactions
+                    .filter(user_id.eq(uid))
+                    .filter(deleted_at.is_not_null())
+                    .order(deleted_at.desc())
+                    .load::<Transaction>(&mut *conn)?;
+                Self::with_splits(&mut *conn, txs)
+            }
+
+            fn restore_transaction(&self, uid: i32, tx_id: i32) -> QueryResult<usize> {
+                use crate::schema::transactions::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::update(
+                    transactions
+                        .filter(tran_id.eq(tx_id))
+                        .filter(user_id.eq(uid))
+                        .filter(deleted_at.is_not_null()),
+                )
+                .set(deleted_at.eq(None::<chrono::NaiveDateTime>))
+                .execute(&mut *conn)
+            }
+
+            fn purge_transaction(&self, uid: i32, tx_id: i32) -> QueryResult<usize> {
+                use crate::schema::transactions::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::delete(
+                    transactions
+                        .filter(tran_id.eq(tx_id))
+                        .filter(user_id.eq(uid))
+                        .filter(deleted_at.is_not_null()),
+                )
+                .execute(&mut *conn)
+            }
+
+            fn purge_trash(&self, uid: i32, before: Option<chrono::NaiveDateTime>) -> QueryResult<usize> {
+                use crate::schema::transactions::dsl::*;
+                let mut conn = self.conn()?;
+                let trashed = transactions
+                    .filter(user_id.eq(uid))
+                    .filter(deleted_at.is_not_null());
+                match before {
+                    Some(cutoff) => diesel::delete(trashed.filter(deleted_at.lt(cutoff))).execute(&mut *conn),
+                    None => diesel::delete(trashed).execute(&mut *conn),
+                }
+            }
+
+            fn load_categories(&self, uid: i32) -> QueryResult<Vec<Category>> {
+                use crate::schema::expense_tags::dsl::*;
+                let mut conn = self.conn()?;
+                expense_tags
+                    .filter(user_id.eq(uid))
+                    .order(id.asc())
+                    .load::<Category>(&mut *conn)
+            }
+
+            fn load_default_categories(&self) -> QueryResult<Vec<Category>> {
+                use crate::schema::expense_tags::dsl::*;
+                let mut conn = self.conn()?;
+                expense_tags
+                    .filter(user_id.is_null())
+                    .order(id.asc())
+                    .load::<Category>(&mut *conn)
+            }
+
+            fn insert_categories(&self, new_categories: &[NewCategory]) -> QueryResult<()> {
+                let mut conn = self.conn()?;
+                conn.transaction(|conn| {
+                    for category in new_categories {
+                        diesel::insert_into(crate::schema::expense_tags::table)
+                            .values(category)
+                            .execute(conn)?;
+                    }
+                    Ok(())
+                })
+            }
+
+            fn update_category(&self, uid: i32, category_id: i32, new_name: &str, new_color: &str, parent: Option<i32>) -> QueryResult<usize> {
+                use crate::schema::expense_tags::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::update(expense_tags.filter(id.eq(category_id)).filter(user_id.eq(uid)))
+                    .set((name.eq(new_name), color.eq(new_color), parent_id.eq(parent)))
+                    .execute(&mut *conn)
+            }
+
+            fn reassign_and_delete_category(&self, uid: i32, category_id: i32, target: Option<i32>) -> QueryResult<()> {
+                use crate::schema::{budgets, envelopes, expense_tags, loans, recurring_rules, transaction_splits, transactions};
+                let mut conn = self.conn()?;
+                conn.transaction(|conn| {
+                    diesel::update(
+                        transactions::table
+                            .filter(transactions::user_id.eq(uid))
+                            .filter(transactions::tag_id.eq(category_id)),
+                    )
+                    .set(transactions::tag_id.eq(target))
+                    .execute(conn)?;
+                    let own_transactions = transactions::table
+                        .filter(transactions::user_id.eq(uid))
+                        .select(transactions::tran_id);
+                    diesel::update(
+                        transaction_splits::table
+                            .filter(transaction_splits::transaction_id.eq_any(own_transactions))
+                            .filter(transaction_splits::tag_id.eq(category_id)),
+                    )
+                    .set(transaction_splits::tag_id.eq(target))
+                    .execute(conn)?;
+                    diesel::update(
+                        recurring_rules::table
+                            .filter(recurring_rules::user_id.eq(uid))
+                            .filter(recurring_rules::tag_id.eq(category_id)),
+                    )
+                    .set(recurring_rules::tag_id.eq(target))
+                    .execute(conn)?;
+                    diesel::update(
+                        loans::table
+                            .filter(loans::user_id.eq(uid))
+                            .filter(loans::interest_tag_id.eq(category_id)),
+                    )
+                    .set(loans::interest_tag_id.eq(target))
+                    .execute(conn)?;
+
+                    // A budget follows its spending to the target; if the target has its own,
+                    // the limits add up. Without a target the budget goes with the category.
+                    if let Some(target) = target {
+                        let moving: Option<Budget> = budgets::table
+                            .filter(budgets::user_id.eq(uid))
+                            .filter(budgets::tag_id.eq(category_id))
+                            .first(conn)
+                            .optional()?;
+                        let existing: Option<Budget> = budgets::table
+                            .filter(budgets::user_id.eq(uid))
+                            .filter(budgets::tag_id.eq(target))
+                            .first(conn)
+                            .optional()?;
+                        match (moving, existing) {
+                            (Some(moving), Some(existing)) => {
+                                diesel::update(budgets::table.filter(budgets::id.eq(existing.id)))
+                                    .set((
+                                        budgets::monthly_limit.eq(existing.monthly_limit + moving.monthly_limit),
+                                        budgets::rollover.eq(existing.rollover || moving.rollover),
+                                        budgets::start_month.eq(existing.start_month.min(moving.start_month)),
+                                    ))
+                                    .execute(conn)?;
+                            }
+                            (Some(moving), None) => {
+                                diesel::update(budgets::table.filter(budgets::id.eq(moving.id)))
+                                    .set(budgets::tag_id.eq(target))
+                                    .execute(conn)?;
+                            }
+                            (None, _) => {}
+                        }
+
+                        // The envelope follows too, unless another envelope already covers the
+                        // target, its subcategories or a parent; then the delete unlinks it.
+                        let user_envelopes: Vec<Envelope> = envelopes::table
+                            .filter(envelopes::user_id.eq(uid))
+                            .load(conn)?;
+                        if let Some(moving) = user_envelopes.iter().find(|e| e.tag_id == Some(category_id)) {
+                            let categories: Vec<Category> = expense_tags::table
+                                .filter(expense_tags::user_id.eq(uid))
+                                .load(conn)?;
+                            let covered = subtree(&categories, target);
+                            let taken = user_envelopes
+                                .iter()
+                                .filter(|e| e.id != moving.id)
+                                .filter_map(|e| e.tag_id)
+                                .any(|other| covered.contains(&other) || subtree(&categories, other).contains(&target));
+                            if !taken {
+                                diesel::update(envelopes::table.filter(envelopes::id.eq(moving.id)))
+                                    .set(envelopes::tag_id.eq(target))
+                                    .execute(conn)?;
+                            }
+                        }
+                    }
+
+                    let grandparent: Option<i32> = expense_tags::table
+                        .filter(expense_tags::id.eq(category_id))
+                        .filter(expense_tags::user_id.eq(uid))
+                        .select(expense_tags::parent_id)
+                        .first(conn)?;
+                    diesel::update(
+                        expense_tags::table
+                            .filter(expense_tags::user_id.eq(uid))
+                            .filter(expense_tags::parent_id.eq(category_id)),
+                    )
+                    .set(expense_tags::parent_id.eq(grandparent))
+                    .execute(conn)?;
+
+                    let deleted = diesel::delete(
+                        expense_tags::table
+                            .filter(expense_tags::id.eq(category_id))
+                            .filter(expense_tags::user_id.eq(uid)),
+                    )
+                    .execute(conn)?;
+                    if deleted == 0 {
+                        return Err(DieselError::NotFound);
+                    }
+                    Ok(())
+                })
+            }
+
+            fn load_exchange_rates(&self) -> QueryResult<Vec<ExchangeRate>> {
+                use crate::schema::exchange_rates::dsl::*;
+                let mut conn = self.conn()?;
+                exchange_rates
+                    .order((rate_date.desc(), currency.asc()))
+                    .load::<ExchangeRate>(&mut *conn)
+            }
+
+            fn save_exchange_rates(&self, rates: &[NewExchangeRate]) -> QueryResult<usize> {
+                use crate::schema::exchange_rates::dsl::*;
+                use diesel::upsert::excluded;
+                let mut conn = self.conn()?;
+                conn.transaction(|conn| {
+                    for rate in rates {
+                        diesel::insert_into(exchange_rates)
+                            .values(rate)
+                            .on_conflict((rate_date, currency, quote_currency))
+                            .do_update()
+                            .set(rate_micros.eq(excluded(rate_micros)))
+                            .execute(conn)?;
+                    }
+                    Ok(rates.len())
+                })
+            }
+
+            fn load_recurring_rules(&self, uid: i32) -> QueryResult<Vec<RecurringRule>> {
+                use crate::schema::recurring_rules::dsl::*;
+                let mut conn = self.conn()?;
+                recurring_rules
+                    .filter(user_id.eq(uid))
+                    .order(id.asc())
+                    .load::<RecurringRule>(&mut *conn)
+            }
+
+            fn insert_recurring_rule(&self, rule: &NewRecurringRule) -> QueryResult<RecurringRule> {
+                use crate::schema::recurring_rules::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::insert_into(recurring_rules)
+                    .values(rule)
+                    .get_result::<RecurringRule>(&mut *conn)
+            }
+
+            fn delete_recurring_rule(&self, uid: i32, rule_id: i32) -> QueryResult<usize> {
+                use crate::schema::recurring_rules::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::delete(recurring_rules.filter(id.eq(rule_id)).filter(user_id.eq(uid)))
+                    .execute(&mut *conn)
+            }
+
+            fn advance_rule_last_run(&self, uid: i32, rule_id: i32, date: chrono::NaiveDate) -> QueryResult<usize> {
+                use crate::schema::recurring_rules::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::update(
+                    recurring_rules
+                        .filter(id.eq(rule_id))
+                        .filter(user_id.eq(uid))
+                        .filter(last_run.is_null().or(last_run.lt(date))),
+                )
+                .set(last_run.eq(Some(date)))
+                .execute(&mut *conn)
+            }
+
+            fn load_budgets(&self, uid: i32) -> QueryResult<Vec<Budget>> {
+                use crate::schema::budgets::dsl::*;
+                let mut conn = self.conn()?;
+                budgets
+                    .filter(user_id.eq(uid))
+                    .order(id.asc())
+                    .load::<Budget>(&mut *conn)
+            }
+
+            fn save_budget(&self, budget: &NewBudget) -> QueryResult<()> {
+                use crate::schema::budgets::dsl::*;
+                use diesel::upsert::excluded;
+                let mut conn = self.conn()?;
+                diesel::insert_into(budgets)
+                    .values(budget)
+                    .on_conflict((user_id, tag_id))
+                    .do_update()
+                    .set((monthly_limit.eq(excluded(monthly_limit)), rollover.eq(excluded(rollover))))
+                    .execute(&mut *conn)?;
+                Ok(())
+            }
+
+            fn delete_budget(&self, uid: i32, category_id: i32) -> QueryResult<usize> {
+                use crate::schema::budgets::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::delete(budgets.filter(user_id.eq(uid)).filter(tag_id.eq(category_id)))
+                    .execute(&mut *conn)
+            }
+
+            fn load_envelopes(&self, uid: i32) -> QueryResult<Vec<Envelope>> {
+                use crate::schema::envelopes::dsl::*;
+                let mut conn = self.conn()?;
+                envelopes
+                    .filter(user_id.eq(uid))
+                    .order(name.asc())
+                    .load::<Envelope>(&mut *conn)
+            }
+
+            fn insert_envelope(&self, envelope: &NewEnvelope) -> QueryResult<Envelope> {
+                use crate::schema::envelopes::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::insert_into(envelopes)
+                    .values(envelope)
+                    .get_result::<Envelope>(&mut *conn)
+            }
+
+            fn delete_envelope(&self, uid: i32, envelope_id: i32) -> QueryResult<usize> {
+                use crate::schema::envelopes::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::delete(envelopes.filter(id.eq(envelope_id)).filter(user_id.eq(uid)))
+                    .execute(&mut *conn)
+            }
+
+            fn load_envelope_moves(&self, uid: i32) -> QueryResult<Vec<EnvelopeMove>> {
+                use crate::schema::envelope_moves::dsl::*;
+                let mut conn = self.conn()?;
+                envelope_moves
+                    .filter(user_id.eq(uid))
+                    .order((moved_at.asc(), id.asc()))
+                    .load::<EnvelopeMove>(&mut *conn)
+            }
+
+            fn insert_envelope_move(&self, envelope_move: &NewEnvelopeMove) -> QueryResult<()> {
+                let mut conn = self.conn()?;
+                diesel::insert_into(crate::schema::envelope_moves::table)
+                    .values(envelope_move)
+                    .execute(&mut *conn)?;
+                Ok(())
+            }
+
+            fn load_savings_goals(&self, uid: i32) -> QueryResult<Vec<SavingsGoal>> {
+                use crate::schema::savings_goals::dsl::*;
+                let mut conn = self.conn()?;
+                savings_goals
+                    .filter(user_id.eq(uid))
+                    .order((target_date.asc(), id.asc()))
+                    .load::<SavingsGoal>(&mut *conn)
+            }
+
+            fn insert_savings_goal(&self, goal: &NewSavingsGoal) -> QueryResult<SavingsGoal> {
+                use crate::schema::savings_goals::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::insert_into(savings_goals)
+                    .values(goal)
+                    .get_result::<SavingsGoal>(&mut *conn)
+            }
+
+            fn delete_savings_goal(&self, uid: i32, goal_id: i32) -> QueryResult<usize> {
+                use crate::schema::savings_goals::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::delete(savings_goals.filter(id.eq(goal_id)).filter(user_id.eq(uid)))
+                    .execute(&mut *conn)
+            }
+
+            fn load_goal_contributions(&self, uid: i32) -> QueryResult<Vec<GoalContribution>> {
+                use crate::schema::{goal_contributions, savings_goals};
+                let mut conn = self.conn()?;
+                goal_contributions::table
+                    .inner_join(savings_goals::table)
+                    .filter(savings_goals::user_id.eq(uid))
+                    .select(goal_contributions::all_columns)
+                    .order(goal_contributions::id.asc())
+                    .load::<GoalContribution>(&mut *conn)
+            }
+
+            fn insert_goal_contribution(&self, contribution: &NewGoalContribution) -> QueryResult<()> {
+                let mut conn = self.conn()?;
+                diesel::insert_into(crate::schema::goal_contributions::table)
+                    .values(contribution)
+                    .execute(&mut *conn)?;
+                Ok(())
+            }
+
+            fn delete_goal_contribution(&self, uid: i32, contribution_id: i32) -> QueryResult<usize> {
+                use crate::schema::{goal_contributions, savings_goals};
+                let mut conn = self.conn()?;
+                let own_goals = savings_goals::table
+                    .filter(savings_goals::user_id.eq(uid))
+                    .select(savings_goals::id);
+                diesel::delete(
+                    goal_contributions::table
+                        .filter(goal_contributions::id.eq(contribution_id))
+                        .filter(goal_contributions::goal_id.eq_any(own_goals)),
+                )
+                .execute(&mut *conn)
+            }
+
+            fn load_loans(&self, uid: i32) -> QueryResult<Vec<Loan>> {
+                use crate::schema::loans::dsl::*;
+                let mut conn = self.conn()?;
+                loans
+                    .filter(user_id.eq(uid))
+                    .order(id.asc())
+                    .load::<Loan>(&mut *conn)
+            }
+
+            fn insert_loan(&self, loan: &NewLoan) -> QueryResult<Loan> {
+                use crate::schema::loans::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::insert_into(loans)
+                    .values(loan)
+                    .get_result::<Loan>(&mut *conn)
+            }
+
+            fn delete_loan(&self, uid: i32, loan_id: i32) -> QueryResult<usize> {
+                use crate::schema::loans::dsl::*;
+                let mut conn = self.conn()?;
+                diesel::delete(loans.filter(id.eq(loan_id)).filter(user_id.eq(uid)))
+                    .execute(&mut *conn)
+            }
+
+            fn load_loan_payments(&self, uid: i32) -> QueryResult<Vec<LoanPayment>> {
+                use crate::schema::{loan_payments, loans};
+                let mut conn = self.conn()?;
+                loan_payments::table
+                    .inner_join(loans::table)
+                    .filter(loans::user_id.eq(uid))
+                    .select(loan_payments::all_columns)
+                    .order(loan_payments::id.asc())
+                    .load::<LoanPayment>(&mut *conn)
+            }
+
+            fn insert_loan_payment(&self, payment: &NewLoanPayment) -> QueryResult<()> {
+                let mut conn = self.conn()?;
+                diesel::insert_into(crate::schema::loan_payments::table)
+                    .values(payment)
+                    .execute(&mut *conn)?;
+                Ok(())
+            }
+
+            fn insert_attachments(&self, new_attachments: &[NewAttachment]) -> QueryResult<()> {
+                let mut conn = self.conn()?;
+                diesel::insert_into(crate::schema::attachments::table)
+                    .values(new_attachments)
+                    .execute(&mut *conn)?;
+                Ok(())
+            }
+
+            fn load_attachments(&self, uid: i32, tx_id: i32) -> QueryResult<Vec<Attachment>> {
+                use crate::schema::{attachments, transactions};
+                let mut conn = self.conn()?;
+                attachments::table
+                    .inner_join(transactions::table)
+                    .filter(attachments::transaction_id.eq(tx_id))
+                    .filter(transactions::user_id.eq(uid))
+                    .select(attachments::all_columns)
+                    .order(attachments::id.asc())
+                    .load::<Attachment>(&mut *conn)
+            }
+
+            fn find_attachment(&self, uid: i32, attachment_id: i32) -> QueryResult<Attachment> {
+                use crate::schema::{attachments, transactions};
+                let mut conn = self.conn()?;
+                attachments::table
+                    .inner_join(transactions::table)
+                    .filter(attachments::id.eq(attachment_id))
+                    .filter(transactions::user_id.eq(uid))
+                    .select(attachments::all_columns)
+                    .first::<Attachment>(&mut *conn)
+            }
+
+            fn delete_attachment(&self, uid: i32, attachment_id: i32) -> QueryResult<usize> {
+                use crate::schema::{attachments, transactions};
+                let mut conn = self.conn()?;
+                let owned = transactions::table
+                    .select(transactions::tran_id)
+                    .filter(transactions::user_id.eq(uid));
+                diesel::delete(
+                    attachments::table
+                        .filter(attachments::id.eq(attachment_id))
+                        .filter(attachments::transaction_id.eq_any(owned)),
+                )
+                .execute(&mut *conn)
+            }
+
+            fn attachment_hashes(&self, tx_ids: &[i32]) -> QueryResult<Vec<String>> {
+                use crate::schema::attachments::dsl::*;
+                let mut conn = self.conn()?;
+                attachments
+                    .filter(transaction_id.eq_any(tx_ids))
+                    .select(content_hash)
+                    .distinct()
+                    .load::<String>(&mut *conn)
+            }
+
+            fn count_attachments_with_hash(&self, hash: &str) -> QueryResult<i64> {
+                use crate::schema::attachments::dsl::*;
+                let mut conn = self.conn()?;
+                attachments
+                    .filter(content_hash.eq(hash))
+                    .count()
+                    .get_result(&mut *conn)
+            }
+
+            fn insert_audit_entry(&self, entry: &NewAuditEntry) -> QueryResult<()> {
+                let mut conn = self.conn()?;
+                diesel::insert_into(crate::schema::audit_log::table)
+                    .values(entry)
+                    .execute(&mut *conn)?;
+                Ok(())
+            }
+
+            fn load_audit_log(&self, uid: i32, filter: &AuditFilter) -> QueryResult<Vec<AuditEntry>> {
+                use crate::schema::audit_log::dsl::*;
+                let mut conn = self.conn()?;
+                let mut query = audit_log.filter(user_id.eq(uid)).into_boxed();
+                if let Some(from) = filter.from {
+                    query = query.filter(created_at.ge(from.and_hms_opt(0, 0, 0).unwrap()));
+                }
+                if let Some(to) = filter.to.and_then(|d| d.succ_opt()) {
+                    query = query.filter(created_at.lt(to.and_hms_opt(0, 0, 0).unwrap()));
+                }
+                if let Some(wanted) = &filter.action {
+                    query = query.filter(action.eq(wanted.as_str()));
+                }
+                query
+                    .order((created_at.desc(), id.desc()))
+                    .load::<AuditEntry>(&mut *conn)
+            }
+        }
+    };
 }
+
+impl_repository!(PgRepository, PgConnection);
+impl_repository!(SqliteRepository, SqliteConnection);
@@ -0,0 +1,82 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use crate::model::Money;
+use crate::schema::{envelope_moves, envelopes};
+
+/// A pot of assigned income for envelope budgeting. Expenses in the linked expense
+/// category and its subcategories draw it down.
+#[derive(Debug, Clone, Queryable)]
+pub struct Envelope {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub tag_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = envelopes)]
+pub struct NewEnvelope {
+    pub user_id: i32,
+    pub name: String,
+    pub tag_id: Option<i32>,
+}
+
+/// Money moved between envelopes, in the user's base currency. `None` on either side
+/// is the income that is still available to assign.
+#[derive(Debug, Clone, Queryable)]
+pub struct EnvelopeMove {
+    pub id: i32,
+    pub user_id: i32,
+    pub from_envelope: Option<i32>,
+    pub to_envelope: Option<i32>,
+    pub amount: Money,
+    pub moved_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = envelope_moves)]
+pub struct NewEnvelopeMove {
+    pub user_id: i32,
+    pub from_envelope: Option<i32>,
+    pub to_envelope: Option<i32>,
+    pub amount: Money,
+    pub moved_at: NaiveDateTime,
+}
+
+/// The user's envelope budgeting setup as loaded from the database.
+#[derive(Debug, Clone, Default)]
+pub struct EnvelopeBook {
+    pub enabled: bool,
+    /// Income and expenses from this day on count towards the envelopes.
+    pub start: Option<NaiveDate>,
+    pub envelopes: Vec<Envelope>,
+    pub moves: Vec<EnvelopeMove>,
+}
+
+/// One side of a move in the envelope screen's pickers: an envelope, or `None` for
+/// the income still available to assign.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvelopeChoice {
+    pub id: Option<i32>,
+    pub name: String,
+}
+
+impl std::fmt::Display for EnvelopeChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Net amount the moves put into `envelope`, or into the unassigned pool for `None`.
+pub fn moved_into(moves: &[EnvelopeMove], envelope: Option<i32>) -> Money {
+    let mut total = Money::ZERO;
+    for m in moves {
+        if m.to_envelope == envelope {
+            total += m.amount;
+        }
+        if m.from_envelope == envelope {
+            total -= m.amount;
+        }
+    }
+    total
+}
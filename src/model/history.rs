@@ -0,0 +1,107 @@
+use std::time::{Duration, Instant};
+use crate::model::Transaction;
+
+/// How many mutations can be undone in a row.
+const HISTORY_LIMIT: usize = 50;
+pub const TOAST_DURATION: Duration = Duration::from_secs(6);
+
+/// A change made to a transaction, kept so it can be reverted and re-applied.
+#[derive(Debug, Clone)]
+pub enum Mutation {
+    Added(Transaction),
+    Deleted(Transaction),
+    Edited { before: Box<Transaction>, after: Box<Transaction> },
+}
+
+impl Mutation {
+    /// The mutation that reverts this one. Undoing means applying the inverse.
+    pub fn inverse(&self) -> Mutation {
+        match self {
+            Mutation::Added(tx) => Mutation::Deleted(tx.clone()),
+            Mutation::Deleted(tx) => Mutation::Added(tx.clone()),
+            Mutation::Edited { before, after } => Mutation::Edited {
+                before: after.clone(),
+                after: before.clone(),
+            },
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let (verb, tx) = match self {
+            Mutation::Added(tx) => ("Added", tx),
+            Mutation::Deleted(tx) => ("Deleted", tx),
+            Mutation::Edited { after, .. } => ("Edited", &**after),
+        };
+        format!("{} {} '{}'", verb, tx.tran_type.to_lowercase(), tx.tran_source)
+    }
+}
+
+/// Undo/redo stacks of transaction mutations. Recording a new mutation drops the redo stack.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Mutation>,
+    redo: Vec<Mutation>,
+}
+
+impl History {
+    pub fn record(&mut self, mutation: Mutation) {
+        self.redo.clear();
+        self.push_undo(mutation);
+    }
+
+    pub fn take_undo(&mut self) -> Option<Mutation> {
+        self.undo.pop()
+    }
+
+    pub fn take_redo(&mut self) -> Option<Mutation> {
+        self.redo.pop()
+    }
+
+    /// Files a mutation that has just been undone, or failed to redo, so it can be redone.
+    pub fn push_redo(&mut self, mutation: Mutation) {
+        self.redo.push(mutation);
+    }
+
+    /// Files a mutation that has just been redone, or failed to undo, so it can be undone.
+    pub fn push_undo(&mut self, mutation: Mutation) {
+        self.undo.push(mutation);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+/// Short notice shown at the bottom of the dashboard.
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub text: String,
+    pub undoable: bool,
+    pub expires: Instant,
+}
+
+impl Toast {
+    pub fn new(text: impl Into<String>, undoable: bool) -> Self {
+        Toast {
+            text: text.into(),
+            undoable,
+            expires: Instant::now() + TOAST_DURATION,
+        }
+    }
+
+    pub fn expired(&self, now: Instant) -> bool {
+        now >= self.expires
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::HashSet;
+use chrono::{NaiveDate, NaiveTime};
+use crate::model::{Category, Money, NewTransaction};
+use crate::model::category::{KIND_EXPENSE, KIND_INCOME};
+
+/// File formats the import screen understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv,
+    Ofx,
+    Qif,
+    Camt053,
+    Mt940,
+}
+
+impl std::fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportFormat::Csv => write!(f, "CSV"),
+            ImportFormat::Ofx => write!(f, "OFX"),
+            ImportFormat::Qif => write!(f, "QIF"),
+            ImportFormat::Camt053 => write!(f, "camt.053"),
+            ImportFormat::Mt940 => write!(f, "MT940"),
+        }
+    }
+}
+
+/// Tells the format from the file name, or from the contents when the extension is unknown.
+pub fn detect_format(file_name: &str, text: &str) -> ImportFormat {
+    let extension = file_name.rsplit('.').next().unwrap_or("").to_lowercase();
+    match extension.as_str() {
+        "ofx" | "qfx" => ImportFormat::Ofx,
+        "qif" => ImportFormat::Qif,
+        "sta" | "mt940" | "940" => ImportFormat::Mt940,
+        "csv" => ImportFormat::Csv,
+        _ if text.contains("<OFX>") || text.starts_with("OFXHEADER") => ImportFormat::Ofx,
+        _ if text.contains("BkToCstmrStmt") => ImportFormat::Camt053,
+        _ if text.contains(":20:") && text.contains(":61:") => ImportFormat::Mt940,
+        _ if text.trim_start_matches('\u{feff}').starts_with('!') => ImportFormat::Qif,
+        _ => ImportFormat::Csv,
+    }
+}
+
+/// One statement line ready to be booked. A positive amount is money coming in and
+/// becomes income; a negative one becomes an expense.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedRow {
+    pub date: NaiveDate,
+    pub amount: Money,
+    pub payee: String,
+    pub comment: Option<String>,
+    /// Category as a `Parent:Child` path; missing categories are created on import.
+    pub category: Option<String>,
+    /// Category lines of a split transaction, adding up to `amount`; `category` is `None` then.
+    pub splits: Vec<ImportedSplit>,
+    /// The bank's id for the line; rows whose id was imported before are skipped.
+    pub external_id: Option<String>,
+    /// Currency named by the statement, if it names one.
+    pub currency: Option<String>,
+}
+
+/// One category line of an imported split transaction, signed like its row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedSplit {
+    /// `Parent:Child` path, as for `ImportedRow::category`.
+    pub category: String,
+    pub amount: Money,
+}
+
+/// A balance stated in a bank statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatementBalance {
+    pub amount: Money,
+    pub date: NaiveDate,
+}
+
+/// One account's statement: its lines and, where the format has them, the balances
+/// before and after.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BankStatement {
+    /// The bank's account number or IBAN.
+    pub account: Option<String>,
+    pub currency: Option<String>,
+    pub opening: Option<StatementBalance>,
+    pub closing: Option<StatementBalance>,
+    pub rows: Vec<ImportedRow>,
+}
+
+impl BankStatement {
+    /// Opening balance plus all rows; `None` without an opening balance.
+    pub fn computed_closing(&self) -> Option<Money> {
+        let opening = self.opening?;
+        Some(opening.amount + self.rows.iter().map(|r| r.amount).sum::<Money>())
+    }
+
+    /// Stated closing balance minus the computed one. Zero means the statement's rows are
+    /// complete; `None` when the statement lacks either balance.
+    pub fn balance_difference(&self) -> Option<Money> {
+        Some(self.closing?.amount - self.computed_closing()?)
+    }
+
+    pub fn summary(&self) -> String {
+        let mut text = match &self.account {
+            Some(account) => format!("Account {}: {} transactions", account, self.rows.len()),
+            None => format!("{} transactions", self.rows.len()),
+        };
+        let currency = self.currency.as_deref().unwrap_or("");
+        if let Some(opening) = self.opening {
+            text.push_str(&format!(", opening {} {} on {}", opening.amount, currency, opening.date.format("%Y-%m-%d")));
+        }
+        if let Some(closing) = self.closing {
+            text.push_str(&format!(", closing {} {} on {}", closing.amount, currency, closing.date.format("%Y-%m-%d")));
+        }
+        text
+    }
+}
+
+/// Outcome of an import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// Rows left out because they were imported before.
+    pub duplicates: usize,
+}
+
+impl std::fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Imported {} transactions", self.imported)?;
+        if self.duplicates > 0 {
+            write!(f, ", skipped {} already imported", self.duplicates)?;
+        }
+        Ok(())
+    }
+}
+
+impl ImportedRow {
+    pub fn kind(&self) -> &'static str {
+        if self.amount.is_negative() { "Expense" } else { "Income" }
+    }
+
+    /// `KIND_INCOME` or `KIND_EXPENSE`, whichever categories the row can be booked under.
+    pub fn category_kind(&self) -> &'static str {
+        if self.amount.is_negative() { KIND_EXPENSE } else { KIND_INCOME }
+    }
+
+    /// The row's category path and those of its split lines.
+    pub fn category_paths(&self) -> impl Iterator<Item = &str> {
+        self.category.as_deref().into_iter().chain(self.splits.iter().map(|s| s.category.as_str()))
+    }
+
+    /// The first existing category on the row's paths that is of the other kind or sits
+    /// under a different parent than the path names. Category names are used only once
+    /// per user, so such rows are imported uncategorized.
+    pub fn category_conflict<'c>(&self, categories: &'c [Category]) -> Option<&'c Category> {
+        let kind = self.category_kind();
+        self.category_paths().find_map(|path| {
+            // The parent the next name must have; `None` once a name is still to be created.
+            let mut parent = Some(None);
+            for name in category_path_names(path) {
+                match categories.iter().find(|c| c.name.eq_ignore_ascii_case(name)) {
+                    Some(c) if c.kind != kind || parent != Some(c.parent_id) => return Some(c),
+                    Some(c) => parent = Some(Some(c.id)),
+                    None => parent = None,
+                }
+            }
+            None
+        })
+    }
+
+    /// The category path, or the split lines with their amounts, for the import preview.
+    pub fn category_label(&self) -> Option<String> {
+        if self.splits.is_empty() {
+            return self.category.clone();
+        }
+        let lines: Vec<String> = self.splits
+            .iter()
+            .map(|s| format!("{} {}", s.category, s.amount.abs()))
+            .collect();
+        Some(lines.join(" + "))
+    }
+
+    /// `currency` is used when the statement does not name one.
+    pub fn to_new<'a>(&'a self, uid: i32, currency: &'a str, account_id: Option<i32>) -> NewTransaction<'a> {
+        NewTransaction {
+            tran_type: self.kind(),
+            user_id: uid,
+            tran_source: &self.payee,
+            date: self.date.and_time(NaiveTime::MIN),
+            tran_amount: self.amount.abs(),
+            tag_id: None,
+            tran_comment: self.comment.as_deref(),
+            currency: self.currency.as_deref().unwrap_or(currency),
+            account_id,
+            transfer_account_id: None,
+            external_id: self.external_id.as_deref(),
+        }
+    }
+}
+
+/// The names of a `Parent:Child` category path, top first.
+pub fn category_path_names(path: &str) -> impl Iterator<Item = &str> {
+    path.split(':').map(str::trim).filter(|name| !name.is_empty())
+}
+
+/// Leaves out rows with a zero amount and rows whose external id is in `seen` or occurs
+/// earlier in `rows`, adding the kept ids to `seen`. Returns the kept rows and how many
+/// were duplicates.
+pub fn skip_duplicates<'a>(rows: &'a [ImportedRow], seen: &mut HashSet<String>) -> (Vec<&'a ImportedRow>, usize) {
+    let mut duplicates = 0;
+    let mut kept = Vec::new();
+    for row in rows.iter().filter(|row| !row.amount.is_zero()) {
+        if let Some(id) = &row.external_id {
+            if !seen.insert(id.clone()) {
+                duplicates += 1;
+                continue;
+            }
+        }
+        kept.push(row);
+    }
+    (kept, duplicates)
+}
+
+/// Parses a statement amount. `decimal` is the decimal separator (`.` or `,`); the other
+/// one, spaces and apostrophes are taken as thousands separators. Currency symbols are
+/// ignored, and a trailing minus or parentheses mark a negative amount.
+pub fn parse_statement_amount(input: &str, decimal: char) -> Result<Money, String> {
+    let text = input.trim();
+    let mut negative = false;
+    let mut cleaned = String::new();
+    for c in text.chars() {
+        match c {
+            '0'..='9' => cleaned.push(c),
+            '-' | '−' | '(' => negative = true,
+            c if c == decimal => cleaned.push('.'),
+            _ => {}
+        }
+    }
+    if cleaned.is_empty() {
+        return Err(format!("'{}' is not an amount", text));
+    }
+    let amount = Money::parse(&cleaned).map_err(|e| format!("'{}': {}", text, e))?;
+    Ok(if negative { -amount } else { amount })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amounts_in_both_decimal_conventions() {
+        assert_eq!(parse_statement_amount("1,234.56", '.'), Ok(Money::from_minor(123456)));
+        assert_eq!(parse_statement_amount("1.234,56", ','), Ok(Money::from_minor(123456)));
+        assert_eq!(parse_statement_amount("1 234,56 €", ','), Ok(Money::from_minor(123456)));
+        assert_eq!(parse_statement_amount("1'234.56", '.'), Ok(Money::from_minor(123456)));
+    }
+
+    #[test]
+    fn the_other_separator_groups_thousands() {
+        assert_eq!(parse_statement_amount("12,50", '.'), Ok(Money::from_minor(125000)));
+        assert_eq!(parse_statement_amount("12.50", ','), Ok(Money::from_minor(125000)));
+    }
+
+    #[test]
+    fn negative_amounts() {
+        assert_eq!(parse_statement_amount("-12.50", '.'), Ok(Money::from_minor(-1250)));
+        assert_eq!(parse_statement_amount("12,50-", ','), Ok(Money::from_minor(-1250)));
+        assert_eq!(parse_statement_amount("(12.50)", '.'), Ok(Money::from_minor(-1250)));
+        assert_eq!(parse_statement_amount("$(1,000.00)", '.'), Ok(Money::from_minor(-100000)));
+    }
+
+    fn category(id: i32, name: &str, parent_id: Option<i32>, kind: &str) -> Category {
+        Category { id, name: name.into(), user_id: Some(1), color: "#888888".into(), parent_id, kind: kind.into() }
+    }
+
+    fn row(minor: i64, path: &str) -> ImportedRow {
+        ImportedRow {
+            date: NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+            amount: Money::from_minor(minor),
+            payee: "Payee".into(),
+            comment: None,
+            category: Some(path.into()),
+            splits: Vec::new(),
+            external_id: None,
+            currency: None,
+        }
+    }
+
+    #[test]
+    fn category_paths_must_match_the_row_kind() {
+        let categories = vec![
+            category(1, "Salary", None, KIND_EXPENSE),
+            category(2, "Food", None, KIND_EXPENSE),
+            category(3, "Wages", None, KIND_INCOME),
+        ];
+        assert_eq!(row(250000, "salary").category_conflict(&categories).map(|c| c.id), Some(1));
+        assert_eq!(row(500, "Food:Refund").category_conflict(&categories).map(|c| c.id), Some(2));
+        assert_eq!(row(-500, "Food:Refund").category_conflict(&categories), None);
+        assert_eq!(row(250000, "Wages:Bonus").category_conflict(&categories), None);
+        assert_eq!(category_path_names(" Food : : Bakery ").collect::<Vec<_>>(), ["Food", "Bakery"]);
+    }
+
+    #[test]
+    fn category_paths_must_match_the_existing_tree() {
+        let categories = vec![
+            category(1, "Food", None, KIND_EXPENSE),
+            category(2, "Groceries", Some(1), KIND_EXPENSE),
+            category(3, "Home", None, KIND_EXPENSE),
+        ];
+        assert_eq!(row(-500, "food:groceries").category_conflict(&categories), None);
+        assert_eq!(row(-500, "Food:Groceries:Fruit").category_conflict(&categories), None);
+        assert_eq!(row(-500, "Home:Garden").category_conflict(&categories), None);
+        assert_eq!(row(-500, "Home:Groceries").category_conflict(&categories).map(|c| c.id), Some(2));
+        assert_eq!(row(-500, "Groceries").category_conflict(&categories).map(|c| c.id), Some(2));
+        assert_eq!(row(-500, "Garden:Food").category_conflict(&categories).map(|c| c.id), Some(1));
+        assert_eq!(row(-500, "Food:Home").category_conflict(&categories).map(|c| c.id), Some(3));
+    }
+
+    #[test]
+    fn text_is_not_an_amount() {
+        assert!(parse_statement_amount("n/a", '.').is_err());
+        assert!(parse_statement_amount("", ',').is_err());
+    }
+}
@@ -0,0 +1,283 @@
+use chrono::{Months, NaiveDate};
+use diesel::prelude::*;
+use crate::model::{Money, Transaction};
+use crate::schema::{loan_payments, loans};
+
+pub const DIRECTION_BORROWED: &str = "borrowed";
+pub const DIRECTION_LENT: &str = "lent";
+pub const DIRECTIONS: [&str; 2] = [DIRECTION_BORROWED, DIRECTION_LENT];
+
+/// Longest schedule ever generated, so a payment that does not even cover the
+/// interest cannot run forever.
+const MAX_SCHEDULE_MONTHS: u32 = 1200;
+
+/// A loan repaid in fixed monthly instalments: money the user borrowed, such as a
+/// mortgage, or money lent to someone else.
+#[derive(Debug, Clone, PartialEq, Eq, Queryable)]
+pub struct Loan {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub direction: String,
+    pub principal: Money,
+    pub currency: String,
+    /// Yearly interest in basis points, 350 meaning 3.50 %.
+    pub annual_rate_bp: i32,
+    pub term_months: i32,
+    pub start_date: NaiveDate,
+    /// Account repayments are paid from, or received into for lent money.
+    pub account_id: Option<i32>,
+    /// Category the interest part is booked on: an expense category for borrowed money,
+    /// an income category for lent money.
+    pub interest_tag_id: Option<i32>,
+}
+
+impl std::fmt::Display for Loan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = loans)]
+pub struct NewLoan {
+    pub user_id: i32,
+    pub name: String,
+    pub direction: String,
+    pub principal: Money,
+    pub currency: String,
+    pub annual_rate_bp: i32,
+    pub term_months: i32,
+    pub start_date: NaiveDate,
+    pub account_id: Option<i32>,
+    pub interest_tag_id: Option<i32>,
+}
+
+/// Links a repayment transaction to its loan. Only the interest is stored; the rest of
+/// the transaction's amount is principal.
+#[derive(Debug, Clone, Queryable)]
+pub struct LoanPayment {
+    pub id: i32,
+    pub loan_id: i32,
+    pub transaction_id: i32,
+    pub interest: Money,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = loan_payments)]
+pub struct NewLoanPayment {
+    pub loan_id: i32,
+    pub transaction_id: i32,
+    pub interest: Money,
+}
+
+impl LoanPayment {
+    /// Interest part of a payment of `amount`; never more than the payment itself.
+    pub fn interest_part(&self, amount: Money) -> Money {
+        self.interest.min(amount)
+    }
+
+    pub fn principal_part(&self, amount: Money) -> Money {
+        amount - self.interest_part(amount)
+    }
+}
+
+/// The user's loans and the payments booked on them.
+#[derive(Debug, Clone, Default)]
+pub struct LoanBook {
+    pub loans: Vec<Loan>,
+    pub payments: Vec<LoanPayment>,
+}
+
+impl LoanBook {
+    /// Principal repaid on the loan and the number of payments, counting only payments
+    /// whose transaction is among `transactions` (so trashed repayments don't count).
+    pub fn repaid(&self, loan: &Loan, transactions: &[Transaction]) -> (Money, u32) {
+        let mut principal = Money::ZERO;
+        let mut count = 0;
+        for payment in self.payments.iter().filter(|p| p.loan_id == loan.id) {
+            if let Some(tx) = transactions.iter().find(|t| t.tran_id == payment.transaction_id) {
+                principal += payment.principal_part(tx.tran_amount);
+                count += 1;
+            }
+        }
+        (principal, count)
+    }
+
+    /// What is still owed on the loan, and how many payments were made.
+    pub fn outstanding(&self, loan: &Loan, transactions: &[Transaction]) -> (Money, u32) {
+        let (repaid, count) = self.repaid(loan, transactions);
+        ((loan.principal - repaid).max(Money::ZERO), count)
+    }
+
+    pub fn payment_for(&self, tx_id: i32) -> Option<&LoanPayment> {
+        self.payments.iter().find(|p| p.transaction_id == tx_id)
+    }
+}
+
+/// One instalment of an amortization schedule.
+#[derive(Debug, Clone, Copy)]
+pub struct ScheduleRow {
+    /// Counts from 1 for the first instalment, a month after the start date.
+    pub number: u32,
+    pub date: NaiveDate,
+    pub payment: Money,
+    pub interest: Money,
+    pub principal: Money,
+    /// What is still owed after this instalment.
+    pub balance: Money,
+}
+
+impl Loan {
+    pub fn is_lent(&self) -> bool {
+        self.direction == DIRECTION_LENT
+    }
+
+    fn monthly_rate(&self) -> f64 {
+        self.annual_rate_bp as f64 / 10_000.0 / 12.0
+    }
+
+    /// Fixed monthly payment that clears the principal over the term, rounded up to
+    /// the cent so the last instalment is never larger than the others.
+    pub fn instalment(&self) -> Money {
+        let principal = self.principal.minor() as f64;
+        let months = self.term_months.max(1) as f64;
+        let rate = self.monthly_rate();
+        let payment = if rate == 0.0 {
+            principal / months
+        } else {
+            principal * rate / (1.0 - (1.0 + rate).powf(-months))
+        };
+        Money::from_minor(payment.ceil() as i64)
+    }
+
+    /// One month of interest on `balance`, rounded to the cent.
+    pub fn interest_on(&self, balance: Money) -> Money {
+        Money::from_minor((balance.minor() as f64 * self.monthly_rate()).round() as i64)
+    }
+
+    pub fn due_date(&self, number: u32) -> Option<NaiveDate> {
+        self.start_date.checked_add_months(Months::new(number))
+    }
+
+    /// The full schedule as agreed when the loan started.
+    pub fn schedule(&self) -> Vec<ScheduleRow> {
+        self.projection(self.principal, 0)
+    }
+
+    /// Remaining instalments when `balance` is still owed after `payments_made` payments.
+    /// The last row's date is the payoff date.
+    pub fn projection(&self, balance: Money, payments_made: u32) -> Vec<ScheduleRow> {
+        let instalment = self.instalment();
+        let mut rows = Vec::new();
+        let mut balance = balance;
+        let mut number = payments_made;
+        while balance > Money::ZERO && number < MAX_SCHEDULE_MONTHS {
+            number += 1;
+            let interest = self.interest_on(balance);
+            let payment = instalment.min(balance + interest);
+            let principal = payment - interest;
+            let date = match self.due_date(number) {
+                Some(date) => date,
+                None => break,
+            };
+            if principal <= Money::ZERO {
+                break;
+            }
+            balance -= principal;
+            rows.push(ScheduleRow { number, date, payment, interest, principal, balance });
+        }
+        rows
+    }
+}
+
+/// Parses a yearly rate such as "3.5" or "3.50%" into basis points.
+pub fn parse_rate(input: &str) -> Result<i32, String> {
+    let trimmed = input.trim().trim_end_matches('%').trim();
+    // Basis points are hundredths of a percent, the same scale as cents.
+    let rate = Money::parse(trimmed).map_err(|_| format!("'{}' is not an interest rate like 3.5", input.trim()))?;
+    if rate.is_negative() || rate.minor() > 10_000 {
+        return Err("The interest rate must be between 0 and 100 %".into());
+    }
+    Ok(rate.minor() as i32)
+}
+
+pub fn format_rate_bp(rate_bp: i32) -> String {
+    format!("{} %", Money::from_minor(rate_bp as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loan(principal: i64, rate_bp: i32, term_months: i32) -> Loan {
+        Loan {
+            id: 1,
+            user_id: 1,
+            name: "Car".into(),
+            direction: DIRECTION_BORROWED.into(),
+            principal: Money::from_minor(principal),
+            currency: "EUR".into(),
+            annual_rate_bp: rate_bp,
+            term_months,
+            start_date: NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(),
+            account_id: None,
+            interest_tag_id: None,
+        }
+    }
+
+    #[test]
+    fn schedule_pays_the_loan_off_over_its_term() {
+        let loan = loan(1_000_000, 600, 12);
+        assert_eq!(loan.instalment(), Money::from_minor(86_067));
+
+        let schedule = loan.schedule();
+        assert_eq!(schedule.len(), 12);
+        assert_eq!(schedule[0].interest, Money::from_minor(5_000));
+        assert_eq!(schedule[0].principal, Money::from_minor(81_067));
+        assert_eq!(schedule[0].balance, Money::from_minor(918_933));
+        assert!(schedule[..11].iter().all(|row| row.payment == loan.instalment()));
+
+        let last = schedule[11];
+        assert_eq!(last.number, 12);
+        assert_eq!(last.date, NaiveDate::from_ymd_opt(2025, 1, 31).unwrap());
+        assert_eq!(last.payment, Money::from_minor(86_059));
+        assert_eq!(last.balance, Money::ZERO);
+
+        let interest: Money = schedule.iter().map(|row| row.interest).sum();
+        let principal: Money = schedule.iter().map(|row| row.principal).sum();
+        assert_eq!(interest, Money::from_minor(32_796));
+        assert_eq!(principal, loan.principal);
+    }
+
+    #[test]
+    fn interest_free_loans_split_the_principal_evenly() {
+        let loan = loan(1_000_000, 0, 3);
+        let payments: Vec<Money> = loan.schedule().iter().map(|row| row.payment).collect();
+        assert_eq!(payments, [333_334, 333_334, 333_332].map(Money::from_minor));
+        assert!(loan.schedule().iter().all(|row| row.interest.is_zero()));
+        assert_eq!(loan.schedule().last().map(|row| row.balance), Some(Money::ZERO));
+    }
+
+    #[test]
+    fn projection_continues_from_the_outstanding_balance() {
+        let loan = loan(1_000_000, 600, 12);
+        let full = loan.schedule();
+        let rest = loan.projection(full[3].balance, 4);
+        assert_eq!(rest.len(), 8);
+        assert_eq!(rest[0].number, 5);
+        assert_eq!(rest[0].date, full[4].date);
+        assert_eq!(rest.last().map(|row| row.balance), Some(Money::ZERO));
+        assert!(loan.projection(Money::ZERO, 12).is_empty());
+    }
+
+    #[test]
+    fn parses_rates_into_basis_points() {
+        assert_eq!(parse_rate("3.5"), Ok(350));
+        assert_eq!(parse_rate(" 4,25 % "), Ok(425));
+        assert_eq!(parse_rate("0"), Ok(0));
+        assert!(parse_rate("-1").is_err());
+        assert!(parse_rate("100.01").is_err());
+        assert_eq!(format_rate_bp(350), "3.50 %");
+    }
+}
@@ -1,11 +1,42 @@
+pub mod money;
 pub mod transaction;
+pub mod account;
 pub mod user;
 pub mod category;
+pub mod currency;
+pub mod audit;
+pub mod attachment;
+pub mod history;
+pub mod recurring;
+pub mod budget;
+pub mod envelope;
+pub mod savings;
+pub mod loan;
+pub mod import;
+pub mod csv_import;
+pub mod ofx;
+pub mod qif;
+pub mod xml;
+pub mod camt;
+pub mod mt940;
 pub mod state;
+pub mod repository;
 pub mod db;
 
-pub use transaction::{Transaction, NewTransaction};
+pub use money::Money;
+pub use transaction::{Transaction, NewSplit, NewTransaction, SplitLine, TransactionDraft};
 pub use user::{ NewUser, AuthData};
-pub use category::Category;
-pub use state::{CombinedApp, DashboardViewMode, Screen, Message};
+pub use account::{Account, NewAccount};
+pub use category::{Category, CategoryOption, NewCategory};
+pub use attachment::{Attachment, NewAttachment, StoredFile};
+pub use audit::{AuditEntry, AuditFilter, NewAuditEntry};
+pub use currency::{ExchangeRate, NewExchangeRate, RateTable};
+pub use history::{History, Mutation, Toast};
+pub use recurring::{NewRecurringRule, RecurringRule};
+pub use budget::{Budget, NewBudget};
+pub use envelope::{Envelope, EnvelopeBook, EnvelopeChoice, EnvelopeMove, NewEnvelope, NewEnvelopeMove};
+pub use loan::{Loan, LoanBook, LoanPayment, NewLoan, NewLoanPayment};
+pub use savings::{EarmarkSource, GoalContribution, NewGoalContribution, NewSavingsGoal, SavingsBook, SavingsGoal};
+pub use state::{CombinedApp, DashboardData, DashboardViewMode, Screen, Message};
+pub use repository::Repository;
 pub use db::*;
@@ -0,0 +1,245 @@
+use diesel::backend::Backend;
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::BigInt;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// Exact amount of money kept as integer minor units (cents).
+/// Stored as BIGINT so sums never drift the way `f64` amounts did.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+    Serialize, Deserialize, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = BigInt)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+    const MINOR_PER_MAJOR: i64 = 100;
+
+    pub fn from_minor(minor: i64) -> Self {
+        Money(minor)
+    }
+
+    pub fn minor(self) -> i64 {
+        self.0
+    }
+
+    pub fn abs(self) -> Self {
+        Money(self.0.abs())
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// Lossy conversion, only meant for drawing charts.
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32 / Self::MINOR_PER_MAJOR as f32
+    }
+
+    /// Parses user input such as `12`, `12.5`, `-3,40` or `1 250.00`.
+    /// More than two decimal places is rejected instead of silently rounded.
+    pub fn parse(input: &str) -> Result<Money, String> {
+        let cleaned: String = input.trim().chars().filter(|c| !c.is_whitespace()).collect();
+        if cleaned.is_empty() {
+            return Err("Amount is empty".into());
+        }
+
+        let (negative, digits) = match cleaned.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, cleaned.strip_prefix('+').unwrap_or(&cleaned)),
+        };
+
+        let normalized = digits.replace(',', ".");
+        let (whole, fraction) = match normalized.split_once('.') {
+            Some((w, f)) => (w, f),
+            None => (normalized.as_str(), ""),
+        };
+
+        let valid = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !valid(whole) || !valid(fraction) {
+            return Err(format!("'{}' is not a valid amount", input.trim()));
+        }
+        if fraction.len() > 2 {
+            return Err("Amount can have at most two decimal places".into());
+        }
+
+        let whole_val: i64 = if whole.is_empty() {
+            0
+        } else {
+            whole.parse().map_err(|_| "Amount is too large".to_string())?
+        };
+        let fraction_val: i64 = format!("{:0<2}", fraction).parse().unwrap_or(0);
+
+        let minor = whole_val
+            .checked_mul(Self::MINOR_PER_MAJOR)
+            .and_then(|v| v.checked_add(fraction_val))
+            .ok_or_else(|| "Amount is too large".to_string())?;
+
+        Ok(Money(if negative { -minor } else { minor }))
+    }
+}
+
+impl FromStr for Money {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Money::parse(s)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 {
+            "-"
+        } else if f.sign_plus() {
+            "+"
+        } else {
+            ""
+        };
+        let abs = self.0.unsigned_abs();
+        let per = Self::MINOR_PER_MAJOR as u64;
+        write!(f, "{}{}.{:02}", sign, abs / per, abs % per)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+impl<DB> ToSql<BigInt, DB> for Money
+where
+    DB: Backend,
+    i64: ToSql<BigInt, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        self.0.to_sql(out)
+    }
+}
+
+impl<DB> FromSql<BigInt, DB> for Money
+where
+    DB: Backend,
+    i64: FromSql<BigInt, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        i64::from_sql(bytes).map(Money)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minor(input: &str) -> Result<i64, String> {
+        Money::parse(input).map(Money::minor)
+    }
+
+    #[test]
+    fn parses_signs_and_decimal_commas() {
+        assert_eq!(minor("12"), Ok(1200));
+        assert_eq!(minor("12.5"), Ok(1250));
+        assert_eq!(minor("+7.05"), Ok(705));
+        assert_eq!(minor("-3,40"), Ok(-340));
+        assert_eq!(minor("-.5"), Ok(-50));
+        assert_eq!(minor(" 0.01 "), Ok(1));
+        assert!(minor("--1").is_err());
+        assert!(minor("-").is_err());
+        assert!(minor("1-").is_err());
+        assert_eq!(minor(""), Err("Amount is empty".into()));
+    }
+
+    #[test]
+    fn rejects_more_than_two_decimals() {
+        assert_eq!(minor("1.234"), Err("Amount can have at most two decimal places".into()));
+        assert_eq!(minor("0,001"), Err("Amount can have at most two decimal places".into()));
+        assert_eq!(minor("1.20"), Ok(120));
+    }
+
+    #[test]
+    fn accepts_spaces_but_not_separators_as_thousands() {
+        assert_eq!(minor("1 250.00"), Ok(125_000));
+        assert_eq!(minor("1\u{a0}250,5"), Ok(125_050));
+        // A comma is a decimal mark, so "1,250.00" has two of them and is not a number.
+        assert!(minor("1,250.00").is_err());
+        assert!(minor("1.250.00").is_err());
+        assert!(minor("12a").is_err());
+    }
+
+    #[test]
+    fn rejects_amounts_that_overflow() {
+        assert_eq!(minor("92233720368547758.07"), Ok(i64::MAX));
+        assert_eq!(minor("92233720368547758.08"), Err("Amount is too large".into()));
+        assert_eq!(minor("-92233720368547758.08"), Err("Amount is too large".into()));
+        assert_eq!(minor("99999999999999999999"), Err("Amount is too large".into()));
+    }
+
+    #[test]
+    fn formats_with_two_decimals_and_parses_back() {
+        assert_eq!(Money::from_minor(0).to_string(), "0.00");
+        assert_eq!(Money::from_minor(5).to_string(), "0.05");
+        assert_eq!(Money::from_minor(-5).to_string(), "-0.05");
+        assert_eq!(Money::from_minor(125_000).to_string(), "1250.00");
+        assert_eq!(format!("{:+}", Money::from_minor(340)), "+3.40");
+        assert_eq!(format!("{:+}", Money::from_minor(-340)), "-3.40");
+        for value in [0, 1, -1, 99, -100, 123_456, i64::MAX, i64::MIN + 1] {
+            let money = Money::from_minor(value);
+            assert_eq!(Money::parse(&money.to_string()), Ok(money));
+            assert_eq!(format!("{:+}", money).parse::<Money>(), Ok(money));
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use chrono::{Datelike, NaiveDate};
+use crate::model::Money;
+use crate::model::import::{parse_statement_amount, BankStatement, ImportedRow, StatementBalance};
+
+/// Reference banks put in `:61:` when there is none.
+const NO_REFERENCE: &str = "NONREF";
+
+/// Splits a statement into `(tag, value)` fields; continuation lines are joined to their
+/// field with a newline. Header blocks like `{1:...}` and the `-` end marker are dropped.
+fn fields(text: &str) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.is_empty() || line == "-" || line.starts_with('{') || line.starts_with("-}") {
+            continue;
+        }
+        let tagged = line
+            .strip_prefix(':')
+            .and_then(|rest| rest.split_once(':'))
+            .filter(|(tag, _)| !tag.is_empty() && tag.len() <= 3 && tag.chars().all(|c| c.is_ascii_alphanumeric()));
+        match (tagged, fields.last_mut()) {
+            (Some((tag, value)), _) => fields.push((tag.to_string(), value.to_string())),
+            (None, Some((_, value))) => {
+                value.push('\n');
+                value.push_str(line);
+            }
+            (None, None) => {}
+        }
+    }
+    fields
+}
+
+fn parse_yymmdd(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..6)?, "%y%m%d").ok()
+}
+
+/// Reads a `:60F:`/`:62F:` balance such as `C260301EUR1234,56` and returns it with its
+/// currency.
+fn parse_balance(value: &str) -> Result<(StatementBalance, String), String> {
+    let invalid = || format!("'{}' is not an MT940 balance", value);
+    let sign = value.get(..1).ok_or_else(invalid)?;
+    let date = value.get(1..7).and_then(parse_yymmdd).ok_or_else(invalid)?;
+    let currency = value.get(7..10).ok_or_else(invalid)?.to_string();
+    let amount = parse_statement_amount(value.get(10..).ok_or_else(invalid)?, ',')?;
+    let amount = if sign == "D" { -amount } else { amount };
+    Ok((StatementBalance { amount, date }, currency))
+}
+
+/// A `:61:` statement line.
+struct StatementLine {
+    date: NaiveDate,
+    amount: Money,
+    customer_reference: Option<String>,
+    bank_reference: Option<String>,
+}
+
+/// Reads a `:61:` line: value date YYMMDD, optional entry date MMDD, debit/credit mark
+/// (`D`, `C`, or `RD`/`RC` for reversals), optional funds code, amount, transaction
+/// type and the customer and bank references separated by `//`.
+fn parse_statement_line(value: &str) -> Result<StatementLine, String> {
+    let invalid = || format!("'{}' is not an MT940 statement line", value.lines().next().unwrap_or(""));
+    let first = value.lines().next().unwrap_or("");
+    let value_date = parse_yymmdd(first).ok_or_else(invalid)?;
+    let mut rest = &first[6..];
+
+    // The booking date has no year: take the value date's, moving to the neighbouring
+    // year when the dates straddle New Year.
+    let mut date = value_date;
+    if rest.get(..4).is_some_and(|d| d.len() == 4 && d.chars().all(|c| c.is_ascii_digit())) {
+        let month: u32 = rest[..2].parse().map_err(|_| invalid())?;
+        let day: u32 = rest[2..4].parse().map_err(|_| invalid())?;
+        let year = match (value_date.month(), month) {
+            (1, 12) => value_date.year() - 1,
+            (12, 1) => value_date.year() + 1,
+            _ => value_date.year(),
+        };
+        date = NaiveDate::from_ymd_opt(year, month, day).ok_or_else(invalid)?;
+        rest = &rest[4..];
+    }
+
+    let (negative, after_mark) = if let Some(r) = rest.strip_prefix("RC") {
+        (true, r)
+    } else if let Some(r) = rest.strip_prefix("RD") {
+        (false, r)
+    } else if let Some(r) = rest.strip_prefix('C') {
+        (false, r)
+    } else if let Some(r) = rest.strip_prefix('D') {
+        (true, r)
+    } else {
+        return Err(invalid());
+    };
+    rest = after_mark;
+    // Funds code: the third letter of the currency code, e.g. R for EUR.
+    if rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        rest = &rest[1..];
+    }
+    let amount_end = rest.find(|c: char| !(c.is_ascii_digit() || c == ',')).unwrap_or(rest.len());
+    let amount = parse_statement_amount(&rest[..amount_end], ',')?;
+    rest = &rest[amount_end..];
+    // Transaction type: N, F or S followed by three characters.
+    rest = rest.get(4..).unwrap_or("");
+    let (customer, bank) = match rest.split_once("//") {
+        Some((customer, bank)) => (customer, Some(bank)),
+        None => (rest, None),
+    };
+    let reference = |r: &str| Some(r.trim().to_string()).filter(|r| !r.is_empty() && r != NO_REFERENCE);
+    Ok(StatementLine {
+        date,
+        amount: if negative { -amount } else { amount },
+        customer_reference: reference(customer),
+        bank_reference: bank.and_then(reference),
+    })
+}
+
+/// Payee and comment from a `:86:` field. The structured form used by German banks
+/// (`?20`–`?29` and `?60`–`?63` remittance, `?32`/`?33` counterparty) is taken apart;
+/// anything else is taken as the comment.
+fn parse_information(value: &str) -> (Option<String>, Option<String>) {
+    let joined: String = value.lines().collect();
+    let structured = joined.get(..3).is_some_and(|code| code.chars().all(|c| c.is_ascii_digit()));
+    let marker = joined.get(3..).and_then(|rest| rest.chars().next());
+    let marker = match marker {
+        Some(c) if structured && !c.is_alphanumeric() && !c.is_whitespace() => c,
+        _ => {
+            let text = value.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+            return (None, Some(text).filter(|t| !t.is_empty()));
+        }
+    };
+    let mut name = String::new();
+    let mut remittance = String::new();
+    for part in joined.split(marker).skip(1) {
+        let code: u32 = part.get(..2).and_then(|c| c.parse().ok()).unwrap_or(0);
+        let text = part.get(2..).unwrap_or("");
+        match code {
+            32 | 33 => name.push_str(text),
+            20..=29 | 60..=63 => remittance.push_str(text),
+            _ => {}
+        }
+    }
+    let clean = |s: String| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+    (clean(name), clean(remittance))
+}
+
+/// Reads the statements of a SWIFT MT940 file. The counterparty's name from `:86:` is
+/// the payee and the remittance information the comment. `:60F:`/`:60M:` is the opening
+/// and `:62F:`/`:62M:` the closing balance.
+pub fn parse_mt940(text: &str) -> Result<Vec<BankStatement>, String> {
+    let mut statements: Vec<BankStatement> = Vec::new();
+    let mut line: Option<StatementLine> = None;
+    let finish_line = |line: Option<StatementLine>, info: Option<&str>, statement: &mut BankStatement| {
+        let line = match line {
+            Some(line) => line,
+            None => return,
+        };
+        if line.amount.is_zero() {
+            return;
+        }
+        let (name, remittance) = info.map(parse_information).unwrap_or((None, None));
+        let (payee, comment) = match (name, remittance) {
+            (Some(name), remittance) => (name, remittance),
+            (None, Some(remittance)) => (remittance, None),
+            (None, None) => (line.customer_reference.clone().unwrap_or_else(|| "Imported".to_string()), None),
+        };
+        // Bank references are not unique on their own, so date and amount go with them.
+        let external_id = line.bank_reference.as_ref().map(|reference| {
+            format!(
+                "{}/{}/{}/{}",
+                statement.account.as_deref().unwrap_or(""),
+                line.date.format("%Y%m%d"),
+                line.amount,
+                reference,
+            )
+        });
+        statement.rows.push(ImportedRow {
+            date: line.date,
+            amount: line.amount,
+            payee,
+            comment,
+            category: None,
+            splits: Vec::new(),
+            external_id,
+            currency: statement.currency.clone(),
+        });
+    };
+
+    for (tag, value) in fields(text) {
+        if tag != "86" {
+            if let Some(statement) = statements.last_mut() {
+                finish_line(line.take(), None, statement);
+            }
+        }
+        match tag.as_str() {
+            "20" => statements.push(BankStatement::default()),
+            "25" => {
+                if let Some(statement) = statements.last_mut() {
+                    statement.account = Some(value.trim().to_string());
+                }
+            }
+            "60F" | "60M" => {
+                let (balance, currency) = parse_balance(value.trim())?;
+                if let Some(statement) = statements.last_mut() {
+                    statement.opening.get_or_insert(balance);
+                    statement.currency = Some(currency);
+                }
+            }
+            "62F" | "62M" => {
+                let (balance, _) = parse_balance(value.trim())?;
+                if let Some(statement) = statements.last_mut() {
+                    statement.closing = Some(balance);
+                }
+            }
+            "61" => line = Some(parse_statement_line(&value)?),
+            "86" => {
+                if let Some(statement) = statements.last_mut() {
+                    finish_line(line.take(), Some(&value), statement);
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(statement) = statements.last_mut() {
+        finish_line(line.take(), None, statement);
+    }
+    if statements.is_empty() {
+        return Err("This is not an MT940 statement".into());
+    }
+    Ok(statements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATEMENT: &str = include_str!("../../tests/data/mt940-2025-12.sta");
+
+    fn statement() -> BankStatement {
+        let mut statements = parse_mt940(STATEMENT).unwrap();
+        assert_eq!(statements.len(), 1);
+        statements.remove(0)
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn reads_account_and_balances() {
+        let statement = statement();
+        assert_eq!(statement.account.as_deref(), Some("37040044/0532013000"));
+        assert_eq!(statement.currency.as_deref(), Some("EUR"));
+        assert_eq!(statement.opening, Some(StatementBalance { amount: Money::from_minor(100000), date: date(2025, 12, 29) }));
+        assert_eq!(statement.closing, Some(StatementBalance { amount: Money::from_minor(337010), date: date(2026, 1, 2) }));
+    }
+
+    #[test]
+    fn complete_statement_balances() {
+        assert_eq!(statement().balance_difference(), Some(Money::ZERO));
+    }
+
+    #[test]
+    fn booking_date_takes_the_neighbouring_year() {
+        let rows = statement().rows;
+        assert_eq!(rows[0].date, date(2025, 12, 29));
+        // Value date 2026-01-02, booked on 31 December.
+        assert_eq!(rows[1].date, date(2025, 12, 31));
+        // Value date 2025-12-30, booked on 2 January.
+        assert_eq!(rows[4].date, date(2026, 1, 2));
+    }
+
+    #[test]
+    fn reversals_flip_the_sign() {
+        let rows = statement().rows;
+        assert_eq!(rows[0].amount, Money::from_minor(-4990));
+        assert_eq!(rows[1].amount, Money::from_minor(250000));
+        assert_eq!(rows[2].amount, Money::from_minor(-10000));
+        assert_eq!(rows[3].amount, Money::from_minor(3000));
+        assert_eq!(rows[4].amount, Money::from_minor(-1000));
+    }
+
+    #[test]
+    fn reads_structured_information() {
+        let rows = statement().rows;
+        assert_eq!(rows[0].payee, "Stadtwerke");
+        assert_eq!(rows[0].comment.as_deref(), Some("SVWZ+Strom Dezember 2025"));
+        assert_eq!(rows[1].payee, "ACME GmbH");
+        assert_eq!(rows[1].comment.as_deref(), Some("SVWZ+Gehalt Dezember"));
+    }
+
+    #[test]
+    fn reads_unstructured_information() {
+        let rows = statement().rows;
+        assert_eq!(rows[2].payee, "Reversal of an incoming payment");
+        assert_eq!(rows[2].comment, None);
+        // Without `:86:` the customer reference names the line.
+        assert_eq!(rows[4].payee, "KUNDENREF");
+    }
+
+    #[test]
+    fn external_ids_need_a_bank_reference() {
+        let rows = statement().rows;
+        assert_eq!(rows[0].external_id.as_deref(), Some("37040044/0532013000/20251229/-49.90/BANKREF1"));
+        assert_eq!(rows[4].external_id, None);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(parse_mt940("Date,Amount\n2026-01-02,1.00").is_err());
+        assert!(parse_mt940(":20:X\n:60F:X251229EUR").is_err());
+    }
+}
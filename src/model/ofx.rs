@@ -0,0 +1,280 @@
+use chrono::NaiveDate;
+use crate::model::Money;
+use crate::model::import::{parse_statement_amount, BankStatement, ImportedRow, StatementBalance};
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Open(String),
+    Close(String),
+    Text(&'a str),
+}
+
+/// Splits the body into tags and text. Works for OFX 1.x SGML, where leaf elements are
+/// not closed, as well as for OFX 2.x XML; headers, processing instructions and
+/// comments are dropped.
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = match text.find('<') {
+        Some(start) => &text[start..],
+        None => return tokens,
+    };
+    while let Some(end) = rest.find('>') {
+        let tag = rest[1..end].trim();
+        if let Some(name) = tag.strip_prefix('/') {
+            tokens.push(Token::Close(name.trim().to_uppercase()));
+        } else if !tag.starts_with('?') && !tag.starts_with('!') {
+            // XML allows attributes and empty elements; OFX uses neither, so only the name matters.
+            let name = tag.split_whitespace().next().unwrap_or("").trim_end_matches('/');
+            tokens.push(Token::Open(name.to_uppercase()));
+        }
+        rest = &rest[end + 1..];
+        let next = rest.find('<').unwrap_or(rest.len());
+        let value = rest[..next].trim();
+        if !value.is_empty() {
+            tokens.push(Token::Text(value));
+        }
+        rest = &rest[next..];
+        if rest.is_empty() {
+            break;
+        }
+    }
+    tokens
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// Reads the date part of an OFX datetime such as `20260301120000.000[-5:EST]`.
+fn parse_ofx_date(value: &str) -> Result<NaiveDate, String> {
+    value
+        .get(..8)
+        .and_then(|day| NaiveDate::parse_from_str(day, "%Y%m%d").ok())
+        .ok_or_else(|| format!("'{}' is not an OFX date", value))
+}
+
+fn parse_ofx_amount(value: &str) -> Result<Money, String> {
+    // The spec allows a comma as decimal separator; thousands separators are not allowed.
+    let decimal = if value.contains(',') && !value.contains('.') { ',' } else { '.' };
+    parse_statement_amount(value, decimal)
+}
+
+/// Leaf values of one STMTTRN aggregate.
+#[derive(Default)]
+struct OfxTransaction {
+    kind: Option<String>,
+    posted: Option<String>,
+    amount: Option<String>,
+    fitid: Option<String>,
+    name: Option<String>,
+    memo: Option<String>,
+    check_number: Option<String>,
+}
+
+impl OfxTransaction {
+    fn set(&mut self, tag: &str, value: String) {
+        match tag {
+            "TRNTYPE" => self.kind = Some(value),
+            "DTPOSTED" => self.posted = Some(value),
+            "TRNAMT" => self.amount = Some(value),
+            "FITID" => self.fitid = Some(value),
+            "NAME" => self.name = Some(value),
+            "MEMO" => self.memo = Some(value),
+            "CHECKNUM" => self.check_number = Some(value),
+            _ => {}
+        }
+    }
+
+    /// `None` for lines with a zero amount, which some banks send as notices.
+    fn into_row(self, statement: &BankStatement) -> Result<Option<ImportedRow>, String> {
+        let date = parse_ofx_date(self.posted.as_deref().ok_or("Transaction without DTPOSTED")?)?;
+        let amount = parse_ofx_amount(self.amount.as_deref().ok_or("Transaction without TRNAMT")?)?;
+        if amount.is_zero() {
+            return Ok(None);
+        }
+        let payee = self.name.clone()
+            .or_else(|| self.memo.clone())
+            .or_else(|| self.check_number.as_ref().map(|n| format!("Check {}", n)))
+            .or(self.kind)
+            .unwrap_or_else(|| "Imported".to_string());
+        let comment = self.memo.filter(|memo| *memo != payee);
+        // FITIDs are only unique within one account, so the account number is kept with them.
+        let external_id = self.fitid.map(|fitid| match &statement.account {
+            Some(account) => format!("{}/{}", account, fitid),
+            None => fitid,
+        });
+        Ok(Some(ImportedRow {
+            date,
+            amount,
+            payee,
+            comment,
+            category: None,
+            splits: Vec::new(),
+            external_id,
+            currency: statement.currency.clone(),
+        }))
+    }
+}
+
+/// Reads every bank and credit card statement in an OFX or QFX file; a file can hold
+/// several, e.g. a checking account and a credit card downloaded together. The account
+/// is the ACCTID, the currency CURDEF and the closing balance LEDGERBAL. OFX has no
+/// opening balance.
+pub fn parse_ofx(text: &str) -> Result<Vec<BankStatement>, String> {
+    let tokens = tokenize(text);
+    if !tokens.iter().any(|t| *t == Token::Open("OFX".into())) {
+        return Err("This is not an OFX file".into());
+    }
+
+    let mut statements = Vec::new();
+    let mut statement: Option<BankStatement> = None;
+    let mut transaction: Option<OfxTransaction> = None;
+    let mut balance: Option<(Option<String>, Option<String>)> = None;
+    let mut current_tag = String::new();
+    for token in tokens {
+        match token {
+            Token::Open(tag) => {
+                match tag.as_str() {
+                    "STMTRS" | "CCSTMTRS" => statement = Some(BankStatement::default()),
+                    "STMTTRN" => transaction = Some(OfxTransaction::default()),
+                    "LEDGERBAL" => balance = Some((None, None)),
+                    _ => {}
+                }
+                current_tag = tag;
+            }
+            Token::Text(value) => {
+                let value = unescape(value);
+                if let Some(tx) = transaction.as_mut() {
+                    tx.set(&current_tag, value);
+                } else if let Some((amount, date)) = balance.as_mut() {
+                    match current_tag.as_str() {
+                        "BALAMT" => *amount = Some(value),
+                        "DTASOF" => *date = Some(value),
+                        _ => {}
+                    }
+                } else if let Some(st) = statement.as_mut() {
+                    match current_tag.as_str() {
+                        "ACCTID" => st.account = Some(value),
+                        "CURDEF" => st.currency = Some(value.to_uppercase()),
+                        _ => {}
+                    }
+                }
+            }
+            Token::Close(tag) => match tag.as_str() {
+                "STMTTRN" => {
+                    if let (Some(tx), Some(st)) = (transaction.take(), statement.as_mut()) {
+                        if let Some(row) = tx.into_row(st)? {
+                            st.rows.push(row);
+                        }
+                    }
+                }
+                "LEDGERBAL" => {
+                    if let (Some((Some(amount), Some(date))), Some(st)) = (balance.take(), statement.as_mut()) {
+                        st.closing = Some(StatementBalance { amount: parse_ofx_amount(&amount)?, date: parse_ofx_date(&date)? });
+                    }
+                }
+                "STMTRS" | "CCSTMTRS" => statements.extend(statement.take()),
+                _ => {}
+            },
+        }
+    }
+    if statements.is_empty() {
+        return Err("The OFX file holds no bank or credit card statement".into());
+    }
+    Ok(statements)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use super::*;
+    use crate::model::import::skip_duplicates;
+
+    const CHECKING_FEBRUARY: &str = include_str!("../../tests/data/checking-2026-02.ofx");
+    const CHECKING_MARCH: &str = include_str!("../../tests/data/checking-2026-03.ofx");
+    const CREDIT_CARD: &str = include_str!("../../tests/data/creditcard-2026-02.ofx");
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn reads_sgml_with_unclosed_leaf_tags() {
+        let statements = parse_ofx(CHECKING_FEBRUARY).unwrap();
+        assert_eq!(statements.len(), 1);
+        let statement = &statements[0];
+        assert_eq!(statement.account.as_deref(), Some("12345678"));
+        assert_eq!(statement.currency.as_deref(), Some("USD"));
+        assert_eq!(statement.closing, Some(StatementBalance { amount: Money::from_minor(333783), date: date(2026, 2, 28) }));
+        assert_eq!(statement.opening, None);
+
+        // The zero-amount notice is left out.
+        let rows = &statement.rows;
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].date, date(2026, 2, 3));
+        assert_eq!(rows[0].amount, Money::from_minor(-4217));
+        assert_eq!(rows[0].payee, "Corner Grocery & Deli");
+        assert_eq!(rows[0].comment.as_deref(), Some("Card 1234"));
+        assert_eq!(rows[0].external_id.as_deref(), Some("12345678/2026020301"));
+        assert_eq!(rows[0].currency.as_deref(), Some("USD"));
+        assert_eq!(rows[1].amount, Money::from_minor(250000));
+        assert_eq!(rows[1].comment, None);
+        assert_eq!(rows[2].payee, "Check 1042");
+    }
+
+    #[test]
+    fn reads_xml() {
+        let statements = parse_ofx(CREDIT_CARD).unwrap();
+        assert_eq!(statements.len(), 1);
+        let statement = &statements[0];
+        assert_eq!(statement.account.as_deref(), Some("4111000011112222"));
+        assert_eq!(statement.currency.as_deref(), Some("EUR"));
+        assert_eq!(statement.closing.map(|b| b.amount), Some(Money::from_minor(-4990)));
+
+        let rows = &statement.rows;
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].date, date(2026, 2, 7));
+        assert_eq!(rows[0].amount, Money::from_minor(-6490));
+        assert_eq!(rows[0].payee, "Bookshop <Online>");
+        assert_eq!(rows[0].comment.as_deref(), Some("Order 5521"));
+        assert_eq!(rows[1].amount, Money::from_minor(1500));
+        assert_eq!(rows[1].payee, "Refund");
+        assert_eq!(rows[1].external_id.as_deref(), Some("4111000011112222/2026021201"));
+    }
+
+    #[test]
+    fn overlapping_statement_imports_only_new_lines() {
+        let february = parse_ofx(CHECKING_FEBRUARY).unwrap().remove(0);
+        let mut seen = HashSet::new();
+        let (kept, duplicates) = skip_duplicates(&february.rows, &mut seen);
+        assert_eq!((kept.len(), duplicates), (3, 0));
+
+        let march = parse_ofx(CHECKING_MARCH).unwrap().remove(0);
+        let (kept, duplicates) = skip_duplicates(&march.rows, &mut seen);
+        assert_eq!(duplicates, 2);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].external_id.as_deref(), Some("12345678/2026030401"));
+    }
+
+    #[test]
+    fn same_fitid_in_another_account_is_not_a_duplicate() {
+        let checking = parse_ofx(CHECKING_FEBRUARY).unwrap().remove(0);
+        let card = parse_ofx(CREDIT_CARD).unwrap().remove(0);
+        let mut seen = HashSet::new();
+        skip_duplicates(&checking.rows, &mut seen);
+        let (kept, duplicates) = skip_duplicates(&card.rows, &mut seen);
+        assert_eq!((kept.len(), duplicates), (2, 0));
+    }
+
+    #[test]
+    fn rejects_files_without_a_statement() {
+        assert!(parse_ofx("Date,Amount\n2026-02-01,1.00").is_err());
+        assert!(parse_ofx("<OFX><SIGNONMSGSRSV1></SIGNONMSGSRSV1></OFX>").is_err());
+    }
+}
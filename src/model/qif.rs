@@ -0,0 +1,477 @@
+use chrono::{Datelike, NaiveDate};
+use crate::model::{Account, Category, Money, Transaction};
+use crate::model::category::ancestry;
+use crate::model::import::{parse_statement_amount, ImportedRow, ImportedSplit};
+
+/// Sections whose records are bank-style transactions. Investment, memorized and list
+/// sections are skipped.
+const QIF_ACCOUNT_TYPES: [&str; 3] = ["bank", "ccard", "cash"];
+
+/// Separates parent and child in QIF category names, e.g. `Food:Groceries`.
+const QIF_CATEGORY_SEPARATOR: char = ':';
+
+/// Reads a QIF date such as `03/01/2026`, `3/ 1'26`, `01.03.2026` or `2026-03-01`. Day and
+/// month come in the order Quicken uses (month first) unless `day_first` is set or the
+/// parts are separated by dots; a year with two digits below 70 is in the 2000s.
+fn parse_qif_date(value: &str, day_first: bool) -> Result<NaiveDate, String> {
+    let cleaned: String = value.chars().filter(|c| !c.is_whitespace()).map(|c| if c == '\'' { '/' } else { c }).collect();
+    let parts: Vec<&str> = cleaned.split(['/', '.', '-']).collect();
+    let invalid = || format!("'{}' is not a QIF date", value.trim());
+    if parts.len() != 3 {
+        return Err(invalid());
+    }
+    let numbers = parts
+        .iter()
+        .map(|p| p.parse::<u32>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (year, month, day) = if parts[0].len() == 4 {
+        (numbers[0], numbers[1], numbers[2])
+    } else if day_first || cleaned.contains('.') {
+        (numbers[2], numbers[1], numbers[0])
+    } else {
+        (numbers[2], numbers[0], numbers[1])
+    };
+    let year = match (parts[0].len() == 4, year) {
+        (false, y) if y < 70 => 2000 + y,
+        (false, y) if y < 100 => 1900 + y,
+        (_, y) => y,
+    };
+    NaiveDate::from_ymd_opt(year as i32, month, day).ok_or_else(invalid)
+}
+
+/// QIF amounts follow the exporting program's locale: `1,234.56` or `1.234,56`.
+fn parse_qif_amount(value: &str) -> Result<Money, String> {
+    let decimal = match (value.rfind('.'), value.rfind(',')) {
+        (Some(dot), Some(comma)) if comma > dot => ',',
+        (None, Some(comma)) if value.len() - comma - 1 <= 2 => ',',
+        _ => '.',
+    };
+    parse_statement_amount(value, decimal)
+}
+
+/// `[Account]` names the other side of a transfer and `/Class` is a tag; neither is a category.
+fn qif_category_path(value: String) -> Option<String> {
+    Some(value)
+        .filter(|c| !c.starts_with('['))
+        .map(|c| c.split('/').next().unwrap_or("").trim().to_string())
+        .filter(|c| !c.is_empty())
+}
+
+/// An `S` category and `$` amount pair of a split record.
+struct QifSplit {
+    category: Option<String>,
+    amount: Option<String>,
+}
+
+#[derive(Default)]
+struct QifRecord {
+    date: Option<String>,
+    amount: Option<String>,
+    payee: Option<String>,
+    memo: Option<String>,
+    category: Option<String>,
+    number: Option<String>,
+    splits: Vec<QifSplit>,
+}
+
+impl QifRecord {
+    fn is_empty(&self) -> bool {
+        self.date.is_none() && self.amount.is_none()
+    }
+
+    fn into_row(self, day_first: bool) -> Result<Option<ImportedRow>, String> {
+        let date = parse_qif_date(self.date.as_deref().ok_or("Record without a date (D line)")?, day_first)?;
+        let amount = parse_qif_amount(self.amount.as_deref().ok_or("Record without an amount (T line)")?)?;
+        if amount.is_zero() {
+            return Ok(None);
+        }
+        let payee = self.payee.clone()
+            .or_else(|| self.memo.clone())
+            .or_else(|| self.number.as_ref().map(|n| format!("Check {}", n)))
+            .unwrap_or_else(|| "Imported".to_string());
+        let mut category = self.category.and_then(qif_category_path);
+        let mut splits = Vec::new();
+        for (i, split) in self.splits.into_iter().enumerate() {
+            let value = split.amount.ok_or_else(|| format!("Split line {} without an amount ($ line)", i + 1))?;
+            let split_amount = parse_qif_amount(&value)?;
+            if split_amount.is_zero() {
+                continue;
+            }
+            let split_category = split.category
+                .and_then(qif_category_path)
+                .ok_or_else(|| format!("Split line {} has no category; only categorized split lines can be imported", i + 1))?;
+            if split_amount.is_negative() != amount.is_negative() {
+                return Err(format!("Split line {} goes the other way than the total", i + 1));
+            }
+            splits.push(ImportedSplit { category: split_category, amount: split_amount });
+        }
+        if !splits.is_empty() {
+            let total: Money = splits.iter().map(|s| s.amount).sum();
+            if total != amount {
+                return Err(format!("Split lines add up to {} but the total is {}", total, amount));
+            }
+            // The L line of a split record names the first split or says `--Splits--`.
+            category = None;
+        }
+        if splits.len() == 1 {
+            category = splits.pop().map(|s| s.category);
+        }
+        Ok(Some(ImportedRow {
+            date,
+            amount,
+            comment: self.memo.filter(|memo| *memo != payee),
+            payee,
+            category,
+            splits,
+            external_id: None,
+            currency: None,
+        }))
+    }
+}
+
+/// Reads the bank, cash and credit card transactions of a QIF file. Category lines
+/// (`L`) are kept as `Parent:Child` paths on the rows, split lines (`S` and `$`) as the
+/// rows' splits. Split records whose lines lack a category or don't add up are rejected.
+pub fn parse_qif(text: &str, day_first: bool) -> Result<Vec<ImportedRow>, String> {
+    let mut rows = Vec::new();
+    let mut in_bank_section = false;
+    let mut found_section = false;
+    let mut record = QifRecord::default();
+    let mut record_number = 0;
+    for line in text.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim_end();
+        if let Some(header) = line.strip_prefix('!') {
+            let header = header.trim().to_lowercase();
+            // `!Option:` and `!Clear:` switches don't change the current section.
+            if header.starts_with("type:") || header == "account" {
+                in_bank_section = header
+                    .strip_prefix("type:")
+                    .is_some_and(|kind| QIF_ACCOUNT_TYPES.contains(&kind.trim()));
+                found_section |= in_bank_section;
+            }
+            record = QifRecord::default();
+            continue;
+        }
+        if !in_bank_section || line.is_empty() {
+            continue;
+        }
+        let mut chars = line.chars();
+        let code = chars.next().unwrap_or(' ');
+        let value = chars.as_str().trim().to_string();
+        match code {
+            '^' => {
+                record_number += 1;
+                let finished = std::mem::take(&mut record);
+                if !finished.is_empty() {
+                    let row = finished.into_row(day_first).map_err(|e| format!("Record {}: {}", record_number, e))?;
+                    rows.extend(row);
+                }
+            }
+            'D' => record.date = Some(value),
+            // Newer Quicken versions write the amount twice, as T and U.
+            'T' => record.amount = Some(value),
+            'U' => {
+                record.amount.get_or_insert(value);
+            }
+            'P' => record.payee = Some(value),
+            'M' => record.memo = Some(value),
+            'L' => record.category = Some(value),
+            'N' => record.number = Some(value),
+            'S' => record.splits.push(QifSplit { category: Some(value), amount: None }),
+            '$' => match record.splits.last_mut() {
+                Some(split) if split.amount.is_none() => split.amount = Some(value),
+                _ => record.splits.push(QifSplit { category: None, amount: Some(value) }),
+            },
+            _ => {}
+        }
+    }
+    if !found_section {
+        return Err("The QIF file holds no bank, cash or credit card transactions".into());
+    }
+    Ok(rows)
+}
+
+fn qif_category(categories: &[Category], tag_id: Option<i32>) -> Option<String> {
+    let path: Vec<String> = ancestry(categories, tag_id?)
+        .iter()
+        .map(|c| c.name.replace(QIF_CATEGORY_SEPARATOR, "-"))
+        .collect();
+    Some(path.join(&QIF_CATEGORY_SEPARATOR.to_string())).filter(|p| !p.is_empty())
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn write_record(out: &mut String, date: NaiveDate, amount: Money, payee: &str, memo: Option<&str>, category: Option<String>) {
+    out.push_str(&format!("D{:02}/{:02}/{}\n", date.month(), date.day(), date.year()));
+    out.push_str(&format!("T{}\n", amount));
+    out.push_str(&format!("P{}\n", one_line(payee)));
+    if let Some(memo) = memo.map(one_line).filter(|m| !m.is_empty()) {
+        out.push_str(&format!("M{}\n", memo));
+    }
+    if let Some(category) = category {
+        out.push_str(&format!("L{}\n", category));
+    }
+}
+
+/// Writes the transactions as QIF, one `!Type:Bank` section per account with transactions
+/// without an account first. Dates are month first like Quicken's. Transfers appear in
+/// both accounts with the other account in brackets. QIF has no currencies, so amounts
+/// are written as booked.
+pub fn export_qif(transactions: &[Transaction], categories: &[Category], accounts: &[Account]) -> String {
+    let account_name = |id: Option<i32>| {
+        id.and_then(|id| accounts.iter().find(|a| a.id == id))
+            .map(|a| a.name.clone())
+            .unwrap_or_else(|| "Unassigned".to_string())
+    };
+    let mut sections: Vec<Option<i32>> = vec![None];
+    sections.extend(accounts.iter().map(|a| Some(a.id)));
+
+    let mut out = String::new();
+    for section in sections {
+        let mut body = String::new();
+        let mut sorted: Vec<&Transaction> = transactions
+            .iter()
+            .filter(|t| t.account_id == section || (t.is_transfer() && t.transfer_account_id == section))
+            .collect();
+        sorted.sort_by_key(|t| t.date);
+        for tx in sorted {
+            let date = tx.date.date();
+            if tx.is_transfer() {
+                let (amount, other) = if tx.account_id == section {
+                    (-tx.tran_amount, tx.transfer_account_id)
+                } else {
+                    (tx.tran_amount, tx.account_id)
+                };
+                let category = Some(format!("[{}]", account_name(other)));
+                write_record(&mut body, date, amount, &tx.tran_source, tx.tran_comment.as_deref(), category);
+            } else {
+                let amount = if tx.tran_type == "Expense" { -tx.tran_amount } else { tx.tran_amount };
+                let category = qif_category(categories, tx.tag_id);
+                write_record(&mut body, date, amount, &tx.tran_source, tx.tran_comment.as_deref(), category);
+                for split in &tx.splits {
+                    let amount = if tx.tran_type == "Expense" { -split.amount } else { split.amount };
+                    if let Some(category) = qif_category(categories, split.tag_id) {
+                        body.push_str(&format!("S{}\n", category));
+                    }
+                    body.push_str(&format!("${}\n", amount));
+                }
+            }
+            body.push_str("^\n");
+        }
+        if body.is_empty() {
+            continue;
+        }
+        if section.is_some() {
+            out.push_str(&format!("!Account\nN{}\nTBank\n^\n", one_line(&account_name(section))));
+        }
+        out.push_str("!Type:Bank\n");
+        out.push_str(&body);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+    use super::*;
+    use crate::model::SplitLine;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn dates_are_month_first_unless_told_otherwise() {
+        assert_eq!(parse_qif_date("03/01/2026", false), Ok(date(2026, 3, 1)));
+        assert_eq!(parse_qif_date("03/01/2026", true), Ok(date(2026, 1, 3)));
+        assert_eq!(parse_qif_date("3/ 1'26", false), Ok(date(2026, 3, 1)));
+        assert_eq!(parse_qif_date("01.03.2026", false), Ok(date(2026, 3, 1)));
+        assert_eq!(parse_qif_date("2026-03-01", true), Ok(date(2026, 3, 1)));
+        assert_eq!(parse_qif_date("12/31/99", false), Ok(date(1999, 12, 31)));
+        assert!(parse_qif_date("13/01/2026", false).is_err());
+        assert!(parse_qif_date("March 1", false).is_err());
+    }
+
+    #[test]
+    fn amounts_follow_the_exporting_locale() {
+        assert_eq!(parse_qif_amount("-1,234.56"), Ok(Money::from_minor(-123456)));
+        assert_eq!(parse_qif_amount("-1.234,56"), Ok(Money::from_minor(-123456)));
+        assert_eq!(parse_qif_amount("12,5"), Ok(Money::from_minor(1250)));
+        assert_eq!(parse_qif_amount("1,000"), Ok(Money::from_minor(100000)));
+    }
+
+    #[test]
+    fn reads_bank_sections_only() {
+        let text = "\
+!Type:Cat
+NFood
+E
+^
+!Type:Bank
+D03/01/2026
+T-42.50
+PBakery
+MRolls
+LFood:Bakery/Weekend
+^
+D03/02'26
+U1,500.00
+T1,500.00
+PEmployer
+LSalary
+^
+!Option:AutoSwitch
+D03/03/2026
+T-100.00
+PSavings
+L[Savings]
+^
+!Type:Invst
+D03/04/2026
+T-999.00
+PBroker
+^
+!Type:CCard
+D03/05/2026
+T0.00
+PNotice
+^
+D03/06/2026
+T-8.00
+N1042
+^
+";
+        let rows = parse_qif(text, false).unwrap();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0].date, date(2026, 3, 1));
+        assert_eq!(rows[0].amount, Money::from_minor(-4250));
+        assert_eq!(rows[0].payee, "Bakery");
+        assert_eq!(rows[0].comment.as_deref(), Some("Rolls"));
+        assert_eq!(rows[0].category.as_deref(), Some("Food:Bakery"));
+        assert_eq!(rows[1].amount, Money::from_minor(150000));
+        assert_eq!(rows[1].category.as_deref(), Some("Salary"));
+        // A transfer names an account, not a category.
+        assert_eq!(rows[2].category, None);
+        assert_eq!(rows[3].date, date(2026, 3, 6));
+        assert_eq!(rows[3].payee, "Check 1042");
+    }
+
+    #[test]
+    fn a_record_ends_at_the_caret() {
+        let text = "!Type:Bank\nD03/01/2026\nT-1.00\nPFirst\n^\nD03/02/2026\nT-2.00\nPSecond\n";
+        let rows = parse_qif(text, false).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].payee, "First");
+        assert_eq!(parse_qif("!Type:Bank\nT-1.00\nPNo date\n^\n", false).err().as_deref(), Some("Record 1: Record without a date (D line)"));
+    }
+
+    #[test]
+    fn reads_split_records() {
+        let text = "\
+!Type:Bank
+D03/01/2026
+T-100.00
+PSupermarket
+L--Splits--
+SFood:Groceries
+EWeekly shop
+$-70.00
+SHousehold
+$-30.00
+^
+";
+        let rows = parse_qif(text, false).unwrap();
+        assert_eq!(rows[0].category, None);
+        assert_eq!(
+            rows[0].splits,
+            vec![
+                ImportedSplit { category: "Food:Groceries".into(), amount: Money::from_minor(-7000) },
+                ImportedSplit { category: "Household".into(), amount: Money::from_minor(-3000) },
+            ]
+        );
+        assert_eq!(rows[0].category_label().as_deref(), Some("Food:Groceries 70.00 + Household 30.00"));
+    }
+
+    #[test]
+    fn rejects_splits_the_app_cannot_book() {
+        let record = |splits: &str| format!("!Type:Bank\nD03/01/2026\nT-100.00\nPShop\n{}^\n", splits);
+        let error = |splits: &str| parse_qif(&record(splits), false).err().unwrap();
+        assert!(error("SFood\n$-70.00\nSHome\n$-20.00\n").contains("add up to -90.00"));
+        assert!(error("SFood\n$-70.00\n$-30.00\n").contains("Split line 2 has no category"));
+        assert!(error("SFood\n$-130.00\nSRefund\n$30.00\n").contains("Split line 2 goes the other way"));
+        // A single line is just the category.
+        let rows = parse_qif(&record("SFood\n$-100.00\n"), false).unwrap();
+        assert_eq!((rows[0].category.as_deref(), rows[0].splits.len()), (Some("Food"), 0));
+    }
+
+    #[test]
+    fn files_without_bank_sections_are_rejected() {
+        assert!(parse_qif("!Type:Invst\nD03/01/2026\nT-1.00\n^\n", false).is_err());
+        assert!(parse_qif("Date,Amount\n", false).is_err());
+    }
+
+    fn category(id: i32, name: &str, parent_id: Option<i32>, kind: &str) -> Category {
+        Category { id, name: name.into(), user_id: Some(1), color: "#888888".into(), parent_id, kind: kind.into() }
+    }
+
+    fn transaction(id: i32, kind: &str, minor: i64, tag_id: Option<i32>, splits: Vec<SplitLine>) -> Transaction {
+        Transaction {
+            tran_id: id,
+            tran_type: kind.into(),
+            user_id: 1,
+            tran_source: format!("Payee {}", id),
+            date: date(2026, 3, id as u32).and_time(NaiveTime::MIN),
+            tran_amount: Money::from_minor(minor),
+            tran_comment: Some("Note\nacross lines".into()),
+            tag_id,
+            currency: "EUR".into(),
+            account_id: Some(1),
+            transfer_account_id: None,
+            deleted_at: None,
+            external_id: None,
+            splits,
+        }
+    }
+
+    #[test]
+    fn exported_files_read_back() {
+        let categories = vec![
+            category(1, "Food", None, "expense"),
+            category(2, "Groceries", Some(1), "expense"),
+            category(3, "Home", None, "expense"),
+            category(4, "Salary", None, "income"),
+        ];
+        let accounts = vec![Account { id: 1, user_id: 1, name: "Main".into() }];
+        let split = vec![
+            SplitLine { tag_id: Some(2), amount: Money::from_minor(2500) },
+            SplitLine { tag_id: Some(3), amount: Money::from_minor(1500) },
+        ];
+        let transactions = vec![
+            transaction(1, "Expense", 1234, Some(2), Vec::new()),
+            transaction(2, "Income", 300000, Some(4), Vec::new()),
+            transaction(3, "Expense", 4000, None, split),
+        ];
+
+        let rows = parse_qif(&export_qif(&transactions, &categories, &accounts), false).unwrap();
+        assert_eq!(rows.len(), 3);
+        for (row, tx) in rows.iter().zip(&transactions) {
+            assert_eq!(row.date, tx.date.date());
+            assert_eq!(row.amount.abs(), tx.tran_amount);
+            assert_eq!(row.kind(), tx.tran_type);
+            assert_eq!(row.payee, tx.tran_source);
+            assert_eq!(row.comment.as_deref(), Some("Note across lines"));
+        }
+        assert_eq!(rows[0].category.as_deref(), Some("Food:Groceries"));
+        assert_eq!(rows[1].category.as_deref(), Some("Salary"));
+        assert_eq!(rows[2].category, None);
+        assert_eq!(
+            rows[2].splits,
+            vec![
+                ImportedSplit { category: "Food:Groceries".into(), amount: Money::from_minor(-2500) },
+                ImportedSplit { category: "Home".into(), amount: Money::from_minor(-1500) },
+            ]
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveTime, Weekday};
+use diesel::prelude::*;
+use crate::model::{Money, TransactionDraft};
+use crate::schema::recurring_rules;
+
+pub const FREQUENCY_DAILY: &str = "daily";
+pub const FREQUENCY_WEEKLY: &str = "weekly";
+pub const FREQUENCY_MONTHLY: &str = "monthly";
+pub const FREQUENCY_YEARLY: &str = "yearly";
+pub const FREQUENCY_LAST_BUSINESS_DAY: &str = "last business day";
+pub const FREQUENCIES: [&str; 5] = [
+    FREQUENCY_DAILY,
+    FREQUENCY_WEEKLY,
+    FREQUENCY_MONTHLY,
+    FREQUENCY_YEARLY,
+    FREQUENCY_LAST_BUSINESS_DAY,
+];
+pub const RULE_TYPES: [&str; 2] = ["Expense", "Income"];
+
+/// A transaction that repeats, such as rent or salary. Occurrences up to `last_run`
+/// have already been booked as ordinary transactions.
+#[derive(Debug, Clone, Queryable)]
+pub struct RecurringRule {
+    pub id: i32,
+    pub user_id: i32,
+    pub tran_type: String,
+    pub source: String,
+    pub amount: Money,
+    pub currency: String,
+    pub tag_id: Option<i32>,
+    pub account_id: Option<i32>,
+    pub frequency: String,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub last_run: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = recurring_rules)]
+pub struct NewRecurringRule {
+    pub user_id: i32,
+    pub tran_type: String,
+    pub source: String,
+    pub amount: Money,
+    pub currency: String,
+    pub tag_id: Option<i32>,
+    pub account_id: Option<i32>,
+    pub frequency: String,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+}
+
+impl RecurringRule {
+    /// Every occurrence in order, up to the end date.
+    pub fn occurrences(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        (0..)
+            .map_while(move |n| occurrence(&self.frequency, self.start_date, n))
+            .take_while(move |date| self.end_date.is_none_or(|end| *date <= end))
+    }
+
+    /// Occurrences not booked yet, oldest first.
+    pub fn pending(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        self.occurrences()
+            .skip_while(move |date| self.last_run.is_some_and(|last| *date <= last))
+    }
+
+    /// Occurrences that should have been booked by `today`.
+    pub fn due(&self, today: NaiveDate) -> Vec<NaiveDate> {
+        self.pending().take_while(|date| *date <= today).collect()
+    }
+
+    pub fn upcoming(&self, count: usize) -> Vec<NaiveDate> {
+        self.pending().take(count).collect()
+    }
+
+    /// The transaction booked for the occurrence on `date`.
+    pub fn draft(&self, date: NaiveDate) -> TransactionDraft {
+        TransactionDraft {
+            source: self.source.clone(),
+            date: date.and_time(NaiveTime::MIN),
+            amount: self.amount,
+            currency: self.currency.clone(),
+            account_id: self.account_id,
+            tag_id: self.tag_id,
+            comment: None,
+            splits: Vec::new(),
+        }
+    }
+}
+
+pub fn is_frequency(frequency: &str) -> bool {
+    FREQUENCIES.contains(&frequency)
+}
+
+/// The `n`-th occurrence, counting from zero. Monthly and yearly rules keep the day of
+/// the start date where the month has it and fall back to the month's last day otherwise.
+fn occurrence(frequency: &str, start: NaiveDate, n: u32) -> Option<NaiveDate> {
+    match frequency {
+        FREQUENCY_DAILY => start.checked_add_signed(Duration::days(n as i64)),
+        FREQUENCY_WEEKLY => start.checked_add_signed(Duration::weeks(n as i64)),
+        FREQUENCY_MONTHLY => start.checked_add_months(Months::new(n)),
+        FREQUENCY_YEARLY => start.checked_add_months(Months::new(n.checked_mul(12)?)),
+        FREQUENCY_LAST_BUSINESS_DAY => {
+            // The first occurrence is in the start month unless its last business day has passed.
+            let skip = u32::from(last_business_day(start)? < start);
+            let month = start.with_day(1)?.checked_add_months(Months::new(n.checked_add(skip)?))?;
+            last_business_day(month)
+        }
+        _ => None,
+    }
+}
+
+/// Last Monday to Friday of the month `date` falls in.
+fn last_business_day(date: NaiveDate) -> Option<NaiveDate> {
+    let mut day = date.with_day(1)?.checked_add_months(Months::new(1))?.pred_opt()?;
+    while matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+        day = day.pred_opt()?;
+    }
+    Some(day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn dates(list: &[&str]) -> Vec<NaiveDate> {
+        list.iter().map(|s| date(s)).collect()
+    }
+
+    fn rule(frequency: &str, start: &str) -> RecurringRule {
+        RecurringRule {
+            id: 1,
+            user_id: 1,
+            tran_type: "Expense".into(),
+            source: "Rent".into(),
+            amount: Money::from_minor(50_000),
+            currency: "EUR".into(),
+            tag_id: None,
+            account_id: None,
+            frequency: frequency.into(),
+            start_date: date(start),
+            end_date: None,
+            last_run: None,
+        }
+    }
+
+    #[test]
+    fn monthly_rules_clamp_to_the_end_of_short_months() {
+        let month_end = rule(FREQUENCY_MONTHLY, "2024-01-31");
+        assert_eq!(month_end.upcoming(4), dates(&["2024-01-31", "2024-02-29", "2024-03-31", "2024-04-30"]));
+
+        let leap_day = rule(FREQUENCY_YEARLY, "2024-02-29");
+        assert_eq!(leap_day.upcoming(3), dates(&["2024-02-29", "2025-02-28", "2026-02-28"]));
+    }
+
+    #[test]
+    fn last_business_day_skips_weekends() {
+        // August 2024 ends on a Saturday, November 2024 on a Saturday, March 2024 on a Sunday.
+        assert_eq!(last_business_day(date("2024-08-10")), Some(date("2024-08-30")));
+        assert_eq!(last_business_day(date("2024-11-01")), Some(date("2024-11-29")));
+        assert_eq!(last_business_day(date("2024-03-31")), Some(date("2024-03-29")));
+        assert_eq!(last_business_day(date("2024-10-01")), Some(date("2024-10-31")));
+
+        let rule = rule(FREQUENCY_LAST_BUSINESS_DAY, "2024-08-01");
+        assert_eq!(rule.upcoming(4), dates(&["2024-08-30", "2024-09-30", "2024-10-31", "2024-11-29"]));
+    }
+
+    #[test]
+    fn last_business_day_starts_next_month_once_passed() {
+        let rule = rule(FREQUENCY_LAST_BUSINESS_DAY, "2024-08-31");
+        assert_eq!(rule.upcoming(2), dates(&["2024-09-30", "2024-10-31"]));
+    }
+
+    #[test]
+    fn occurrences_stop_at_the_end_date() {
+        let mut rule = rule(FREQUENCY_WEEKLY, "2024-01-01");
+        rule.end_date = Some(date("2024-01-22"));
+        assert_eq!(
+            rule.occurrences().collect::<Vec<_>>(),
+            dates(&["2024-01-01", "2024-01-08", "2024-01-15", "2024-01-22"]),
+        );
+
+        rule.end_date = Some(date("2023-12-31"));
+        assert_eq!(rule.occurrences().count(), 0);
+    }
+
+    #[test]
+    fn due_resumes_after_the_last_run() {
+        let mut rule = rule(FREQUENCY_MONTHLY, "2024-01-15");
+        assert_eq!(rule.due(date("2024-03-01")), dates(&["2024-01-15", "2024-02-15"]));
+
+        rule.last_run = Some(date("2024-03-15"));
+        assert_eq!(rule.due(date("2024-04-14")), Vec::<NaiveDate>::new());
+        assert_eq!(rule.due(date("2024-05-20")), dates(&["2024-04-15", "2024-05-15"]));
+
+        rule.last_run = Some(date("2024-03-20"));
+        assert_eq!(rule.upcoming(1), dates(&["2024-04-15"]));
+
+        rule.end_date = Some(date("2024-04-30"));
+        assert_eq!(rule.due(date("2024-12-31")), dates(&["2024-04-15"]));
+    }
+
+    #[test]
+    fn drafts_are_booked_at_midnight_of_the_occurrence() {
+        let draft = rule(FREQUENCY_DAILY, "2024-01-01").draft(date("2024-01-03"));
+        assert_eq!(draft.date, date("2024-01-03").and_time(NaiveTime::MIN));
+        assert_eq!(draft.amount, Money::from_minor(50_000));
+        assert_eq!(draft.source, "Rent");
+    }
+}
@@ -0,0 +1,116 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::QueryResult;
+use crate::model::{Account, Attachment, AuditEntry, AuditFilter, Budget, Category, NewAccount, NewAttachment, NewBudget, NewCategory, NewEnvelope, NewEnvelopeMove, Envelope, EnvelopeMove, ExchangeRate, NewAuditEntry, NewExchangeRate, NewGoalContribution, NewSavingsGoal, GoalContribution, SavingsGoal, Loan, LoanPayment, NewLoan, NewLoanPayment, NewRecurringRule, NewTransaction, NewUser, RecurringRule, SplitLine, Transaction, TransactionDraft};
+use crate::model::user::User;
+
+/// Storage operations the controllers rely on. Implemented once per database backend
+/// in `model::db`, so the rest of the app never sees a concrete connection type.
+pub trait Repository: Send + Sync {
+    /// Runs `work` in one database transaction: everything it does through the repository
+    /// it is given is committed together, or rolled back when it returns an error.
+    fn transaction(&self, work: &mut dyn FnMut(&dyn Repository) -> QueryResult<()>) -> QueryResult<()>;
+
+    fn find_user_by_name(&self, name: &str) -> QueryResult<User>;
+    fn find_user_by_id(&self, uid: i32) -> QueryResult<User>;
+    fn insert_user(&self, new_user: &NewUser) -> QueryResult<()>;
+    fn update_password(&self, uid: i32, hashed: &str) -> QueryResult<()>;
+    fn set_base_currency(&self, uid: i32, code: &str) -> QueryResult<()>;
+    /// Turns envelope budgeting on or off; `start` is only stored if the user has none yet.
+    fn set_envelope_mode(&self, uid: i32, enabled: bool, start: NaiveDate) -> QueryResult<()>;
+
+    fn load_accounts(&self, uid: i32) -> QueryResult<Vec<Account>>;
+    fn insert_account(&self, new_account: &NewAccount) -> QueryResult<()>;
+
+    fn load_transactions(&self, uid: i32) -> QueryResult<Vec<Transaction>>;
+    fn find_transaction(&self, uid: i32, tx_id: i32) -> QueryResult<Transaction>;
+    /// Inserts the transaction with its split lines and returns it with its new id.
+    fn insert_transaction(&self, new_tx: &NewTransaction, splits: &[SplitLine]) -> QueryResult<Transaction>;
+    /// External ids of all the user's transactions, trashed ones included.
+    fn load_external_ids(&self, uid: i32) -> QueryResult<Vec<String>>;
+    /// Overwrites the editable fields and split lines of one of the user's transactions;
+    /// type and owner never change.
+    fn update_transaction(&self, uid: i32, tx_id: i32, draft: &TransactionDraft) -> QueryResult<usize>;
+    /// Moves a transaction to the trash. Trashed rows are left out of `load_transactions`.
+    fn delete_transaction(&self, uid: i32, tx_id: i32) -> QueryResult<usize>;
+    fn load_trash(&self, uid: i32) -> QueryResult<Vec<Transaction>>;
+    /// Takes a transaction back out of the trash.
+    fn restore_transaction(&self, uid: i32, tx_id: i32) -> QueryResult<usize>;
+    /// Permanently removes one trashed transaction.
+    fn purge_transaction(&self, uid: i32, tx_id: i32) -> QueryResult<usize>;
+    /// Permanently removes the user's transactions trashed before `before`, or all of them for `None`.
+    fn purge_trash(&self, uid: i32, before: Option<NaiveDateTime>) -> QueryResult<usize>;
+
+    fn load_categories(&self, uid: i32) -> QueryResult<Vec<Category>>;
+    /// Template categories (`user_id IS NULL`) copied to users who have none.
+    fn load_default_categories(&self) -> QueryResult<Vec<Category>>;
+    fn insert_categories(&self, new_categories: &[NewCategory]) -> QueryResult<()>;
+    fn update_category(&self, uid: i32, category_id: i32, name: &str, color: &str, parent: Option<i32>) -> QueryResult<usize>;
+    /// Moves the user's transactions, split lines, recurring rules and loan interest from `category_id` to `target`
+    /// and deletes the category.
+    /// Its budget moves to `target` too, adding its limit to a budget the target already has;
+    /// without a target the budget is deleted with the category.
+    /// Its envelope is relinked to `target` when no other envelope covers the target's tree,
+    /// otherwise it is left unlinked.
+    /// Its subcategories move up to the deleted category's parent.
+    fn reassign_and_delete_category(&self, uid: i32, category_id: i32, target: Option<i32>) -> QueryResult<()>;
+
+    fn load_exchange_rates(&self) -> QueryResult<Vec<ExchangeRate>>;
+    /// Inserts the rates, replacing any existing rate for the same date and currency pair.
+    fn save_exchange_rates(&self, rates: &[NewExchangeRate]) -> QueryResult<usize>;
+
+    fn load_recurring_rules(&self, uid: i32) -> QueryResult<Vec<RecurringRule>>;
+    /// Inserts the rule and returns it with its new id.
+    fn insert_recurring_rule(&self, rule: &NewRecurringRule) -> QueryResult<RecurringRule>;
+    fn delete_recurring_rule(&self, uid: i32, rule_id: i32) -> QueryResult<usize>;
+    /// Marks the rule's occurrences up to `date` as booked. Touches no row when `date` is
+    /// already marked, so of two runs booking the same occurrence only one gets a row.
+    fn advance_rule_last_run(&self, uid: i32, rule_id: i32, date: NaiveDate) -> QueryResult<usize>;
+
+    fn load_budgets(&self, uid: i32) -> QueryResult<Vec<Budget>>;
+    /// Creates the category's budget or changes its limit and rollover; the start month is kept.
+    fn save_budget(&self, budget: &NewBudget) -> QueryResult<()>;
+    fn delete_budget(&self, uid: i32, tag_id: i32) -> QueryResult<usize>;
+
+    fn load_envelopes(&self, uid: i32) -> QueryResult<Vec<Envelope>>;
+    /// Inserts the envelope and returns it with its new id.
+    fn insert_envelope(&self, envelope: &NewEnvelope) -> QueryResult<Envelope>;
+    /// Fails while moves still refer to the envelope.
+    fn delete_envelope(&self, uid: i32, envelope_id: i32) -> QueryResult<usize>;
+    /// The user's envelope moves, oldest first.
+    fn load_envelope_moves(&self, uid: i32) -> QueryResult<Vec<EnvelopeMove>>;
+    fn insert_envelope_move(&self, envelope_move: &NewEnvelopeMove) -> QueryResult<()>;
+
+    fn load_savings_goals(&self, uid: i32) -> QueryResult<Vec<SavingsGoal>>;
+    /// Inserts the goal and returns it with its new id.
+    fn insert_savings_goal(&self, goal: &NewSavingsGoal) -> QueryResult<SavingsGoal>;
+    /// Deletes the goal and its contributions; the transactions stay.
+    fn delete_savings_goal(&self, uid: i32, goal_id: i32) -> QueryResult<usize>;
+    /// Contributions to any of the user's goals, oldest first.
+    fn load_goal_contributions(&self, uid: i32) -> QueryResult<Vec<GoalContribution>>;
+    fn insert_goal_contribution(&self, contribution: &NewGoalContribution) -> QueryResult<()>;
+    fn delete_goal_contribution(&self, uid: i32, contribution_id: i32) -> QueryResult<usize>;
+
+    fn load_loans(&self, uid: i32) -> QueryResult<Vec<Loan>>;
+    /// Inserts the loan and returns it with its new id.
+    fn insert_loan(&self, loan: &NewLoan) -> QueryResult<Loan>;
+    /// Deletes the loan; its repayment transactions stay as ordinary transactions.
+    fn delete_loan(&self, uid: i32, loan_id: i32) -> QueryResult<usize>;
+    /// Payments on any of the user's loans, oldest first.
+    fn load_loan_payments(&self, uid: i32) -> QueryResult<Vec<LoanPayment>>;
+    fn insert_loan_payment(&self, payment: &NewLoanPayment) -> QueryResult<()>;
+
+    fn insert_attachments(&self, new_attachments: &[NewAttachment]) -> QueryResult<()>;
+    /// Attachments of one of the user's transactions, oldest first.
+    fn load_attachments(&self, uid: i32, tx_id: i32) -> QueryResult<Vec<Attachment>>;
+    fn find_attachment(&self, uid: i32, attachment_id: i32) -> QueryResult<Attachment>;
+    fn delete_attachment(&self, uid: i32, attachment_id: i32) -> QueryResult<usize>;
+    /// Content hashes of every attachment of the given transactions.
+    fn attachment_hashes(&self, tx_ids: &[i32]) -> QueryResult<Vec<String>>;
+    /// How many attachments, across all users, still point at a stored file.
+    fn count_attachments_with_hash(&self, hash: &str) -> QueryResult<i64>;
+
+    /// Appends to the audit log. There is deliberately no way to change or remove entries.
+    fn insert_audit_entry(&self, entry: &NewAuditEntry) -> QueryResult<()>;
+    /// The user's audit entries matching `filter`, newest first.
+    fn load_audit_log(&self, uid: i32, filter: &AuditFilter) -> QueryResult<Vec<AuditEntry>>;
+}
@@ -0,0 +1,108 @@
+use chrono::{Datelike, NaiveDate};
+use diesel::prelude::*;
+use crate::model::Money;
+use crate::schema::{goal_contributions, savings_goals};
+
+/// Something the user saves up for, such as "Vacation 2000 EUR by June".
+#[derive(Debug, Clone, PartialEq, Eq, Queryable)]
+pub struct SavingsGoal {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub target_amount: Money,
+    pub currency: String,
+    pub target_date: NaiveDate,
+}
+
+impl std::fmt::Display for SavingsGoal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = savings_goals)]
+pub struct NewSavingsGoal {
+    pub user_id: i32,
+    pub name: String,
+    pub target_amount: Money,
+    pub currency: String,
+    pub target_date: NaiveDate,
+}
+
+/// Part of an income or transfer earmarked for a goal, in the transaction's currency.
+#[derive(Debug, Clone, Queryable)]
+pub struct GoalContribution {
+    pub id: i32,
+    pub goal_id: i32,
+    pub transaction_id: i32,
+    pub amount: Money,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = goal_contributions)]
+pub struct NewGoalContribution {
+    pub goal_id: i32,
+    pub transaction_id: i32,
+    pub amount: Money,
+}
+
+/// The user's goals and everything earmarked for them.
+#[derive(Debug, Clone, Default)]
+pub struct SavingsBook {
+    pub goals: Vec<SavingsGoal>,
+    pub contributions: Vec<GoalContribution>,
+}
+
+/// How far a goal has come, in the goal's currency.
+#[derive(Debug, Clone, Copy)]
+pub struct GoalProgress {
+    pub saved: Money,
+    pub target: Money,
+    /// Months left including the current one; zero once the target date has passed.
+    pub months_left: u32,
+}
+
+impl GoalProgress {
+    pub fn remaining(&self) -> Money {
+        (self.target - self.saved).max(Money::ZERO)
+    }
+
+    pub fn is_reached(&self) -> bool {
+        self.saved >= self.target
+    }
+
+    /// What still has to be put aside every month to reach the target in time,
+    /// rounded up to the cent. Past the deadline everything remaining is due now.
+    pub fn monthly_needed(&self) -> Money {
+        let months = i64::from(self.months_left.max(1));
+        Money::from_minor((self.remaining().minor() + months - 1) / months)
+    }
+}
+
+impl SavingsGoal {
+    pub fn progress(&self, saved: Money, today: NaiveDate) -> GoalProgress {
+        let months_left = if self.target_date < today {
+            0
+        } else {
+            let months = (self.target_date.year() - today.year()) * 12
+                + self.target_date.month() as i32
+                - today.month() as i32;
+            months as u32 + 1
+        };
+        GoalProgress { saved, target: self.target_amount, months_left }
+    }
+}
+
+/// An income or transfer that still has money to earmark, as offered in the goal screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EarmarkSource {
+    pub tx_id: i32,
+    pub label: String,
+}
+
+impl std::fmt::Display for EarmarkSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
@@ -1,6 +1,17 @@
-use crate::model::{Transaction, DbPool};
+use crate::model::{Account, Attachment, AuditEntry, Budget, Category, EarmarkSource, Loan, LoanBook, Envelope, EnvelopeBook, EnvelopeChoice, SavingsBook, SavingsGoal, Transaction, DbPool, ExchangeRate, History, Money, Mutation, RateTable, RecurringRule, Toast, TransactionDraft};
+use crate::model::budget::{month_start, BudgetStatus};
+use crate::model::category::subtree;
+use crate::model::envelope::moved_into;
+use crate::model::savings::GoalProgress;
+use crate::model::loan::{ScheduleRow, DIRECTION_BORROWED};
+use crate::model::csv_import::{parse_rows, ColumnChoice, CsvField, CsvMapping, Delimiter, SignConvention};
+use crate::model::import::{BankStatement, ImportFormat, ImportSummary, ImportedRow};
+use crate::model::currency::DEFAULT_CURRENCY;
+use crate::model::category::{DEFAULT_CATEGORY_COLOR, KIND_EXPENSE};
+use crate::model::recurring::{FREQUENCY_MONTHLY, RULE_TYPES};
 use crate::model::db::create_pool;
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
+use std::path::PathBuf;
 use dotenv::dotenv;
 
 #[derive(Debug, Clone)]
@@ -8,6 +19,16 @@ pub enum DashboardViewMode {
     Main,       
     AddExpense,
     AddIncome,
+    AddTransfer,
+    Currencies,
+    Categories,
+    Trash,
+    Audit,
+    Recurring,
+    Envelopes,
+    Goals,
+    Loans,
+    Import,
 }
 
 #[derive(Debug, Clone)]
@@ -49,23 +70,213 @@ pub enum Message {
     ChangeIncomeDate(String),
     ChangeIncomeSum(String),
     ConfirmAddIncome,
-    CategorySelected(Option<String>),
+    CategorySelected(Option<Category>),
+    IncomeCategorySelected(Option<Category>),
     ExitPressed,
-    CombinedLoaded((Vec<Transaction>, Vec<String>)),
+    CombinedLoaded(DashboardData),
     SortTypeChanged(SortType),
 
     DeleteTransaction(i32),
-    TransactionDeleted(Result<(), String>),
+    TransactionDeleted(Result<Transaction, String>),
+    TransactionAdded(Result<Transaction, String>),
 
 
 
 
     ChangeExpenseDateString(String),
     SetExpenseDateToToday,
+    SetIncomeDateToToday,
+
+    ExpenseCurrencySelected(String),
+    IncomeCurrencySelected(String),
+    ChooseCurrencies,
+    BaseCurrencySelected(String),
+    BaseCurrencySaved(Result<String, String>),
+    RatesCsvPathChanged(String),
+    ImportRatesPressed,
+    RatesImported(Result<(usize, Vec<ExchangeRate>), String>),
+
+    AccountSelected(Account),
+    NewAccountNameChanged(String),
+    CreateAccountPressed,
+    AccountsLoaded(Result<Vec<Account>, String>),
+    ChooseAddTransfer,
+    TransferFromSelected(Account),
+    TransferToSelected(Account),
+    ChangeTransferSum(String),
+    TransferCurrencySelected(String),
+    ChangeTransferNote(String),
+    ConfirmAddTransfer,
+
+    ChooseCategories,
+    EditCategory(Category),
+    NewCategoryPressed,
+    CategoryNameChanged(String),
+    CategoryColorChanged(String),
+    SaveCategory,
+    CategoryTargetSelected(Category),
+    CategoryParentSelected(Option<Category>),
+    CategoryKindSelected(&'static str),
+    MergeCategory,
+    DeleteCategory,
+    CategoriesUpdated(Result<(Vec<Category>, Vec<Transaction>), String>),
+    DrillIntoCategory(i32),
+    ChartUp,
+
+    EditTransaction(Transaction),
+    ChangeExpenseComment(String),
+    ChangeIncomeComment(String),
+    ExpenseCommentNewLine(usize),
+    IncomeCommentNewLine(usize),
+    SearchChanged(String),
+
+    PickAttachments,
+    AttachmentsPicked(Vec<PathBuf>),
+    RemovePendingAttachment(usize),
+    RemoveAttachment(i32),
+    AttachmentRemoved(Result<Vec<Attachment>, String>),
+    AttachmentsLoaded(Result<Vec<Attachment>, String>),
+    PreviewAttachment(Option<i32>),
+    SplitTransaction,
+    AddSplitLine,
+    RemoveSplitLine(usize),
+    SplitCategorySelected(usize, Category),
+    SplitAmountChanged(usize, String),
+    TransactionUpdated(Result<Transaction, String>),
+    Undo,
+    Redo,
+    Undone(Result<Mutation, (Mutation, String)>),
+    Redone(Result<Mutation, (Mutation, String)>),
+    ToastTick,
+    DismissToast,
+
+    ChooseTrash,
+    TrashLoaded(Result<Vec<Transaction>, String>),
+    RestoreFromTrash(i32),
+    TransactionRestored(Result<Transaction, String>),
+    PurgeTransaction(i32),
+    EmptyTrash,
+    TrashPurged(Result<(), String>),
+    ExpiredTrashPurged(Result<usize, String>),
+
+    ChooseAudit,
+    AuditFromChanged(String),
+    AuditToChanged(String),
+    AuditActionSelected(Option<&'static str>),
+    ApplyAuditFilter,
+    AuditLoaded(Result<Vec<AuditEntry>, String>),
+
+    ChooseRecurring,
+    RecurringLoaded(Result<Vec<RecurringRule>, String>),
+    RuleTypeSelected(&'static str),
+    RuleSourceChanged(String),
+    RuleAmountChanged(String),
+    RuleCurrencySelected(String),
+    RuleCategorySelected(Option<Category>),
+    RuleAccountSelected(Account),
+    RuleFrequencySelected(&'static str),
+    RuleStartChanged(String),
+    RuleEndChanged(String),
+    SaveRule,
+    RuleSaved(Result<RecurringRule, String>),
+    DeleteRule(i32),
+    RecurringTick,
+    RecurringBooked(Result<usize, String>),
+
+    BudgetLimitChanged(String),
+    BudgetRolloverToggled(bool),
+    SaveBudget,
+    RemoveBudget,
+    BudgetsLoaded(Result<Vec<Budget>, String>),
+
+    ChooseEnvelopes,
+    EnvelopeModeToggled(bool),
+    EnvelopesLoaded(Result<EnvelopeBook, String>),
+    EnvelopeNameChanged(String),
+    EnvelopeCategorySelected(Option<Category>),
+    CreateEnvelope,
+    DeleteEnvelope(i32),
+    MoveFromSelected(EnvelopeChoice),
+    MoveToSelected(EnvelopeChoice),
+    MoveAmountChanged(String),
+    MoveMoney,
+
+    ChooseGoals,
+    GoalsLoaded(Result<SavingsBook, String>),
+    GoalNameChanged(String),
+    GoalTargetChanged(String),
+    GoalCurrencySelected(String),
+    GoalDateChanged(String),
+    CreateGoal,
+    GoalCreated(Result<SavingsBook, String>),
+    DeleteGoal(i32),
+    EarmarkGoalSelected(SavingsGoal),
+    EarmarkSourceSelected(EarmarkSource),
+    EarmarkAmountChanged(String),
+    Earmark,
+    RemoveContribution(i32),
+
+    ChooseLoans,
+    LoansLoaded(Result<LoanBook, String>),
+    LoanNameChanged(String),
+    LoanDirectionSelected(&'static str),
+    LoanPrincipalChanged(String),
+    LoanCurrencySelected(String),
+    LoanRateChanged(String),
+    LoanTermChanged(String),
+    LoanStartChanged(String),
+    LoanAccountSelected(Account),
+    LoanInterestCategorySelected(Option<Category>),
+    CreateLoan,
+    LoanCreated(Result<LoanBook, String>),
+    DeleteLoan(i32),
+    ShowLoan(i32),
+    RepaymentAmountChanged(String),
+    RepaymentDateChanged(String),
+    RecordRepayment,
+    RepaymentRecorded(Result<LoanBook, String>),
+
+    ChooseImport,
+    PickImportFile,
+    ImportFileLoaded(Option<(String, String)>),
+    ImportDelimiterSelected(Delimiter),
+    ImportHeaderToggled(bool),
+    ImportColumnSelected(CsvField, ColumnChoice),
+    ImportDateFormatSelected(&'static str),
+    ImportDecimalSelected(char),
+    ImportSignSelected(SignConvention),
+    ImportCurrencySelected(String),
+    ImportAccountSelected(Account),
+    ImportDayFirstToggled(bool),
+    ImportTransactions,
+    TransactionsImported(Result<ImportSummary, String>),
+    ExportQif,
+    QifExported(Option<Result<String, String>>),
 
 
 }
 
+/// One line of the split editor in the expense or income form.
+#[derive(Debug, Clone, Default)]
+pub struct SplitInput {
+    pub category: Option<Category>,
+    pub amount: String,
+}
+
+/// Everything the dashboard needs right after login or a reload.
+#[derive(Debug, Clone, Default)]
+pub struct DashboardData {
+    pub transactions: Vec<Transaction>,
+    pub accounts: Vec<Account>,
+    pub categories: Vec<Category>,
+    pub base_currency: String,
+    pub exchange_rates: Vec<ExchangeRate>,
+    pub budgets: Vec<Budget>,
+    pub envelope_book: EnvelopeBook,
+    pub savings_book: SavingsBook,
+    pub loan_book: LoanBook,
+}
+
 pub struct CombinedApp {
     pub current_screen: Screen,
     pub login_username: String,
@@ -88,31 +299,561 @@ pub struct CombinedApp {
     pub new_password: String,       
     pub confirm_new_password: String,
     pub pool: DbPool,
-    pub categories: Vec<String>,
-    pub selected_category: Option<String>,
+    pub categories: Vec<Category>,
+    pub selected_category: Option<Category>,
+    pub selected_income_category: Option<Category>,
     pub sort_type: SortType,
     pub expense_date_str: String,
     pub income_date_str: String,
-
-
+    pub dashboard_message: String,
+    pub base_currency: String,
+    pub expense_currency: String,
+    pub income_currency: String,
+    pub exchange_rates: Vec<ExchangeRate>,
+    pub rate_table: RateTable,
+    pub rates_csv_path: String,
+    pub currency_message: String,
+    pub accounts: Vec<Account>,
+    pub selected_account: Option<Account>,
+    pub new_account_name: String,
+    pub transfer_from: Option<Account>,
+    pub transfer_to: Option<Account>,
+    pub transfer_sum: String,
+    pub transfer_currency: String,
+    pub transfer_note: String,
+    pub editing_category: Option<Category>,
+    pub category_name_input: String,
+    pub category_color_input: String,
+    pub category_target: Option<Category>,
+    pub category_parent_input: Option<Category>,
+    pub category_kind_input: &'static str,
+    pub category_message: String,
+    pub chart_parent: Option<i32>,
+    pub editing_transaction: Option<Transaction>,
+    pub expense_comment: String,
+    pub income_comment: String,
+    pub history: History,
+    pub toast: Option<Toast>,
+    pub trash: Vec<Transaction>,
+    pub trash_message: String,
+    pub audit_entries: Vec<AuditEntry>,
+    pub audit_from: String,
+    pub audit_to: String,
+    pub audit_action: Option<&'static str>,
+    pub audit_message: String,
+    pub search_query: String,
+    pub attachments: Vec<Attachment>,
+    pub pending_attachments: Vec<PathBuf>,
+    pub preview_attachment: Option<i32>,
+    pub split_lines: Vec<SplitInput>,
+    pub recurring_rules: Vec<RecurringRule>,
+    pub rule_type: &'static str,
+    pub rule_source: String,
+    pub rule_amount: String,
+    pub rule_currency: String,
+    pub rule_category: Option<Category>,
+    pub rule_account: Option<Account>,
+    pub rule_frequency: &'static str,
+    pub rule_start: String,
+    pub rule_end: String,
+    pub recurring_message: String,
+    pub budgets: Vec<Budget>,
+    pub budget_limit_input: String,
+    pub budget_rollover_input: bool,
+    /// Set when the expense being saved goes over a budget; confirming again saves anyway.
+    pub budget_warning: Option<String>,
+    pub envelope_book: EnvelopeBook,
+    pub envelope_name_input: String,
+    pub envelope_category_input: Option<Category>,
+    pub move_from: Option<EnvelopeChoice>,
+    pub move_to: Option<EnvelopeChoice>,
+    pub move_amount_input: String,
+    pub envelope_message: String,
+    pub savings_book: SavingsBook,
+    pub goal_name_input: String,
+    pub goal_target_input: String,
+    pub goal_currency_input: String,
+    pub goal_date_input: String,
+    pub earmark_goal: Option<SavingsGoal>,
+    pub earmark_source: Option<EarmarkSource>,
+    pub earmark_amount_input: String,
+    pub goal_message: String,
+    pub loan_book: LoanBook,
+    pub loan_name_input: String,
+    pub loan_direction: &'static str,
+    pub loan_principal_input: String,
+    pub loan_currency_input: String,
+    pub loan_rate_input: String,
+    pub loan_term_input: String,
+    pub loan_start_input: String,
+    pub loan_account: Option<Account>,
+    pub loan_interest_category: Option<Category>,
+    /// Loan whose schedule is shown and repayments are recorded.
+    pub selected_loan: Option<i32>,
+    pub repayment_amount_input: String,
+    pub repayment_date_input: String,
+    pub loan_message: String,
+    pub import_file_name: String,
+    pub import_format: ImportFormat,
+    /// Contents of the picked file, kept so it can be split again with another delimiter.
+    pub import_text: String,
+    /// CSV fields, read with `import_mapping`.
+    pub import_rows: Vec<Vec<String>>,
+    /// Statements of a format that needs no mapping, such as OFX.
+    pub import_statements: Vec<BankStatement>,
+    /// QIF dates are day/month/year rather than Quicken's month/day/year.
+    pub import_day_first: bool,
+    pub import_mapping: CsvMapping,
+    pub import_currency: String,
+    pub import_account: Option<Account>,
+    pub import_message: String,
 }
 
 impl CombinedApp {
-    pub fn apply_login_result(&mut self, transactions: Vec<Transaction>, categories: Vec<String>) {
-        self.transactions = transactions;
-        self.categories = categories;
+    pub fn apply_login_result(&mut self, data: DashboardData) {
+        self.transactions = data.transactions;
+        self.categories = data.categories;
+        self.budgets = data.budgets;
+        self.envelope_book = data.envelope_book;
+        self.savings_book = data.savings_book;
+        self.loan_book = data.loan_book;
+        self.expense_currency = data.base_currency.clone();
+        self.income_currency = data.base_currency.clone();
+        self.transfer_currency = data.base_currency.clone();
+        self.base_currency = data.base_currency;
+        self.set_exchange_rates(data.exchange_rates);
+        self.set_accounts(data.accounts);
         self.current_screen = Screen::Dashboard(DashboardViewMode::Main);
     }
 
+    pub fn set_accounts(&mut self, accounts: Vec<Account>) {
+        let keep = |selected: &Option<Account>| {
+            selected.as_ref().and_then(|a| accounts.iter().find(|b| b.id == a.id).cloned())
+        };
+        self.selected_account = keep(&self.selected_account).or_else(|| accounts.first().cloned());
+        self.transfer_from = keep(&self.transfer_from).or_else(|| accounts.first().cloned());
+        self.transfer_to = keep(&self.transfer_to).or_else(|| accounts.get(1).cloned());
+        self.accounts = accounts;
+    }
+
+    pub fn start_new_category(&mut self) {
+        self.editing_category = None;
+        self.category_name_input.clear();
+        self.category_color_input = DEFAULT_CATEGORY_COLOR.to_string();
+        self.category_target = None;
+        self.category_parent_input = None;
+        self.budget_limit_input.clear();
+        self.budget_rollover_input = false;
+    }
+
+    pub fn category(&self, category_id: i32) -> Option<&Category> {
+        self.categories.iter().find(|c| c.id == category_id)
+    }
+
+    pub fn category_usage(&self, category_id: i32) -> usize {
+        self.transactions.iter().filter(|t| t.uses_category(category_id)).count()
+    }
+
+    pub fn account_name(&self, account_id: Option<i32>) -> &str {
+        account_id
+            .and_then(|id| self.accounts.iter().find(|a| a.id == id))
+            .map(|a| a.name.as_str())
+            .unwrap_or("?")
+    }
+
+    /// Signed effect of a transaction on the total balance, in base currency.
+    /// Transfers only move money between accounts and never change the total.
+    pub fn balance_effect(&self, tx: &Transaction) -> Option<Money> {
+        if tx.is_transfer() {
+            return Some(Money::ZERO);
+        }
+        let amount = self.amount_in_base(tx)?;
+        Some(if tx.tran_type == "Expense" { -amount } else { amount })
+    }
+
+    pub fn account_balance(&self, account_id: i32) -> Money {
+        self.transactions
+            .iter()
+            .filter_map(|tx| {
+                let amount = self.amount_in_base(tx)?;
+                if tx.is_transfer() {
+                    if tx.account_id == Some(account_id) {
+                        Some(-amount)
+                    } else if tx.transfer_account_id == Some(account_id) {
+                        Some(amount)
+                    } else {
+                        None
+                    }
+                } else if tx.account_id == Some(account_id) {
+                    self.balance_effect(tx)
+                } else {
+                    None
+                }
+            })
+            .sum()
+    }
+
+    pub fn set_exchange_rates(&mut self, rates: Vec<ExchangeRate>) {
+        self.rate_table = RateTable::new(&rates);
+        self.exchange_rates = rates;
+    }
+
+    /// The transaction amount converted to the user's base currency as of the
+    /// transaction date, or `None` when no exchange rate is known.
+    pub fn amount_in_base(&self, tx: &Transaction) -> Option<Money> {
+        self.rate_table
+            .convert(tx.tran_amount, &tx.currency, &self.base_currency, tx.date.date())
+    }
+
+    /// Amount per category in base currency. A split transaction contributes each
+    /// line to its own category; lines without an exchange rate are left out.
+    /// Of a loan repayment only the interest counts; the principal just pays off debt.
+    pub fn category_amounts(&self, tx: &Transaction) -> Vec<(Option<i32>, Money)> {
+        if let Some(payment) = self.loan_book.payment_for(tx.tran_id) {
+            return self.rate_table
+                .convert(payment.interest_part(tx.tran_amount), &tx.currency, &self.base_currency, tx.date.date())
+                .map(|amount| vec![(tx.tag_id, amount)])
+                .unwrap_or_default();
+        }
+        tx.category_lines()
+            .into_iter()
+            .filter_map(|line| {
+                let amount = self.rate_table
+                    .convert(line.amount, &tx.currency, &self.base_currency, tx.date.date())?;
+                Some((line.tag_id, amount))
+            })
+            .collect()
+    }
+
+    /// Expenses booked in `month` on the category or any of its subcategories, in base
+    /// currency. `excluding` leaves out one transaction, such as the one being edited.
+    pub fn spent_in_category(&self, category_id: i32, month: NaiveDate, excluding: Option<i32>) -> Money {
+        self.spent_where(category_id, excluding, |date| month_start(date) == month)
+    }
+
+    fn spent_where(&self, category_id: i32, excluding: Option<i32>, on: impl Fn(NaiveDate) -> bool) -> Money {
+        let categories = subtree(&self.categories, category_id);
+        self.transactions
+            .iter()
+            .filter(|tx| tx.tran_type == "Expense" && Some(tx.tran_id) != excluding)
+            .filter(|tx| on(tx.date.date()))
+            .flat_map(|tx| self.category_amounts(tx))
+            .filter(|(tag_id, _)| tag_id.is_some_and(|id| categories.contains(&id)))
+            .map(|(_, amount)| amount)
+            .sum()
+    }
+
+    pub fn budget_status(&self, budget: &Budget, month: NaiveDate, excluding: Option<i32>) -> BudgetStatus {
+        budget.status(month, |m| self.spent_in_category(budget.tag_id, m, excluding))
+    }
+
+    /// One line per budget that saving `draft` as an expense would push past its limit.
+    pub fn budget_overruns(&self, draft: &TransactionDraft, excluding: Option<i32>) -> Vec<String> {
+        let month = month_start(draft.date.date());
+        let lines: Vec<(Option<i32>, Money)> = if draft.splits.is_empty() {
+            vec![(draft.tag_id, draft.amount)]
+        } else {
+            draft.splits.iter().map(|s| (s.tag_id, s.amount)).collect()
+        };
+        self.budgets
+            .iter()
+            .filter_map(|budget| {
+                let name = &self.category(budget.tag_id)?.name;
+                let covered = subtree(&self.categories, budget.tag_id);
+                let added: Money = lines
+                    .iter()
+                    .filter(|(tag_id, _)| tag_id.is_some_and(|id| covered.contains(&id)))
+                    .filter_map(|(_, amount)| {
+                        self.rate_table.convert(*amount, &draft.currency, &self.base_currency, draft.date.date())
+                    })
+                    .sum();
+                let status = self.budget_status(budget, month, excluding);
+                (added > Money::ZERO && status.spent + added > status.available()).then(|| {
+                    format!(
+                        "{} would be at {} of {} {}.",
+                        name,
+                        status.spent + added,
+                        status.available(),
+                        self.base_currency,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// What removing the category does to its budget and envelope, given the chosen target.
+    pub fn category_removal_warnings(&self, category_id: i32) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let target = self.category_target.as_ref();
+        if let Some(budget) = self.budgets.iter().find(|b| b.tag_id == category_id) {
+            match target {
+                None => warnings.push(format!(
+                    "Deleting without a target also removes its budget of {} {} per month.",
+                    budget.monthly_limit, self.base_currency,
+                )),
+                Some(target) if self.budgets.iter().any(|b| b.tag_id == target.id) => warnings.push(format!(
+                    "Its budget of {} {} per month is added to the budget of {}.",
+                    budget.monthly_limit, self.base_currency, target.name,
+                )),
+                Some(target) => warnings.push(format!("Its budget moves to {}.", target.name)),
+            }
+        }
+        let envelopes = &self.envelope_book.envelopes;
+        if let Some(envelope) = envelopes.iter().find(|e| e.tag_id == Some(category_id)) {
+            let blocking = target.and_then(|target| {
+                let covered = subtree(&self.categories, target.id);
+                envelopes
+                    .iter()
+                    .filter(|e| e.id != envelope.id)
+                    .find(|e| e.tag_id.is_some_and(|other| {
+                        covered.contains(&other) || subtree(&self.categories, other).contains(&target.id)
+                    }))
+            });
+            match (target, blocking) {
+                (Some(target), None) => warnings.push(format!("Envelope '{}' moves to {}.", envelope.name, target.name)),
+                (Some(_), Some(other)) => warnings.push(format!(
+                    "Envelope '{}' is unlinked, since '{}' already covers the target; it keeps its money.",
+                    envelope.name, other.name,
+                )),
+                (None, _) => warnings.push(format!("Envelope '{}' is unlinked; it keeps its money.", envelope.name)),
+            }
+        }
+        warnings
+    }
+
+    /// Income since envelope budgeting started that has not been moved into an envelope yet.
+    pub fn available_to_assign(&self) -> Money {
+        let start = match self.envelope_book.start {
+            Some(start) => start,
+            None => return Money::ZERO,
+        };
+        let income: Money = self.transactions
+            .iter()
+            .filter(|tx| tx.tran_type == "Income" && tx.date.date() >= start)
+            .filter_map(|tx| self.amount_in_base(tx))
+            .sum();
+        income + moved_into(&self.envelope_book.moves, None)
+    }
+
+    /// Money moved into the envelope minus what its category spent since the start.
+    pub fn envelope_balance(&self, envelope: &Envelope) -> Money {
+        let moved = moved_into(&self.envelope_book.moves, Some(envelope.id));
+        match (envelope.tag_id, self.envelope_book.start) {
+            (Some(tag), Some(start)) => moved - self.spent_where(tag, None, |date| date >= start),
+            _ => moved,
+        }
+    }
+
+    /// What a move can take from: the pool for `None`, otherwise the envelope's balance.
+    pub fn envelope_choice_balance(&self, choice: &EnvelopeChoice) -> Money {
+        match choice.id {
+            None => self.available_to_assign(),
+            Some(id) => self.envelope_book.envelopes
+                .iter()
+                .find(|e| e.id == id)
+                .map(|e| self.envelope_balance(e))
+                .unwrap_or(Money::ZERO),
+        }
+    }
+
+    /// The pool of unassigned income followed by every envelope.
+    pub fn envelope_choices(&self) -> Vec<EnvelopeChoice> {
+        std::iter::once(EnvelopeChoice { id: None, name: "Available to assign".to_string() })
+            .chain(self.envelope_book.envelopes.iter().map(|e| EnvelopeChoice {
+                id: Some(e.id),
+                name: e.name.clone(),
+            }))
+            .collect()
+    }
+
+    /// Everything earmarked for the goal from transactions that are not in the trash,
+    /// converted into the goal's currency as of each transaction's date.
+    pub fn goal_saved(&self, goal: &SavingsGoal) -> Money {
+        self.savings_book.contributions
+            .iter()
+            .filter(|c| c.goal_id == goal.id)
+            .filter_map(|c| {
+                let tx = self.transactions.iter().find(|t| t.tran_id == c.transaction_id)?;
+                self.rate_table.convert(c.amount, &tx.currency, &goal.currency, tx.date.date())
+            })
+            .sum()
+    }
+
+    pub fn goal_progress(&self, goal: &SavingsGoal, today: NaiveDate) -> GoalProgress {
+        goal.progress(self.goal_saved(goal), today)
+    }
+
+    /// Part of a transaction not earmarked for any goal yet, in its own currency.
+    pub fn unearmarked(&self, tx: &Transaction) -> Money {
+        let earmarked: Money = self.savings_book.contributions
+            .iter()
+            .filter(|c| c.transaction_id == tx.tran_id)
+            .map(|c| c.amount)
+            .sum();
+        tx.tran_amount - earmarked
+    }
+
+    /// Income and transfers with money left to earmark, newest first.
+    pub fn earmark_sources(&self) -> Vec<EarmarkSource> {
+        let mut txs: Vec<&Transaction> = self.transactions
+            .iter()
+            .filter(|tx| tx.tran_type != "Expense" && self.unearmarked(tx) > Money::ZERO)
+            .collect();
+        txs.sort_by_key(|tx| std::cmp::Reverse(tx.date));
+        txs.into_iter()
+            .map(|tx| EarmarkSource {
+                tx_id: tx.tran_id,
+                label: format!(
+                    "{} {} {} {} ({} free)",
+                    tx.date.format("%Y-%m-%d"),
+                    tx.tran_source,
+                    tx.tran_amount,
+                    tx.currency,
+                    self.unearmarked(tx),
+                ),
+            })
+            .collect()
+    }
+
+    pub fn clear_goal_form(&mut self) {
+        self.goal_name_input.clear();
+        self.goal_target_input.clear();
+        self.goal_currency_input = self.base_currency.clone();
+        self.goal_date_input.clear();
+    }
+
+    pub fn loan(&self, loan_id: i32) -> Option<&Loan> {
+        self.loan_book.loans.iter().find(|l| l.id == loan_id)
+    }
+
+    /// What is still owed on the loan and how many repayments were booked.
+    pub fn loan_outstanding(&self, loan: &Loan) -> (Money, u32) {
+        self.loan_book.outstanding(loan, &self.transactions)
+    }
+
+    /// Remaining instalments from the current balance; the last one is the payoff date.
+    pub fn loan_projection(&self, loan: &Loan) -> Vec<ScheduleRow> {
+        let (outstanding, paid) = self.loan_outstanding(loan);
+        loan.projection(outstanding, paid)
+    }
+
+    /// Every row of the picked file, numbered from 1; CSV rows are read with the current mapping.
+    pub fn import_preview(&self) -> Vec<(usize, Result<ImportedRow, String>)> {
+        match self.import_format {
+            ImportFormat::Csv => parse_rows(&self.import_rows, &self.import_mapping),
+            _ => self.import_statements
+                .iter()
+                .flat_map(|st| st.rows.iter().cloned())
+                .enumerate()
+                .map(|(i, row)| (i + 1, Ok(row)))
+                .collect(),
+        }
+    }
+
+    /// The rows that would be booked; rows that fail to parse are left out.
+    pub fn importable_rows(&self) -> Vec<ImportedRow> {
+        self.import_preview()
+            .into_iter()
+            .filter_map(|(_, row)| row.ok())
+            .collect()
+    }
+
+    pub fn clear_loan_form(&mut self) {
+        self.loan_name_input.clear();
+        self.loan_principal_input.clear();
+        self.loan_currency_input = self.base_currency.clone();
+        self.loan_rate_input.clear();
+        self.loan_term_input.clear();
+        self.loan_start_input = chrono::Local::now().date_naive().format("%Y-%m-%d").to_string();
+        self.loan_account = self.selected_account.clone();
+        self.loan_interest_category = None;
+    }
+
     pub fn clear_expense_form(&mut self) {
+        self.dashboard_message.clear();
         self.store_name.clear();
         self.expense_sum.clear();
+        self.expense_comment.clear();
+        self.expense_date_str.clear();
         self.selected_category = None;
+        self.clear_shared_form_state();
     }
 
     pub fn clear_income_form(&mut self) {
+        self.dashboard_message.clear();
         self.income_source.clear();
         self.income_sum.clear();
+        self.income_comment.clear();
+        self.income_date_str.clear();
+        self.selected_income_category = None;
+        self.clear_shared_form_state();
+    }
+
+    /// State the expense and income forms share: the edited transaction, split lines,
+    /// attachments and the budget warning.
+    fn clear_shared_form_state(&mut self) {
+        self.editing_transaction = None;
+        self.budget_warning = None;
+        self.split_lines.clear();
+        self.attachments.clear();
+        self.pending_attachments.clear();
+        self.preview_attachment = None;
+    }
+
+    /// Fills the expense or income form with an existing transaction so it can be edited.
+    pub fn start_editing(&mut self, tx: Transaction) {
+        let category = tx.tag_id.and_then(|id| self.category(id)).cloned();
+        let account = tx.account_id.and_then(|id| self.accounts.iter().find(|a| a.id == id)).cloned();
+        let comment = tx.tran_comment.clone().unwrap_or_default();
+        self.dashboard_message.clear();
+        self.clear_shared_form_state();
+        self.split_lines = tx.splits
+            .iter()
+            .map(|line| SplitInput {
+                category: line.tag_id.and_then(|id| self.category(id)).cloned(),
+                amount: line.amount.to_string(),
+            })
+            .collect();
+        self.expense_date = tx.date;
+        self.expense_date_str = tx.date.format("%Y-%m-%d").to_string();
+        self.income_date = tx.date;
+        self.income_date_str = self.expense_date_str.clone();
+        if account.is_some() {
+            self.selected_account = account;
+        }
+        if tx.tran_type == "Expense" {
+            self.store_name = tx.tran_source.clone();
+            self.expense_sum = tx.tran_amount.to_string();
+            self.expense_currency = tx.currency.clone();
+            self.expense_comment = comment;
+            self.selected_category = category;
+            self.current_screen = Screen::Dashboard(DashboardViewMode::AddExpense);
+        } else {
+            self.income_source = tx.tran_source.clone();
+            self.income_sum = tx.tran_amount.to_string();
+            self.income_currency = tx.currency.clone();
+            self.income_comment = comment;
+            self.selected_income_category = category;
+            self.current_screen = Screen::Dashboard(DashboardViewMode::AddIncome);
+        }
+        self.editing_transaction = Some(tx);
+    }
+
+    /// Empties the recurring rule form; the next rule starts today in the base currency.
+    pub fn clear_rule_form(&mut self) {
+        self.rule_source.clear();
+        self.rule_amount.clear();
+        self.rule_currency = self.base_currency.clone();
+        self.rule_category = None;
+        self.rule_account = self.selected_account.clone();
+        self.rule_start = chrono::Local::now().date_naive().format("%Y-%m-%d").to_string();
+        self.rule_end.clear();
+    }
+
+    pub fn clear_transfer_form(&mut self) {
+        self.dashboard_message.clear();
+        self.transfer_sum.clear();
+        self.transfer_note.clear();
     }
 }
 
@@ -147,12 +888,107 @@ impl Default for CombinedApp {
             pool: create_pool(),
             categories: vec![],
             selected_category: None,
+            selected_income_category: None,
             sort_type: SortType::NewestFirst,
 
             expense_date_str: "".to_string(),
             income_date_str: String::new(),
-
-
+            dashboard_message: String::new(),
+            base_currency: DEFAULT_CURRENCY.to_string(),
+            expense_currency: DEFAULT_CURRENCY.to_string(),
+            income_currency: DEFAULT_CURRENCY.to_string(),
+            exchange_rates: Vec::new(),
+            rate_table: RateTable::default(),
+            rates_csv_path: String::new(),
+            currency_message: String::new(),
+            accounts: Vec::new(),
+            selected_account: None,
+            new_account_name: String::new(),
+            transfer_from: None,
+            transfer_to: None,
+            transfer_sum: String::new(),
+            transfer_currency: DEFAULT_CURRENCY.to_string(),
+            transfer_note: String::new(),
+            editing_category: None,
+            category_name_input: String::new(),
+            category_color_input: DEFAULT_CATEGORY_COLOR.to_string(),
+            category_target: None,
+            category_parent_input: None,
+            category_kind_input: KIND_EXPENSE,
+            category_message: String::new(),
+            chart_parent: None,
+            editing_transaction: None,
+            expense_comment: String::new(),
+            income_comment: String::new(),
+            history: History::default(),
+            toast: None,
+            trash: Vec::new(),
+            trash_message: String::new(),
+            audit_entries: Vec::new(),
+            audit_from: String::new(),
+            audit_to: String::new(),
+            audit_action: None,
+            audit_message: String::new(),
+            search_query: String::new(),
+            attachments: Vec::new(),
+            pending_attachments: Vec::new(),
+            preview_attachment: None,
+            split_lines: Vec::new(),
+            recurring_rules: Vec::new(),
+            rule_type: RULE_TYPES[0],
+            rule_source: String::new(),
+            rule_amount: String::new(),
+            rule_currency: DEFAULT_CURRENCY.to_string(),
+            rule_category: None,
+            rule_account: None,
+            rule_frequency: FREQUENCY_MONTHLY,
+            rule_start: String::new(),
+            rule_end: String::new(),
+            recurring_message: String::new(),
+            budgets: Vec::new(),
+            budget_limit_input: String::new(),
+            budget_rollover_input: false,
+            budget_warning: None,
+            envelope_book: EnvelopeBook::default(),
+            envelope_name_input: String::new(),
+            envelope_category_input: None,
+            move_from: None,
+            move_to: None,
+            move_amount_input: String::new(),
+            envelope_message: String::new(),
+            savings_book: SavingsBook::default(),
+            goal_name_input: String::new(),
+            goal_target_input: String::new(),
+            goal_currency_input: DEFAULT_CURRENCY.to_string(),
+            goal_date_input: String::new(),
+            earmark_goal: None,
+            earmark_source: None,
+            earmark_amount_input: String::new(),
+            goal_message: String::new(),
+            loan_book: LoanBook::default(),
+            loan_name_input: String::new(),
+            loan_direction: DIRECTION_BORROWED,
+            loan_principal_input: String::new(),
+            loan_currency_input: DEFAULT_CURRENCY.to_string(),
+            loan_rate_input: String::new(),
+            loan_term_input: String::new(),
+            loan_start_input: String::new(),
+            loan_account: None,
+            loan_interest_category: None,
+            selected_loan: None,
+            repayment_amount_input: String::new(),
+            repayment_date_input: String::new(),
+            loan_message: String::new(),
+            import_file_name: String::new(),
+            import_format: ImportFormat::Csv,
+            import_text: String::new(),
+            import_rows: Vec::new(),
+            import_statements: Vec::new(),
+            import_day_first: false,
+            import_mapping: CsvMapping::default(),
+            import_currency: DEFAULT_CURRENCY.to_string(),
+            import_account: None,
+            import_message: String::new(),
         }
     }
 }
@@ -184,3 +1020,15 @@ impl std::fmt::Display for SortType {
         }
     }
 }
+
+/// Widget id of one line of a multi-line comment field, so focus can follow Enter.
+pub fn comment_line_id(field: &str, line: usize) -> String {
+    format!("{}-comment-{}", field, line)
+}
+
+/// Splits the comment after `line` (0-based), as pressing Enter in a text editor would.
+pub fn insert_comment_line(comment: &str, line: usize) -> String {
+    let mut lines: Vec<&str> = comment.split('\n').collect();
+    lines.insert((line + 1).min(lines.len()), "");
+    lines.join("\n")
+}
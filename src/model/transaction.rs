@@ -1,19 +1,137 @@
 // model/transaction.rs
+use diesel::backend::Backend;
+use diesel::deserialize::{self, Queryable};
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
-use crate::schema::transactions;
+use crate::schema::{transaction_splits, transactions};
 use chrono::NaiveDateTime;
+use crate::model::Money;
+use std::env;
 
-#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]
+const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
     pub tran_id: i32,
     pub tran_type: String,
     pub user_id: i32,
     pub tran_source: String,
     pub date: NaiveDateTime, 
-    pub tran_amount: f64,
+    pub tran_amount: Money,
+    pub tran_comment: Option<String>,
+    pub tag_id: Option<i32>,
+    pub currency: String,
+    pub account_id: Option<i32>,
+    pub transfer_account_id: Option<i32>,
+    /// Set while the transaction sits in the trash.
+    pub deleted_at: Option<NaiveDateTime>,
+    /// Id from the bank statement the transaction was imported from.
+    pub external_id: Option<String>,
+    /// Category lines of a split transaction, filled in by the repository. A split
+    /// transaction has no `tag_id` of its own; its lines add up to `tran_amount`.
+    pub splits: Vec<SplitLine>,
+}
+
+/// The `transactions` columns; `Transaction` is built from it and gets its splits afterwards.
+#[derive(Queryable)]
+pub struct TransactionRow {
+    pub tran_id: i32,
+    pub tran_type: String,
+    pub user_id: i32,
+    pub tran_source: String,
+    pub date: NaiveDateTime,
+    pub tran_amount: Money,
     pub tran_comment: Option<String>,
     pub tag_id: Option<i32>,
+    pub currency: String,
+    pub account_id: Option<i32>,
+    pub transfer_account_id: Option<i32>,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub external_id: Option<String>,
+}
+
+impl<ST, DB> Queryable<ST, DB> for Transaction
+where
+    DB: Backend,
+    TransactionRow: Queryable<ST, DB>,
+{
+    type Row = <TransactionRow as Queryable<ST, DB>>::Row;
+
+    fn build(row: Self::Row) -> deserialize::Result<Self> {
+        let row = TransactionRow::build(row)?;
+        Ok(Transaction {
+            tran_id: row.tran_id,
+            tran_type: row.tran_type,
+            user_id: row.user_id,
+            tran_source: row.tran_source,
+            date: row.date,
+            tran_amount: row.tran_amount,
+            tran_comment: row.tran_comment,
+            tag_id: row.tag_id,
+            currency: row.currency,
+            account_id: row.account_id,
+            transfer_account_id: row.transfer_account_id,
+            deleted_at: row.deleted_at,
+            external_id: row.external_id,
+            splits: Vec::new(),
+        })
+    }
+}
+
+/// One category line of a split transaction, in the transaction's currency.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitLine {
+    pub tag_id: Option<i32>,
+    pub amount: Money,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = transaction_splits)]
+pub struct NewSplit {
+    pub transaction_id: i32,
+    pub tag_id: Option<i32>,
+    pub amount: Money,
+}
+
+impl NewSplit {
+    pub fn for_transaction(tx_id: i32, lines: &[SplitLine]) -> Vec<NewSplit> {
+        lines
+            .iter()
+            .map(|line| NewSplit { transaction_id: tx_id, tag_id: line.tag_id, amount: line.amount })
+            .collect()
+    }
+}
+
+impl Transaction {
+    pub fn is_transfer(&self) -> bool {
+        self.tran_type == "Transfer"
+    }
+
+    pub fn is_split(&self) -> bool {
+        !self.splits.is_empty()
+    }
+
+    /// Category lines in the transaction's currency: the split lines, or the whole
+    /// amount under `tag_id` for an ordinary transaction.
+    pub fn category_lines(&self) -> Vec<SplitLine> {
+        if self.is_split() {
+            self.splits.clone()
+        } else {
+            vec![SplitLine { tag_id: self.tag_id, amount: self.tran_amount }]
+        }
+    }
+
+    pub fn uses_category(&self, category_id: i32) -> bool {
+        self.tag_id == Some(category_id) || self.splits.iter().any(|s| s.tag_id == Some(category_id))
+    }
+
+    /// Case-insensitive match of `query` against the comment and the source.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        query.is_empty()
+            || self.tran_source.to_lowercase().contains(&query)
+            || self.tran_comment.as_deref().is_some_and(|c| c.to_lowercase().contains(&query))
+    }
 }
 
 #[derive(Insertable)]
@@ -23,7 +141,92 @@ pub struct NewTransaction<'a> {
     pub user_id: i32,
     pub tran_source: &'a str,
     pub date: NaiveDateTime,   
-    pub tran_amount: f64,
+    pub tran_amount: Money,
     pub tag_id: Option<i32>,
     pub tran_comment: Option<&'a str>,
+    pub currency: &'a str,
+    pub account_id: Option<i32>,
+    pub transfer_account_id: Option<i32>,
+    pub external_id: Option<&'a str>,
+}
+
+/// Values entered in the add forms, shared by expenses, incomes and transfers.
+#[derive(Debug, Clone)]
+pub struct TransactionDraft {
+    pub source: String,
+    pub date: NaiveDateTime,
+    pub amount: Money,
+    pub currency: String,
+    pub account_id: Option<i32>,
+    pub tag_id: Option<i32>,
+    pub comment: Option<String>,
+    /// Empty unless the transaction is split across categories.
+    pub splits: Vec<SplitLine>,
+}
+
+impl From<&Transaction> for TransactionDraft {
+    fn from(tx: &Transaction) -> Self {
+        TransactionDraft {
+            source: tx.tran_source.clone(),
+            date: tx.date,
+            amount: tx.tran_amount,
+            currency: tx.currency.clone(),
+            account_id: tx.account_id,
+            tag_id: tx.tag_id,
+            comment: tx.tran_comment.clone(),
+            splits: tx.splits.clone(),
+        }
+    }
+}
+
+impl TransactionDraft {
+    pub fn to_new<'a>(&'a self, kind: &'a str, uid: i32) -> NewTransaction<'a> {
+        NewTransaction {
+            tran_type: kind,
+            user_id: uid,
+            tran_source: &self.source,
+            date: self.date,
+            tran_amount: self.amount,
+            tag_id: self.tag_id,
+            tran_comment: self.comment.as_deref(),
+            currency: &self.currency,
+            account_id: self.account_id,
+            transfer_account_id: None,
+            external_id: None,
+        }
+    }
+}
+
+/// Checks that split lines can replace the single category: at least two lines,
+/// each with a category and a positive amount, adding up to the total.
+pub fn check_splits(draft: &TransactionDraft) -> Result<(), String> {
+    if draft.splits.is_empty() {
+        return Ok(());
+    }
+    if draft.tag_id.is_some() {
+        return Err("A split transaction cannot have a category of its own".into());
+    }
+    if draft.splits.len() < 2 {
+        return Err("A split needs at least two lines".into());
+    }
+    if draft.splits.iter().any(|s| s.tag_id.is_none()) {
+        return Err("Choose a category for every split line".into());
+    }
+    if draft.splits.iter().any(|s| s.amount <= Money::ZERO) {
+        return Err("Split amounts must be positive".into());
+    }
+    let total: Money = draft.splits.iter().map(|s| s.amount).sum();
+    if total != draft.amount {
+        return Err(format!("Split lines add up to {} but the total is {}", total, draft.amount));
+    }
+    Ok(())
+}
+
+/// Days a transaction stays in the trash, from `TRASH_RETENTION_DAYS` (default 30).
+pub fn trash_retention_days() -> i64 {
+    env::var("TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|days: &i64| *days >= 0)
+        .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS)
 }
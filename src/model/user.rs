@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use diesel::prelude::*;
 use crate::schema::users;
@@ -8,6 +9,10 @@ pub struct User {
     pub username: String,
     pub password: String,
     pub secret_pass: String,
+    pub base_currency: String,
+    pub envelope_mode: bool,
+    /// Day envelope budgeting was first turned on; kept while the mode is off.
+    pub envelope_start: Option<NaiveDate>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Insertable)]
@@ -0,0 +1,162 @@
+/// A parsed XML element. Namespace prefixes are dropped from element and attribute
+/// names, so `<ns2:Stmt>` is read as `Stmt`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XmlElement {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    /// The element's own text, trimmed; text of child elements is not included.
+    pub text: String,
+    pub children: Vec<XmlElement>,
+}
+
+impl XmlElement {
+    pub fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlElement> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// Follows child names from this element, e.g. `["Acct", "Id", "IBAN"]`.
+    pub fn at(&self, path: &[&str]) -> Option<&XmlElement> {
+        path.iter().try_fold(self, |element, name| element.child(name))
+    }
+
+    /// Text of the element at `path`, if it exists and is not empty.
+    pub fn text_at(&self, path: &[&str]) -> Option<&str> {
+        self.at(path).map(|e| e.text.as_str()).filter(|t| !t.is_empty())
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// The first element named `name` anywhere below this one, depth first.
+    pub fn find(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find_map(|c| if c.name == name { Some(c) } else { c.find(name) })
+    }
+}
+
+fn local_name(name: &str) -> String {
+    name.rsplit(':').next().unwrap_or(name).to_string()
+}
+
+fn unescape(value: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..].find(';').ok_or_else(|| format!("Unterminated entity in '{}'", value))? + start;
+        let entity = &rest[start + 1..end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => entity.strip_prefix('#').and_then(|dec| dec.parse().ok()),
+                };
+                code.and_then(char::from_u32).ok_or_else(|| format!("Unknown entity &{};", entity))?
+            }
+        };
+        out.push(c);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn parse_attributes(input: &str) -> Result<Vec<(String, String)>, String> {
+    let mut attributes = Vec::new();
+    let mut rest = input.trim();
+    while !rest.is_empty() {
+        let eq = rest.find('=').ok_or_else(|| format!("Malformed attributes '{}'", input))?;
+        let name = local_name(rest[..eq].trim());
+        let after = rest[eq + 1..].trim_start();
+        let quote = after.chars().next().filter(|q| *q == '"' || *q == '\'')
+            .ok_or_else(|| format!("Unquoted attribute '{}'", name))?;
+        let close = after[1..].find(quote).ok_or_else(|| format!("Unterminated attribute '{}'", name))? + 1;
+        attributes.push((name, unescape(&after[1..close])?));
+        rest = after[close + 1..].trim_start();
+    }
+    Ok(attributes)
+}
+
+/// Parses a document into its root element. Enough for bank statement files: the
+/// prolog, comments, processing instructions and DOCTYPE are skipped, and CDATA is
+/// read as text. Mismatched tags are reported as errors.
+pub fn parse_xml(text: &str) -> Result<XmlElement, String> {
+    let mut stack: Vec<XmlElement> = Vec::new();
+    let mut root: Option<XmlElement> = None;
+    let mut rest = text.trim_start_matches('\u{feff}');
+    loop {
+        let next = rest.find('<').unwrap_or(rest.len());
+        let content = &rest[..next];
+        if let Some(current) = stack.last_mut() {
+            current.text.push_str(&unescape(content)?);
+        }
+        rest = &rest[next..];
+        if rest.is_empty() {
+            break;
+        }
+        if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>").ok_or("Unterminated CDATA section")?;
+            if let Some(current) = stack.last_mut() {
+                current.text.push_str(&after[..end]);
+            }
+            rest = &after[end + 3..];
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("<!--") {
+            let end = after.find("-->").ok_or("Unterminated comment")?;
+            rest = &after[end + 3..];
+            continue;
+        }
+        let end = rest.find('>').ok_or("Unterminated tag")?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            let name = local_name(name.trim());
+            let mut element = stack.pop().ok_or_else(|| format!("Unexpected </{}>", name))?;
+            if element.name != name {
+                return Err(format!("Expected </{}> but found </{}>", element.name, name));
+            }
+            element.text = element.text.trim().to_string();
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(element),
+                None => root = Some(element),
+            }
+            continue;
+        }
+        let (tag, self_closing) = match tag.strip_suffix('/') {
+            Some(tag) => (tag, true),
+            None => (tag, false),
+        };
+        let tag = tag.trim();
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let element = XmlElement {
+            name: local_name(&tag[..name_end]),
+            attributes: parse_attributes(&tag[name_end..])?,
+            ..XmlElement::default()
+        };
+        if self_closing {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(element),
+                None => root = Some(element),
+            }
+        } else {
+            stack.push(element);
+        }
+    }
+    if let Some(open) = stack.last() {
+        return Err(format!("<{}> is never closed", open.name));
+    }
+    root.ok_or_else(|| "The file holds no XML element".to_string())
+}
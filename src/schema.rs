@@ -7,9 +7,22 @@ table! {
         user_id -> Int4,
         tran_source -> Varchar,
         date -> Timestamp,
-        tran_amount -> Float8,
+        tran_amount -> Int8,
         tran_comment -> Nullable<Varchar>,
         tag_id -> Nullable<Int4>,
+        currency -> Varchar,
+        account_id -> Nullable<Int4>,
+        transfer_account_id -> Nullable<Int4>,
+        deleted_at -> Nullable<Timestamp>,
+        external_id -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    accounts (id) {
+        id -> Int4,
+        user_id -> Int4,
+        name -> Varchar,
     }
 }
 
@@ -17,6 +30,10 @@ table! {
     expense_tags (id) {
         id -> Int4,
         name -> Varchar,
+        user_id -> Nullable<Int4>,
+        color -> Varchar,
+        parent_id -> Nullable<Int4>,
+        kind -> Varchar,
     }
 }
 
@@ -26,15 +43,185 @@ table! {
         username -> Varchar,
         password -> Varchar,
         secret_pass ->Varchar,
+        base_currency -> Varchar,
+        envelope_mode -> Bool,
+        envelope_start -> Nullable<Date>,
+    }
+}
+
+table! {
+    exchange_rates (id) {
+        id -> Int4,
+        rate_date -> Date,
+        currency -> Varchar,
+        quote_currency -> Varchar,
+        rate_micros -> Int8,
+    }
+}
+
+table! {
+    audit_log (id) {
+        id -> Int4,
+        user_id -> Int4,
+        created_at -> Timestamp,
+        action -> Varchar,
+        entity -> Varchar,
+        entity_id -> Nullable<Int4>,
+        old_value -> Nullable<Text>,
+        new_value -> Nullable<Text>,
+    }
+}
+
+table! {
+    attachments (id) {
+        id -> Int4,
+        transaction_id -> Int4,
+        file_name -> Varchar,
+        content_hash -> Varchar,
+        mime_type -> Varchar,
+        size_bytes -> Int8,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    transaction_splits (id) {
+        id -> Int4,
+        transaction_id -> Int4,
+        tag_id -> Nullable<Int4>,
+        amount -> Int8,
+    }
+}
+
+table! {
+    recurring_rules (id) {
+        id -> Int4,
+        user_id -> Int4,
+        tran_type -> Varchar,
+        source -> Varchar,
+        amount -> Int8,
+        currency -> Varchar,
+        tag_id -> Nullable<Int4>,
+        account_id -> Nullable<Int4>,
+        frequency -> Varchar,
+        start_date -> Date,
+        end_date -> Nullable<Date>,
+        last_run -> Nullable<Date>,
+    }
+}
+
+table! {
+    budgets (id) {
+        id -> Int4,
+        user_id -> Int4,
+        tag_id -> Int4,
+        monthly_limit -> Int8,
+        rollover -> Bool,
+        start_month -> Date,
+    }
+}
+
+table! {
+    envelopes (id) {
+        id -> Int4,
+        user_id -> Int4,
+        name -> Varchar,
+        tag_id -> Nullable<Int4>,
+    }
+}
+
+table! {
+    envelope_moves (id) {
+        id -> Int4,
+        user_id -> Int4,
+        from_envelope -> Nullable<Int4>,
+        to_envelope -> Nullable<Int4>,
+        amount -> Int8,
+        moved_at -> Timestamp,
+    }
+}
+
+table! {
+    savings_goals (id) {
+        id -> Int4,
+        user_id -> Int4,
+        name -> Varchar,
+        target_amount -> Int8,
+        currency -> Varchar,
+        target_date -> Date,
+    }
+}
+
+table! {
+    goal_contributions (id) {
+        id -> Int4,
+        goal_id -> Int4,
+        transaction_id -> Int4,
+        amount -> Int8,
+    }
+}
+
+table! {
+    loans (id) {
+        id -> Int4,
+        user_id -> Int4,
+        name -> Varchar,
+        direction -> Varchar,
+        principal -> Int8,
+        currency -> Varchar,
+        annual_rate_bp -> Int4,
+        term_months -> Int4,
+        start_date -> Date,
+        account_id -> Nullable<Int4>,
+        interest_tag_id -> Nullable<Int4>,
+    }
+}
+
+table! {
+    loan_payments (id) {
+        id -> Int4,
+        loan_id -> Int4,
+        transaction_id -> Int4,
+        interest -> Int8,
     }
 }
 
 joinable!(transactions -> users (user_id));
 joinable!(transactions -> expense_tags (tag_id));
+joinable!(accounts -> users (user_id));
+joinable!(expense_tags -> users (user_id));
+joinable!(audit_log -> users (user_id));
+joinable!(attachments -> transactions (transaction_id));
+joinable!(transaction_splits -> transactions (transaction_id));
+joinable!(recurring_rules -> users (user_id));
+joinable!(budgets -> users (user_id));
+joinable!(budgets -> expense_tags (tag_id));
+joinable!(envelopes -> users (user_id));
+joinable!(envelopes -> expense_tags (tag_id));
+joinable!(envelope_moves -> users (user_id));
+joinable!(savings_goals -> users (user_id));
+joinable!(goal_contributions -> savings_goals (goal_id));
+joinable!(goal_contributions -> transactions (transaction_id));
+joinable!(loans -> users (user_id));
+joinable!(loan_payments -> loans (loan_id));
+joinable!(loan_payments -> transactions (transaction_id));
 
 allow_tables_to_appear_in_same_query!(
     transactions,
     users,
     expense_tags,
+    exchange_rates,
+    accounts,
+    audit_log,
+    attachments,
+    transaction_splits,
+    recurring_rules,
+    budgets,
+    envelopes,
+    envelope_moves,
+    savings_goals,
+    goal_contributions,
+    loans,
+    loan_payments,
 );
 
@@ -1,4 +1,5 @@
-use iced::{Application, executor, Command, Element, Theme};
+use iced::{Application, executor, time, Command, Element, Subscription, Theme};
+use std::time::Duration;
 
 use crate::controller::app_controller;
 use crate::model::{CombinedApp, Screen, Message};
@@ -24,6 +25,19 @@ impl Application for CombinedApp {
         app_controller::update(self, message)
     }
 
+    fn subscription(&self) -> Subscription<Message> {
+        let toast = match self.toast {
+            Some(_) => time::every(Duration::from_millis(500)).map(|_| Message::ToastTick),
+            None => Subscription::none(),
+        };
+        // Logging in books everything due; while the app stays open, new days are picked up hourly.
+        let recurring = match self.user_id {
+            Some(_) => time::every(Duration::from_secs(60 * 60)).map(|_| Message::RecurringTick),
+            None => Subscription::none(),
+        };
+        Subscription::batch([toast, recurring])
+    }
+
     fn view(&self) -> Element<Message> {
         match &self.current_screen {
             Screen::Login => login_view::render(self),
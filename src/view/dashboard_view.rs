@@ -1,13 +1,27 @@
 use iced::{
-    widget::{Button, Canvas, Column, PickList, Row, Scrollable, Space, Text as IcedText, TextInput},
+    widget::{image, text_input, Button, Canvas, Checkbox, Column, Image, MouseArea, PickList, ProgressBar, Row, Scrollable, Space, Text as IcedText, TextInput},
     Alignment, Color, Element, Length, Point, Rectangle, mouse::Cursor, Renderer, Theme,Background,
 };
-use iced::widget::canvas::{Frame, Path, Program, Geometry, Text as CanvasText};
+use iced::widget::canvas::{Event as CanvasEvent, Frame, Path, Program, Geometry, Text as CanvasText};
+use iced::{event, mouse};
 use iced::widget::canvas::path::Arc as CanvasArc;
 use iced::widget::Container;
 use std::collections::HashMap;
-use crate::model::{CombinedApp, DashboardViewMode, Message};
-use crate::model::state::SortType;
+use crate::model::{Category, CategoryOption, CombinedApp, DashboardViewMode, Message, Money, Toast};
+use crate::model::state::{comment_line_id, SortType};
+use crate::model::transaction::trash_retention_days;
+use crate::model::audit::AUDIT_ACTIONS;
+use crate::model::attachment::format_size;
+use crate::model::recurring::{FREQUENCIES, RULE_TYPES};
+use crate::model::budget::month_start;
+use crate::model::loan::{format_rate_bp, DIRECTIONS, DIRECTION_LENT};
+use crate::model::import::ImportFormat;
+use crate::model::csv_import::{column_choices, CsvField, DATE_FORMATS, DECIMAL_SEPARATORS, DELIMITERS, SIGN_CONVENTIONS};
+use crate::model::currency::{format_rate, CURRENCIES};
+use crate::model::category::{
+    child_under, has_children, of_kind, parse_hex_color, subtree, tree_order, CATEGORY_KINDS, CATEGORY_PALETTE,
+    KIND_EXPENSE, KIND_INCOME,
+};
 struct BlackBackground;
 
 impl iced::widget::container::StyleSheet for BlackBackground {
@@ -36,6 +50,25 @@ impl iced::widget::container::StyleSheet for BodyBackground {
         }
     }
 }
+struct Swatch(Color);
+
+impl iced::widget::container::StyleSheet for Swatch {
+    type Style = Theme;
+
+    fn appearance(&self, _style: &Self::Style) -> iced::widget::container::Appearance {
+        iced::widget::container::Appearance {
+            background: Some(Background::Color(self.0)),
+            ..Default::default()
+        }
+    }
+}
+
+fn swatch<'a>(rgb: [f32; 3], size: f32) -> Element<'a, Message> {
+    Container::new(Space::new(Length::Fixed(size), Length::Fixed(size)))
+        .style(iced::theme::Container::Custom(Box::new(Swatch(Color::from_rgb(rgb[0], rgb[1], rgb[2])))))
+        .into()
+}
+
 struct TransactionListBackground;
 
 impl iced::widget::container::StyleSheet for TransactionListBackground {
@@ -62,129 +95,371 @@ pub fn render<'a>(
         DashboardViewMode::Main => render_dashboard_main(&app),
         DashboardViewMode::AddExpense => render_add_expense(&app),
         DashboardViewMode::AddIncome => render_add_income(&app),
+        DashboardViewMode::AddTransfer => render_add_transfer(&app),
+        DashboardViewMode::Currencies => render_currencies(&app),
+        DashboardViewMode::Categories => render_categories(&app),
+        DashboardViewMode::Trash => render_trash(&app),
+        DashboardViewMode::Audit => render_audit(&app),
+        DashboardViewMode::Recurring => render_recurring(&app),
+        DashboardViewMode::Envelopes => render_envelopes(&app),
+        DashboardViewMode::Goals => render_goals(&app),
+        DashboardViewMode::Loans => render_loans(&app),
+        DashboardViewMode::Import => render_import(&app),
     }
 }
 
-fn render_dashboard_main(app: &CombinedApp) -> Element<Message> {
-    let mut totals: HashMap<String, f32> = HashMap::new();
-    for cat in &app.categories {
-        totals.insert(cat.clone(), 0.0);
+struct Slice {
+    id: i32,
+    label: String,
+    value: f32,
+    color: [f32; 3],
+    drillable: bool,
+}
+
+struct PieChart {
+    data: Vec<Slice>,
+}
+
+impl PieChart {
+    fn radii(size: iced::Size) -> (Point, f32, f32) {
+        let center = Point::new(size.width / 2.0, size.height / 2.0);
+        let outer_radius = center.x.min(center.y) * 0.8;
+        (center, outer_radius, outer_radius * 0.5)
     }
-    for tx in &app.transactions {
-        if tx.tran_type == "Expense" {
-            if let Some(tag_id) = tx.tag_id {
-                if let Some(name) = app.categories.get((tag_id - 1) as usize) {
-                    *totals.get_mut(name).unwrap() += tx.tran_amount as f32;
+
+    fn slice_at(&self, size: iced::Size, position: Point) -> Option<&Slice> {
+        let (center, outer_radius, inner_radius) = Self::radii(size);
+        let (dx, dy) = (position.x - center.x, position.y - center.y);
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance < inner_radius || distance > outer_radius {
+            return None;
+        }
+        let mut angle = dy.atan2(dx);
+        if angle < 0.0 {
+            angle += 2.0 * std::f32::consts::PI;
+        }
+
+        let total: f32 = self.data.iter().map(|s| s.value).sum();
+        let mut start_angle = 0.0_f32;
+        for slice in &self.data {
+            let end_angle = start_angle + slice.value / total * 2.0 * std::f32::consts::PI;
+            if angle >= start_angle && angle < end_angle {
+                return Some(slice);
+            }
+            start_angle = end_angle;
+        }
+        None
+    }
+}
+
+impl Program<Message> for PieChart {
+    type State = ();
+
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        event: CanvasEvent,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> (event::Status, Option<Message>) {
+        if let CanvasEvent::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event {
+            if let Some(position) = cursor.position_in(bounds) {
+                if let Some(slice) = self.slice_at(bounds.size(), position).filter(|s| s.drillable) {
+                    return (event::Status::Captured, Some(Message::DrillIntoCategory(slice.id)));
                 }
             }
         }
+        (event::Status::Ignored, None)
     }
 
-    let chart_data: Vec<(String, f32)> = totals.into_iter().filter(|(_, sum)| sum > &0.0).collect();
-
-    struct PieChart {
-        data: Vec<(String, f32)>,
-    }
-    impl<Message> Program<Message> for PieChart {
-        type State = ();
-        fn draw(
-            &self,
-            _state: &Self::State,
-            renderer: &Renderer,
-            _theme: &Theme,
-            bounds: Rectangle,
-            _cursor: Cursor,
-        ) -> Vec<Geometry> {
-            let mut frame = Frame::new(renderer, bounds.size());
-            let center = frame.center();
-            let outer_radius = center.x.min(center.y) * 0.8;
-            let inner_radius = outer_radius * 0.5;
-            let total: f32 = self.data.iter().map(|(_, v)| *v).sum();
-
-            let mut start_angle = 0.0_f32;
-            for (i, (label, value)) in self.data.iter().enumerate() {
-                let pct = if total > 0.0 { value / total } else { 0.0 };
-                let sweep = pct * 2.0 * std::f32::consts::PI;
-                let end_angle = start_angle + sweep;
-
-                let path = Path::new(|p| {
-                    p.move_to(Point {
-                        x: center.x + outer_radius * start_angle.cos(),
-                        y: center.y + outer_radius * start_angle.sin(),
-                    });
-                    p.line_to(Point {
-                        x: center.x + inner_radius * end_angle.cos(),
-                        y: center.y + inner_radius * end_angle.sin(),
-                    });
-                    p.arc(CanvasArc {
-                        center,
-                        radius: outer_radius,
-                        start_angle,
-                        end_angle,
-                    });
-
-                    p.arc(CanvasArc {
-                        center,
-                        radius: inner_radius,
-                        start_angle: end_angle,
-                        end_angle: start_angle,
-                    });
-
-                    p.close();
-                });
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let (center, outer_radius, inner_radius) = Self::radii(bounds.size());
+        let total: f32 = self.data.iter().map(|s| s.value).sum();
 
+        let mut start_angle = 0.0_f32;
+        for slice in &self.data {
+            let pct = if total > 0.0 { slice.value / total } else { 0.0 };
+            let sweep = pct * 2.0 * std::f32::consts::PI;
+            let end_angle = start_angle + sweep;
 
-                let color = match i % 8 {
-                    0 => Color::from_rgb(0.8, 0.1, 0.4),
-                    1 => Color::from_rgb(0.1, 0.8, 0.4),
-                    2 => Color::from_rgb(0.4, 0.4, 1.0),
-                    3 => Color::from_rgb(1.0, 0.6, 0.2),
-                    4 => Color::from_rgb(0.6, 0.2, 1.0),
-                    5 => Color::from_rgb(0.2, 1.0, 0.8),
-                    6 => Color::from_rgb(0.8, 0.8, 0.2),
-                    _ => Color::from_rgb(0.3, 0.3, 0.3),
-                };
+            let path = Path::new(|p| {
+                p.move_to(Point {
+                    x: center.x + outer_radius * start_angle.cos(),
+                    y: center.y + outer_radius * start_angle.sin(),
+                });
+                p.line_to(Point {
+                    x: center.x + inner_radius * end_angle.cos(),
+                    y: center.y + inner_radius * end_angle.sin(),
+                });
+                p.arc(CanvasArc {
+                    center,
+                    radius: outer_radius,
+                    start_angle,
+                    end_angle,
+                });
 
-                frame.fill(&path, color);
-
-                let mid_angle = start_angle + sweep / 2.0;
-                let tx = center.x + outer_radius * 0.65 * mid_angle.cos();
-                let ty = center.y + outer_radius * 0.65 * mid_angle.sin();
-                frame.fill_text(CanvasText {
-                    content: format!("{} ({:.0}%)", label, pct * 100.0),
-                    position: Point::new(tx, ty),
-                    color: Color::BLACK,
-                    size: 14.0,
-                    ..Default::default()
+                p.arc(CanvasArc {
+                    center,
+                    radius: inner_radius,
+                    start_angle: end_angle,
+                    end_angle: start_angle,
                 });
 
-                start_angle = end_angle;
-            }
+                p.close();
+            });
+
+
+            let color = Color::from_rgb(slice.color[0], slice.color[1], slice.color[2]);
+
+            frame.fill(&path, color);
+
+            let mid_angle = start_angle + sweep / 2.0;
+            let tx = center.x + outer_radius * 0.65 * mid_angle.cos();
+            let ty = center.y + outer_radius * 0.65 * mid_angle.sin();
+            let marker = if slice.drillable { " ▸" } else { "" };
+            frame.fill_text(CanvasText {
+                content: format!("{} ({:.0}%){}", slice.label, pct * 100.0, marker),
+                position: Point::new(tx, ty),
+                color: Color::BLACK,
+                size: 14.0,
+                ..Default::default()
+            });
+
+            start_angle = end_angle;
+        }
+
+        let clear_center = Path::circle(center, inner_radius);
+        frame.fill(&clear_center, Color::from_rgb(0.686, 0.933, 0.933));
+
+        vec![frame.into_geometry()]
+    }
+
+
+}
+
+/// Spent versus available for every budget in the current month.
+fn budget_panel(app: &CombinedApp) -> Element<Message> {
+    let month = month_start(chrono::Local::now().date_naive());
+    let mut panel = Column::new().spacing(4).width(Length::Fixed(250.0));
+    if app.envelope_book.enabled {
+        let to_assign = app.available_to_assign();
+        panel = panel.push(
+            IcedText::new(format!("To assign: {} {}", to_assign, app.base_currency))
+                .style(if to_assign.is_negative() { Color::from_rgb(0.8, 0.0, 0.0) } else { Color::from_rgb(0.0, 0.5, 0.0) })
+        );
+    }
+    if !app.budgets.is_empty() {
+        panel = panel.push(IcedText::new(format!("Budgets {}", month.format("%B %Y"))));
+    }
+    for (budget, category) in app.budgets.iter().filter_map(|b| Some((b, app.category(b.tag_id)?))) {
+        let status = app.budget_status(budget, month, None);
+        let available = status.available().to_f32().max(0.01);
+        let color = if status.is_over() { Color::from_rgb(0.8, 0.0, 0.0) } else { Color::from_rgb(0.2, 0.2, 0.2) };
+        let carried = if status.carried > Money::ZERO {
+            format!(" (+{} carried)", status.carried)
+        } else {
+            String::new()
+        };
+        panel = panel
+            .push(
+                IcedText::new(format!("{}: {} / {}{}", category.name, status.spent, status.available(), carried))
+                    .size(14)
+                    .style(color)
+            )
+            .push(
+                ProgressBar::new(0.0..=available, status.spent.to_f32().min(available))
+                    .height(Length::Fixed(8.0))
+            );
+    }
+    panel.into()
+}
+
+fn goals_panel(app: &CombinedApp) -> Element<Message> {
+    let today = chrono::Local::now().date_naive();
+    let mut panel = Column::new().spacing(4).width(Length::Fixed(250.0));
+    if !app.savings_book.goals.is_empty() {
+        panel = panel.push(IcedText::new("Savings goals").size(18));
+    }
+    for goal in &app.savings_book.goals {
+        let progress = app.goal_progress(goal, today);
+        let target = progress.target.to_f32().max(0.01);
+        let needed = if progress.is_reached() {
+            "reached".to_string()
+        } else if progress.months_left == 0 {
+            format!("{} {} overdue", progress.remaining(), goal.currency)
+        } else {
+            format!("{} {}/month needed", progress.monthly_needed(), goal.currency)
+        };
+        panel = panel
+            .push(IcedText::new(format!("{}: {} / {} {}", goal.name, progress.saved, progress.target, goal.currency)).size(14))
+            .push(ProgressBar::new(0.0..=target, progress.saved.to_f32().min(target)).height(Length::Fixed(8.0)))
+            .push(IcedText::new(needed).size(12).style(Color::from_rgb(0.3, 0.3, 0.3)));
+    }
+    panel.into()
+}
 
-            let clear_center = Path::circle(center, inner_radius);
-            frame.fill(&clear_center, Color::from_rgb(0.686, 0.933, 0.933));
+fn loans_panel(app: &CombinedApp) -> Element<Message> {
+    let mut panel = Column::new().spacing(4).width(Length::Fixed(250.0));
+    if !app.loan_book.loans.is_empty() {
+        panel = panel.push(IcedText::new("Loans").size(18));
+    }
+    for loan in &app.loan_book.loans {
+        let (outstanding, _) = app.loan_outstanding(loan);
+        let owed = if loan.is_lent() { "to receive" } else { "owed" };
+        let payoff = match app.loan_projection(loan).last() {
+            Some(row) => format!("paid off {}", row.date.format("%Y-%m")),
+            None if outstanding.is_zero() => "paid off".to_string(),
+            None => "payments don't cover the interest".to_string(),
+        };
+        panel = panel
+            .push(IcedText::new(format!("{}: {} {} {}", loan.name, outstanding, loan.currency, owed)).size(14))
+            .push(IcedText::new(payoff).size(12).style(Color::from_rgb(0.3, 0.3, 0.3)));
+    }
+    panel.into()
+}
 
-            vec![frame.into_geometry()]
+/// Spending rolls up into the category shown at the current chart level (`None` = top level);
+/// money booked directly on the drilled-into category gets its own slice.
+/// Split transactions count each line under its own category.
+fn expense_slices(app: &CombinedApp, level: Option<i32>) -> Vec<Slice> {
+    let mut totals: HashMap<i32, Money> = HashMap::new();
+    for tx in &app.transactions {
+        if tx.tran_type == "Expense" {
+            for (tag_id, amount) in app.category_amounts(tx) {
+                let key = match tag_id {
+                    Some(tag_id) if Some(tag_id) == level => Some(tag_id),
+                    Some(tag_id) => child_under(&app.categories, tag_id, level),
+                    None => None,
+                };
+                if let Some(key) = key {
+                    *totals.entry(key).or_insert(Money::ZERO) += amount;
+                }
+            }
         }
+    }
 
+    // Sums stay exact; only the final per-category totals become f32 for drawing.
+    // Categories keep their list order so slices do not jump around between renders.
+    app.categories
+        .iter()
+        .filter_map(|cat| {
+            let sum = *totals.get(&cat.id)?;
+            let direct = Some(cat.id) == level;
+            (sum > Money::ZERO).then(|| Slice {
+                id: cat.id,
+                label: if direct { format!("{} (direct)", cat.name) } else { cat.name.clone() },
+                value: sum.to_f32(),
+                color: cat.rgb(),
+                drillable: !direct && has_children(&app.categories, cat.id),
+            })
+        })
+        .collect()
+}
 
+/// Income per top-level income category; income without a category is grouped separately.
+fn income_slices(app: &CombinedApp) -> Vec<Slice> {
+    let mut totals: HashMap<i32, Money> = HashMap::new();
+    let mut uncategorized = Money::ZERO;
+    for tx in &app.transactions {
+        if tx.tran_type == "Income" {
+            for (tag_id, amount) in app.category_amounts(tx) {
+                match tag_id.and_then(|id| child_under(&app.categories, id, None)) {
+                    Some(key) => *totals.entry(key).or_insert(Money::ZERO) += amount,
+                    None => uncategorized += amount,
+                }
+            }
+        }
+    }
+
+    let mut slices: Vec<Slice> = app
+        .categories
+        .iter()
+        .filter_map(|cat| {
+            let sum = *totals.get(&cat.id)?;
+            (sum > Money::ZERO).then(|| Slice {
+                id: cat.id,
+                label: cat.name.clone(),
+                value: sum.to_f32(),
+                color: cat.rgb(),
+                drillable: false,
+            })
+        })
+        .collect();
+    if uncategorized > Money::ZERO {
+        slices.push(Slice {
+            id: 0,
+            label: "Uncategorized".into(),
+            value: uncategorized.to_f32(),
+            color: [0.6, 0.6, 0.6],
+            drillable: false,
+        });
     }
+    slices
+}
 
-    let pie = Canvas::new(PieChart { data: chart_data })
+fn render_dashboard_main(app: &CombinedApp) -> Element<Message> {
+    let level = app.chart_parent;
+    let pie = Canvas::new(PieChart { data: expense_slices(app, level) })
+        .width(Length::Fixed(250.0))
+        .height(Length::Fixed(250.0));
+    let income_pie = Canvas::new(PieChart { data: income_slices(app) })
         .width(Length::Fixed(250.0))
         .height(Length::Fixed(250.0));
 
+    let mut chart_header = Row::new().spacing(10).align_items(Alignment::Center);
+    match level.and_then(|id| app.category(id)) {
+        Some(category) => {
+            chart_header = chart_header
+                .push(IcedText::new(format!("Spending: {}", category.name)))
+                .push(Button::new(IcedText::new("Up")).on_press(Message::ChartUp));
+        }
+        None => chart_header = chart_header.push(IcedText::new("Spending by category")),
+    }
+    let chart_column = Column::new()
+        .spacing(5)
+        .push(chart_header)
+        .push(pie)
+        .push(IcedText::new("Income by source"))
+        .push(income_pie)
+        .push(budget_panel(app));
+
     let sort_picker = PickList::new(
         &SortType::ALL[..],
         Some(app.sort_type.clone()),
         Message::SortTypeChanged,
     );
 
+    let mut undo = Button::new(IcedText::new("Undo"));
+    if app.history.can_undo() {
+        undo = undo.on_press(Message::Undo);
+    }
+    let mut redo = Button::new(IcedText::new("Redo"));
+    if app.history.can_redo() {
+        redo = redo.on_press(Message::Redo);
+    }
+
     let top_controls = Row::new()
         .padding(10)
         .spacing(20)
+        .align_items(Alignment::Center)
         .push(IcedText::new("Sorting:"))
-        .push(sort_picker);
+        .push(sort_picker)
+        .push(
+            TextInput::new("Search comments", &app.search_query)
+                .on_input(Message::SearchChanged)
+                .width(Length::Fixed(220.0))
+        )
+        .push(Space::with_width(Length::Fill))
+        .push(undo)
+        .push(redo);
 
     let mut tx_list_column = Column::new().padding(10).spacing(5).align_items(Alignment::Center);
     tx_list_column = tx_list_column.push(IcedText::new("Transactions").size(18));
@@ -207,21 +482,79 @@ fn render_dashboard_main(app: &CombinedApp) -> Element<Message> {
                 sorted_transactions.sort_by(|a, b| b.date.cmp(&a.date));
             }
     }
+    sorted_transactions.retain(|t| t.matches(&app.search_query));
 
 for tx in &sorted_transactions {
-    let color = if tx.tran_type.eq_ignore_ascii_case("expense") {
+    let color = if tx.is_transfer() {
+        Color::from_rgb(0.1, 0.3, 0.8)
+    } else if tx.tran_type.eq_ignore_ascii_case("expense") {
         Color::from_rgb(1.0, 0.0, 0.0)
     } else {
         Color::from_rgb(0.0, 0.6, 0.0)
     };
 
     let formatted_date = tx.date.format("%Y-%m-%d %H:%M:%S").to_string();
-    let line = format!("{} {} – {:+.2} [{}]", tx.tran_type, tx.tran_source, tx.tran_amount, formatted_date);
+    let line = if tx.is_transfer() {
+        format!(
+            "Transfer {} → {} – {} {} [{}]",
+            app.account_name(tx.account_id),
+            app.account_name(tx.transfer_account_id),
+            tx.tran_amount,
+            tx.currency,
+            formatted_date,
+        )
+    } else {
+        let category = if tx.is_split() {
+            let names: Vec<String> = tx.splits
+                .iter()
+                .map(|s| {
+                    let name = s.tag_id.and_then(|id| app.category(id)).map_or("?", |c| c.name.as_str());
+                    format!("#{} {}", name, s.amount)
+                })
+                .collect();
+            format!(" [{}]", names.join(", "))
+        } else {
+            tx.tag_id
+                .and_then(|id| app.category(id))
+                .map(|c| format!(" #{}", c.name))
+                .unwrap_or_default()
+        };
+        format!(
+            "{} {}{} – {:+} {} ({}) [{}]",
+            tx.tran_type,
+            tx.tran_source,
+            category,
+            tx.tran_amount,
+            tx.currency,
+            app.account_name(tx.account_id),
+            formatted_date,
+        )
+    };
 
+    let mut text = Column::new().push(IcedText::new(line).style(iced::theme::Text::Color(color)));
+    if let Some(payment) = app.loan_book.payment_for(tx.tran_id) {
+        text = text.push(
+            IcedText::new(format!(
+                "Loan repayment: interest {}, principal {}",
+                payment.interest_part(tx.tran_amount),
+                payment.principal_part(tx.tran_amount),
+            ))
+            .size(14)
+            .style(Color::from_rgb(0.3, 0.3, 0.3))
+        );
+    }
+    if let Some(comment) = &tx.tran_comment {
+        text = text.push(IcedText::new(comment).size(14).style(Color::from_rgb(0.3, 0.3, 0.3)));
+    }
+    let label: Element<Message> = if tx.is_transfer() {
+        text.into()
+    } else {
+        MouseArea::new(text).on_press(Message::EditTransaction(tx.clone())).into()
+    };
     let row = Row::new()
         .spacing(10)
         .align_items(Alignment::Center)
-        .push(IcedText::new(line).style(iced::theme::Text::Color(color)))
+        .push(label)
         .push(
             Button::new(IcedText::new("Delete"))
                 .on_press(Message::DeleteTransaction(tx.tran_id))
@@ -236,9 +569,19 @@ for tx in &sorted_transactions {
       
 
 
-    let balance: f64 = app.transactions.iter().map(|tx| {
-        if tx.tran_type == "Expense" { -tx.tran_amount } else { tx.tran_amount }
+    let mut missing_rates = 0;
+    let balance: Money = app.transactions.iter().filter_map(|tx| {
+        let effect = app.balance_effect(tx);
+        if effect.is_none() {
+            missing_rates += 1;
+        }
+        effect
     }).sum();
+    let balance_text = if missing_rates > 0 {
+        format!("Balance: {:+} {} ({} without exchange rate)", balance, app.base_currency, missing_rates)
+    } else {
+        format!("Balance: {:+} {}", balance, app.base_currency)
+    };
 
 
 
@@ -251,7 +594,7 @@ let top_bar = Container::new(
             .size(20)
             .style(Color::WHITE))
         .push(Space::with_width(Length::Fill))
-        .push(IcedText::new(format!("Balance: {:+.2}", balance))
+        .push(IcedText::new(balance_text)
             .size(20)
             .style(Color::WHITE))
         .push(Space::with_width(Length::Fill))
@@ -265,86 +608,1376 @@ let top_bar = Container::new(
     .style(iced::theme::Container::Custom(Box::new(BlackBackground)));
 
 
+    let mut accounts_panel = Column::new()
+        .spacing(5)
+        .push(IcedText::new("Accounts").size(18));
+    for account in &app.accounts {
+        accounts_panel = accounts_panel.push(IcedText::new(format!(
+            "{}: {:+} {}",
+            account.name,
+            app.account_balance(account.id),
+            app.base_currency,
+        )));
+    }
+    accounts_panel = accounts_panel
+        .push(
+            TextInput::new("New account", &app.new_account_name)
+                .on_input(Message::NewAccountNameChanged)
+                .on_submit(Message::CreateAccountPressed)
+                .width(Length::Fixed(150.0))
+        )
+        .push(Button::new(IcedText::new("Add account")).on_press(Message::CreateAccountPressed))
+        .push(IcedText::new(&app.dashboard_message).style(Color::from_rgb(0.8, 0.0, 0.0)));
+
     let buttons = Column::new()
         .spacing(10)
         .push(Button::new(IcedText::new("Add Expense")).on_press(Message::ChooseAddExpense))
-        .push(Button::new(IcedText::new("Add Income")).on_press(Message::ChooseAddIncome));
-        Container::new(
-            Column::new()
-                .push(top_bar)
-                .push(top_controls)
-                .push(
-                    Row::new()
-                        .spacing(40)
-                        .push(pie)
-                        .push(tx_list)
-                        .push(buttons)
-                ))
+        .push(Button::new(IcedText::new("Add Income")).on_press(Message::ChooseAddIncome))
+        .push(Button::new(IcedText::new("Transfer")).on_press(Message::ChooseAddTransfer))
+        .push(Button::new(IcedText::new("Categories")).on_press(Message::ChooseCategories))
+        .push(Button::new(IcedText::new("Currencies")).on_press(Message::ChooseCurrencies))
+        .push(Button::new(IcedText::new("Trash")).on_press(Message::ChooseTrash))
+        .push(Button::new(IcedText::new("Recurring")).on_press(Message::ChooseRecurring))
+        .push(Button::new(IcedText::new("Envelopes")).on_press(Message::ChooseEnvelopes))
+        .push(Button::new(IcedText::new("Savings goals")).on_press(Message::ChooseGoals))
+        .push(Button::new(IcedText::new("Loans")).on_press(Message::ChooseLoans))
+        .push(Button::new(IcedText::new("Import")).on_press(Message::ChooseImport))
+        .push(Button::new(IcedText::new("Audit log")).on_press(Message::ChooseAudit))
+        .push(Space::with_height(Length::Fixed(20.0)))
+        .push(accounts_panel)
+        .push(goals_panel(app))
+        .push(loans_panel(app));
+    let mut body = Column::new()
+        .push(top_bar)
+        .push(top_controls)
+        .push(
+            Row::new()
+                .spacing(40)
+                .push(chart_column)
+                .push(tx_list)
+                .push(buttons)
+                .height(Length::Fill)
+        );
+    if let Some(toast) = &app.toast {
+        body = body.push(render_toast(toast));
+    }
+
+        Container::new(body)
                 .style(iced::theme::Container::Custom(Box::new(BodyBackground)))
                 .width(Length::Fill)
                 .height(Length::Fill)
                 .into()
 }
 
+fn render_toast(toast: &Toast) -> Element<Message> {
+    let mut row = Row::new()
+        .spacing(20)
+        .align_items(Alignment::Center)
+        .push(IcedText::new(&toast.text).style(Color::WHITE))
+        .push(Space::with_width(Length::Fill));
+    if toast.undoable {
+        row = row.push(Button::new(IcedText::new("Undo")).on_press(Message::Undo));
+    }
+    row = row.push(Button::new(IcedText::new("×")).on_press(Message::DismissToast));
+    Container::new(row)
+        .width(Length::Fill)
+        .padding(10)
+        .style(iced::theme::Container::Custom(Box::new(BlackBackground)))
+        .into()
+}
+
 fn render_add_expense(app: &CombinedApp) -> Element<Message> {
+    let (title, confirm) = form_labels(app, "expense");
+    let confirm = if app.budget_warning.is_some() { "Save anyway" } else { confirm };
     Column::new()
         .padding(20)
         .spacing(10)
+        .push(IcedText::new(title).size(18))
         .push(
             TextInput::new("Store", &app.store_name)
                 .on_input(Message::ChangeStoreName)
         )
         .push(
-    TextInput::new("Date (YYYY-MM-DD)", &app.expense_date.format("%Y-%m-%d").to_string())
-        .on_input(Message::ChangeExpenseDateString)
+            TextInput::new("Date (YYYY-MM-DD)", &app.expense_date_str)
+                .on_input(Message::ChangeExpenseDateString)
         )
         .push(
             Button::new(IcedText::new("Today")).on_press(Message::SetExpenseDateToToday)
         )
 
         .push(
-            TextInput::new("Amount", &app.expense_sum)
-                .on_input(Message::ChangeExpenseSum)
+            Row::new().spacing(10)
+                .push(
+                    TextInput::new("Amount", &app.expense_sum)
+                        .on_input(Message::ChangeExpenseSum)
+                )
+                .push(
+                    PickList::new(
+                        &CURRENCIES[..],
+                        listed_currency(&app.expense_currency),
+                        |code| Message::ExpenseCurrencySelected(code.to_string()),
+                    )
+                )
         )
+        .push(category_or_splits(
+            app,
+            KIND_EXPENSE,
+            &app.expense_sum,
+            PickList::new(
+                tree_order(&of_kind(&app.categories, KIND_EXPENSE)),
+                selected_option(app, app.selected_category.as_ref()),
+                |selected| Message::CategorySelected(Some(selected.category)),
+            )
+            .placeholder("Category")
+            .into(),
+        ))
         .push(
             PickList::new(
-                &app.categories[..],
-                app.selected_category.clone(),
-                |selected| Message::CategorySelected(Some(selected)),
+                &app.accounts[..],
+                app.selected_account.clone(),
+                Message::AccountSelected,
             )
+            .placeholder("Account")
         )
+        .push(comment_editor("expense", &app.expense_comment, Message::ChangeExpenseComment, Message::ExpenseCommentNewLine))
+        .push(attachments_panel(app))
+        .push(IcedText::new(&app.dashboard_message).style(Color::from_rgb(0.8, 0.0, 0.0)))
+        .push(IcedText::new(app.budget_warning.as_deref().unwrap_or("")).style(Color::from_rgb(0.85, 0.45, 0.0)))
         .push(
             Row::new().spacing(10)
-                .push(Button::new(IcedText::new("Confirm")).on_press(Message::ConfirmAddExpense))
+                .push(Button::new(IcedText::new(confirm)).on_press(Message::ConfirmAddExpense))
                 .push(Button::new(IcedText::new("Cancel")).on_press(Message::CancelDashboardAction))
         )
         .into()
 }
 
 fn render_add_income(app: &CombinedApp) -> Element<Message> {
+    let (title, confirm) = form_labels(app, "income");
     Column::new()
         .padding(20)
         .spacing(10)
+        .push(IcedText::new(title).size(18))
         .push(
             TextInput::new("Source", &app.income_source)
                 .on_input(Message::ChangeIncomeSource)
         )
 
         .push(
-    TextInput::new("Date (YYYY-MM-DD)", &app.expense_date.format("%Y-%m-%d").to_string())
-        .on_input(Message::ChangeExpenseDateString)
+            TextInput::new("Date (YYYY-MM-DD)", &app.income_date_str)
+                .on_input(Message::ChangeIncomeDate)
         )
         .push(
-            Button::new(IcedText::new("Today")).on_press(Message::SetExpenseDateToToday)
+            Button::new(IcedText::new("Today")).on_press(Message::SetIncomeDateToToday)
         )
         .push(
-            TextInput::new("Amount", &app.income_sum)
-                .on_input(Message::ChangeIncomeSum)
+            Row::new().spacing(10)
+                .push(
+                    TextInput::new("Amount", &app.income_sum)
+                        .on_input(Message::ChangeIncomeSum)
+                )
+                .push(
+                    PickList::new(
+                        &CURRENCIES[..],
+                        listed_currency(&app.income_currency),
+                        |code| Message::IncomeCurrencySelected(code.to_string()),
+                    )
+                )
         )
+        .push(category_or_splits(
+            app,
+            KIND_INCOME,
+            &app.income_sum,
+            PickList::new(
+                tree_order(&of_kind(&app.categories, KIND_INCOME)),
+                selected_option(app, app.selected_income_category.as_ref()),
+                |selected| Message::IncomeCategorySelected(Some(selected.category)),
+            )
+            .placeholder("Category")
+            .into(),
+        ))
+        .push(
+            PickList::new(
+                &app.accounts[..],
+                app.selected_account.clone(),
+                Message::AccountSelected,
+            )
+            .placeholder("Account")
+        )
+        .push(comment_editor("income", &app.income_comment, Message::ChangeIncomeComment, Message::IncomeCommentNewLine))
+        .push(attachments_panel(app))
+        .push(IcedText::new(&app.dashboard_message).style(Color::from_rgb(0.8, 0.0, 0.0)))
         .push(
             Row::new().spacing(10)
-                .push(Button::new(IcedText::new("Confirm")).on_press(Message::ConfirmAddIncome))
+                .push(Button::new(IcedText::new(confirm)).on_press(Message::ConfirmAddIncome))
                 .push(Button::new(IcedText::new("Cancel")).on_press(Message::CancelDashboardAction))
         )
         .into()
 }
+
+/// Multi-line comment input: one text input per line, Enter starts a new line below.
+fn comment_editor<'a>(
+    field: &str,
+    comment: &str,
+    on_change: fn(String) -> Message,
+    on_new_line: fn(usize) -> Message,
+) -> Element<'a, Message> {
+    let lines: Vec<String> = comment.split('\n').map(str::to_string).collect();
+    let mut editor = Column::new().spacing(2);
+    for (i, line) in lines.iter().enumerate() {
+        let current = lines.clone();
+        let input = TextInput::new(if i == 0 { "Comment (Enter for a new line)" } else { "" }, line)
+            .id(text_input::Id::new(comment_line_id(field, i)))
+            .on_input(move |value| {
+                let mut edited = current.clone();
+                edited[i] = value;
+                on_change(edited.join("\n"))
+            })
+            .on_submit(on_new_line(i));
+        let mut row = Row::new().spacing(5).align_items(Alignment::Center).push(input);
+        if lines.len() > 1 {
+            let mut remaining = lines.clone();
+            remaining.remove(i);
+            row = row.push(Button::new(IcedText::new("×")).on_press(on_change(remaining.join("\n"))));
+        }
+        editor = editor.push(row);
+    }
+    editor.into()
+}
+
+/// The single category picker with a "Split" button, or the split editor once the
+/// transaction is split: one category and amount per line plus what is left to assign.
+fn category_or_splits<'a>(
+    app: &'a CombinedApp,
+    kind: &str,
+    total: &str,
+    picker: Element<'a, Message>,
+) -> Element<'a, Message> {
+    if app.split_lines.is_empty() {
+        return Row::new().spacing(10).align_items(Alignment::Center)
+            .push(picker)
+            .push(Button::new(IcedText::new("Split")).on_press(Message::SplitTransaction))
+            .into();
+    }
+
+    let options = tree_order(&of_kind(&app.categories, kind));
+    let mut editor = Column::new().spacing(5);
+    for (i, line) in app.split_lines.iter().enumerate() {
+        editor = editor.push(
+            Row::new().spacing(5).align_items(Alignment::Center)
+                .push(
+                    PickList::new(
+                        options.clone(),
+                        selected_option(app, line.category.as_ref()),
+                        move |selected| Message::SplitCategorySelected(i, selected.category),
+                    )
+                    .placeholder("Category")
+                )
+                .push(
+                    TextInput::new("Amount", &line.amount)
+                        .on_input(move |value| Message::SplitAmountChanged(i, value))
+                        .width(Length::Fixed(100.0))
+                )
+                .push(Button::new(IcedText::new("×")).on_press(Message::RemoveSplitLine(i)))
+        );
+    }
+
+    let assigned: Money = app.split_lines.iter().filter_map(|l| Money::parse(&l.amount).ok()).sum();
+    let status = match Money::parse(total) {
+        Ok(total) if total == assigned => IcedText::new("Split lines match the total").style(Color::from_rgb(0.0, 0.5, 0.0)),
+        Ok(total) => IcedText::new(format!("Left to assign: {}", total - assigned)).style(Color::from_rgb(0.8, 0.4, 0.0)),
+        Err(_) => IcedText::new(format!("Assigned: {}", assigned)),
+    };
+    editor
+        .push(
+            Row::new().spacing(10).align_items(Alignment::Center)
+                .push(Button::new(IcedText::new("Add line")).on_press(Message::AddSplitLine))
+                .push(status.size(14))
+        )
+        .into()
+}
+
+/// Saved attachments of the edited transaction plus files picked for the next save.
+fn attachments_panel(app: &CombinedApp) -> Element<Message> {
+    let mut panel = Column::new()
+        .spacing(5)
+        .push(Button::new(IcedText::new("Attach files…")).on_press(Message::PickAttachments));
+
+    for (i, path) in app.pending_attachments.iter().enumerate() {
+        let name = path.file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        panel = panel.push(
+            Row::new().spacing(5).align_items(Alignment::Center)
+                .push(IcedText::new(format!("+ {}", name)).size(14))
+                .push(Button::new(IcedText::new("×")).on_press(Message::RemovePendingAttachment(i)))
+        );
+    }
+
+    if !app.attachments.is_empty() {
+        let mut thumbnails = Row::new().spacing(10).align_items(Alignment::End);
+        for attachment in &app.attachments {
+            let preview: Element<Message> = if attachment.is_image() {
+                MouseArea::new(
+                    Image::new(image::Handle::from_path(attachment.path()))
+                        .width(Length::Fixed(96.0))
+                        .height(Length::Fixed(96.0))
+                )
+                .on_press(Message::PreviewAttachment(Some(attachment.id)))
+                .into()
+            } else {
+                IcedText::new(format!("📄 {}", attachment.file_name)).size(14).into()
+            };
+            thumbnails = thumbnails.push(
+                Column::new().spacing(3).align_items(Alignment::Center)
+                    .push(preview)
+                    .push(IcedText::new(format_size(attachment.size_bytes)).size(12))
+                    .push(Button::new(IcedText::new("Remove").size(12)).on_press(Message::RemoveAttachment(attachment.id)))
+            );
+        }
+        panel = panel.push(thumbnails);
+    }
+
+    if let Some(attachment) = app.preview_attachment
+        .and_then(|id| app.attachments.iter().find(|a| a.id == id))
+    {
+        panel = panel.push(
+            Column::new().spacing(5)
+                .push(
+                    Row::new().spacing(10).align_items(Alignment::Center)
+                        .push(IcedText::new(&attachment.file_name))
+                        .push(Button::new(IcedText::new("Close")).on_press(Message::PreviewAttachment(None)))
+                )
+                .push(
+                    Image::new(image::Handle::from_path(attachment.path()))
+                        .width(Length::Fixed(400.0))
+                )
+        );
+    }
+    panel.into()
+}
+
+fn form_labels(app: &CombinedApp, kind: &str) -> (String, &'static str) {
+    match app.editing_transaction {
+        Some(_) => (format!("Edit {}", kind), "Save"),
+        None => (format!("Add {}", kind), "Confirm"),
+    }
+}
+
+fn render_add_transfer(app: &CombinedApp) -> Element<Message> {
+    Column::new()
+        .padding(20)
+        .spacing(10)
+        .push(IcedText::new("Transfer between accounts").size(20))
+        .push(
+            Row::new().spacing(10).align_items(Alignment::Center)
+                .push(IcedText::new("From"))
+                .push(PickList::new(
+                    &app.accounts[..],
+                    app.transfer_from.clone(),
+                    Message::TransferFromSelected,
+                ))
+                .push(IcedText::new("To"))
+                .push(PickList::new(
+                    &app.accounts[..],
+                    app.transfer_to.clone(),
+                    Message::TransferToSelected,
+                ))
+        )
+        .push(
+            Row::new().spacing(10)
+                .push(
+                    TextInput::new("Amount", &app.transfer_sum)
+                        .on_input(Message::ChangeTransferSum)
+                )
+                .push(
+                    PickList::new(
+                        &CURRENCIES[..],
+                        listed_currency(&app.transfer_currency),
+                        |code| Message::TransferCurrencySelected(code.to_string()),
+                    )
+                )
+        )
+        .push(
+            TextInput::new("Note", &app.transfer_note)
+                .on_input(Message::ChangeTransferNote)
+        )
+        .push(IcedText::new(&app.dashboard_message).style(Color::from_rgb(0.8, 0.0, 0.0)))
+        .push(
+            Row::new().spacing(10)
+                .push(Button::new(IcedText::new("Confirm")).on_press(Message::ConfirmAddTransfer))
+                .push(Button::new(IcedText::new("Cancel")).on_press(Message::CancelDashboardAction))
+        )
+        .into()
+}
+
+// Pick lists need `'static` options, so the selection is looked up in `CURRENCIES`.
+fn listed_currency(code: &str) -> Option<&'static str> {
+    CURRENCIES.iter().copied().find(|c| *c == code)
+}
+
+fn render_currencies(app: &CombinedApp) -> Element<Message> {
+    let mut rates_column = Column::new().spacing(5);
+    for rate in &app.exchange_rates {
+        rates_column = rates_column.push(IcedText::new(format!(
+            "{}  1 {} = {} {}",
+            rate.rate_date.format("%Y-%m-%d"),
+            rate.currency,
+            format_rate(rate.rate_micros),
+            rate.quote_currency,
+        )));
+    }
+
+    Column::new()
+        .padding(20)
+        .spacing(10)
+        .push(IcedText::new("Currencies").size(20))
+        .push(
+            Row::new().spacing(10).align_items(Alignment::Center)
+                .push(IcedText::new("Base currency:"))
+                .push(
+                    PickList::new(
+                        &CURRENCIES[..],
+                        listed_currency(&app.base_currency),
+                        |code| Message::BaseCurrencySelected(code.to_string()),
+                    )
+                )
+        )
+        .push(IcedText::new("Import rates from CSV (date,currency,quote_currency,rate):"))
+        .push(
+            Row::new().spacing(10)
+                .push(
+                    TextInput::new("/path/to/rates.csv", &app.rates_csv_path)
+                        .on_input(Message::RatesCsvPathChanged)
+                )
+                .push(Button::new(IcedText::new("Import")).on_press(Message::ImportRatesPressed))
+        )
+        .push(IcedText::new(&app.currency_message))
+        .push(Scrollable::new(rates_column).height(Length::Fill))
+        .push(Button::new(IcedText::new("Back")).on_press(Message::CancelDashboardAction))
+        .into()
+}
+
+fn selected_option(app: &CombinedApp, selected: Option<&Category>) -> Option<CategoryOption> {
+    let selected = selected?;
+    tree_order(&app.categories).into_iter().find(|o| o.category.id == selected.id)
+}
+
+fn render_categories(app: &CombinedApp) -> Element<Message> {
+    let mut list = Column::new().spacing(5);
+    for kind in CATEGORY_KINDS {
+        list = list.push(IcedText::new(if kind == KIND_INCOME { "Income" } else { "Expenses" }).size(16));
+        for option in tree_order(&of_kind(&app.categories, kind)) {
+            let category = &option.category;
+            list = list.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(Space::with_width(Length::Fixed(20.0 * option.depth as f32)))
+                    .push(swatch(category.rgb(), 16.0))
+                    .push(IcedText::new(format!("{} ({})", category.name, app.category_usage(category.id))).width(Length::Fill))
+                    .push(Button::new(IcedText::new("Edit")).on_press(Message::EditCategory(category.clone())))
+            );
+        }
+    }
+
+    let mut palette = Row::new().spacing(5);
+    for hex in CATEGORY_PALETTE {
+        let rgb = parse_hex_color(hex).unwrap_or([0.5, 0.5, 0.5]);
+        palette = palette.push(
+            Button::new(swatch(rgb, 14.0))
+                .padding(2)
+                .on_press(Message::CategoryColorChanged(hex.to_string()))
+        );
+    }
+
+    // A category cannot move below itself or one of its own subcategories.
+    let excluded = app.editing_category.as_ref()
+        .map(|c| subtree(&app.categories, c.id))
+        .unwrap_or_default();
+    let kind = app.editing_category.as_ref().map_or(app.category_kind_input, |c| c.kind.as_str());
+    let parent_options: Vec<_> = tree_order(&of_kind(&app.categories, kind))
+        .into_iter()
+        .filter(|o| !excluded.contains(&o.category.id))
+        .collect();
+
+    let title = match &app.editing_category {
+        Some(c) => format!("Edit '{}'", c.name),
+        None => "New category".to_string(),
+    };
+
+    let mut editor = Column::new()
+        .spacing(10)
+        .push(IcedText::new(title).size(18))
+        .push(
+            TextInput::new("Name", &app.category_name_input)
+                .on_input(Message::CategoryNameChanged)
+        )
+        .push(
+            TextInput::new("Colour (#rrggbb)", &app.category_color_input)
+                .on_input(Message::CategoryColorChanged)
+        )
+        .push(palette);
+
+    if app.editing_category.is_none() {
+        editor = editor.push(
+            Row::new().spacing(10).align_items(Alignment::Center)
+                .push(IcedText::new("Kind:"))
+                .push(PickList::new(&CATEGORY_KINDS[..], Some(app.category_kind_input), Message::CategoryKindSelected))
+        );
+    }
+
+    editor = editor
+        .push(
+            Row::new().spacing(10).align_items(Alignment::Center)
+                .push(IcedText::new("Parent:"))
+                .push(
+                    PickList::new(
+                        parent_options,
+                        selected_option(app, app.category_parent_input.as_ref()),
+                        |selected| Message::CategoryParentSelected(Some(selected.category)),
+                    )
+                    .placeholder("Top level")
+                )
+                .push(Button::new(IcedText::new("Top level")).on_press(Message::CategoryParentSelected(None)))
+        )
+        .push(
+            Row::new().spacing(10)
+                .push(Button::new(IcedText::new("Save")).on_press(Message::SaveCategory))
+                .push(Button::new(IcedText::new("New")).on_press(Message::NewCategoryPressed))
+        );
+
+    if let Some(editing) = app.editing_category.as_ref().filter(|c| c.kind == KIND_EXPENSE) {
+        let mut remove = Button::new(IcedText::new("Remove budget"));
+        if app.budgets.iter().any(|b| b.tag_id == editing.id) {
+            remove = remove.on_press(Message::RemoveBudget);
+        }
+        editor = editor
+            .push(IcedText::new(format!("Monthly budget ({}, includes subcategories):", app.base_currency)))
+            .push(
+                Row::new().spacing(10).align_items(Alignment::Center)
+                    .push(
+                        TextInput::new("Limit", &app.budget_limit_input)
+                            .on_input(Message::BudgetLimitChanged)
+                            .width(Length::Fixed(120.0))
+                    )
+                    .push(Checkbox::new("Roll over unused budget", app.budget_rollover_input, Message::BudgetRolloverToggled))
+            )
+            .push(
+                Row::new().spacing(10)
+                    .push(Button::new(IcedText::new("Save budget")).on_press(Message::SaveBudget))
+                    .push(remove)
+            );
+    }
+
+    if let Some(editing) = &app.editing_category {
+        let others: Vec<_> = of_kind(&app.categories, &editing.kind)
+            .into_iter()
+            .filter(|c| c.id != editing.id)
+            .collect();
+        editor = editor
+            .push(IcedText::new("Move its transactions to:"))
+            .push(
+                PickList::new(others, app.category_target.clone(), Message::CategoryTargetSelected)
+                    .placeholder("Target category")
+            );
+        for warning in app.category_removal_warnings(editing.id) {
+            editor = editor.push(IcedText::new(warning));
+        }
+        editor = editor
+            .push(
+                Row::new().spacing(10)
+                    .push(Button::new(IcedText::new("Merge into target")).on_press(Message::MergeCategory))
+                    .push(Button::new(IcedText::new("Delete")).on_press(Message::DeleteCategory))
+            );
+    }
+
+    Column::new()
+        .padding(20)
+        .spacing(10)
+        .push(IcedText::new("Categories").size(20))
+        .push(
+            Row::new()
+                .spacing(40)
+                .push(Scrollable::new(list).width(Length::FillPortion(1)).height(Length::Fill))
+                .push(Container::new(editor).width(Length::FillPortion(1)))
+                .height(Length::FillPortion(1))
+        )
+        .push(IcedText::new(&app.category_message))
+        .push(Button::new(IcedText::new("Back")).on_press(Message::CancelDashboardAction))
+        .into()
+}
+
+fn render_trash(app: &CombinedApp) -> Element<Message> {
+    let mut list = Column::new().spacing(5);
+    if app.trash.is_empty() {
+        list = list.push(IcedText::new("The trash is empty."));
+    }
+    for tx in &app.trash {
+        let deleted = tx.deleted_at
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        list = list.push(
+            Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(
+                    IcedText::new(format!(
+                        "{} {} – {} {} [{}], deleted {}",
+                        tx.tran_type,
+                        tx.tran_source,
+                        tx.tran_amount,
+                        tx.currency,
+                        tx.date.format("%Y-%m-%d"),
+                        deleted,
+                    ))
+                    .width(Length::Fill)
+                )
+                .push(Button::new(IcedText::new("Restore")).on_press(Message::RestoreFromTrash(tx.tran_id)))
+                .push(Button::new(IcedText::new("Delete forever")).on_press(Message::PurgeTransaction(tx.tran_id)))
+        );
+    }
+
+    let mut empty = Button::new(IcedText::new("Empty trash"));
+    if !app.trash.is_empty() {
+        empty = empty.on_press(Message::EmptyTrash);
+    }
+
+    Column::new()
+        .padding(20)
+        .spacing(10)
+        .push(IcedText::new("Trash").size(20))
+        .push(IcedText::new(format!(
+            "Deleted transactions are removed for good after {} days.",
+            trash_retention_days(),
+        )))
+        .push(Scrollable::new(list).height(Length::Fill))
+        .push(IcedText::new(&app.trash_message).style(Color::from_rgb(0.8, 0.0, 0.0)))
+        .push(
+            Row::new().spacing(10)
+                .push(empty)
+                .push(Button::new(IcedText::new("Back")).on_press(Message::CancelDashboardAction))
+        )
+        .into()
+}
+
+fn render_audit(app: &CombinedApp) -> Element<Message> {
+    let filters = Row::new()
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .push(
+            TextInput::new("From (YYYY-MM-DD)", &app.audit_from)
+                .on_input(Message::AuditFromChanged)
+                .on_submit(Message::ApplyAuditFilter)
+                .width(Length::Fixed(160.0))
+        )
+        .push(
+            TextInput::new("To (YYYY-MM-DD)", &app.audit_to)
+                .on_input(Message::AuditToChanged)
+                .on_submit(Message::ApplyAuditFilter)
+                .width(Length::Fixed(160.0))
+        )
+        .push(
+            PickList::new(&AUDIT_ACTIONS[..], app.audit_action, |a| Message::AuditActionSelected(Some(a)))
+                .placeholder("All actions")
+        )
+        .push(Button::new(IcedText::new("All actions")).on_press(Message::AuditActionSelected(None)))
+        .push(Button::new(IcedText::new("Filter")).on_press(Message::ApplyAuditFilter));
+
+    let grey = Color::from_rgb(0.35, 0.35, 0.35);
+    let mut list = Column::new().spacing(8);
+    if app.audit_entries.is_empty() {
+        list = list.push(IcedText::new("No changes recorded for this filter."));
+    }
+    for entry in &app.audit_entries {
+        let target = match entry.entity_id {
+            Some(id) => format!("{} #{}", entry.entity, id),
+            None => entry.entity.clone(),
+        };
+        let mut item = Column::new().push(IcedText::new(format!(
+            "{}  {}  {}",
+            entry.created_at.format("%Y-%m-%d %H:%M:%S"),
+            entry.action,
+            target,
+        )));
+        if let Some(old) = &entry.old_value {
+            item = item.push(IcedText::new(format!("    before: {}", old)).size(14).style(grey));
+        }
+        if let Some(new) = &entry.new_value {
+            item = item.push(IcedText::new(format!("    after: {}", new)).size(14).style(grey));
+        }
+        list = list.push(item);
+    }
+
+    Column::new()
+        .padding(20)
+        .spacing(10)
+        .push(IcedText::new("Audit log").size(20))
+        .push(filters)
+        .push(IcedText::new(&app.audit_message).style(Color::from_rgb(0.8, 0.0, 0.0)))
+        .push(Scrollable::new(list).height(Length::Fill))
+        .push(Button::new(IcedText::new("Back")).on_press(Message::CancelDashboardAction))
+        .into()
+}
+
+/// How far ahead the recurring screen lists upcoming instances.
+const UPCOMING_DAYS: i64 = 60;
+
+fn render_recurring(app: &CombinedApp) -> Element<Message> {
+    let today = chrono::Local::now().date_naive();
+    let horizon = today + chrono::Duration::days(UPCOMING_DAYS);
+
+    let mut rules = Column::new().spacing(8);
+    if app.recurring_rules.is_empty() {
+        rules = rules.push(IcedText::new("No recurring transactions yet."));
+    }
+    let mut upcoming = Vec::new();
+    for rule in &app.recurring_rules {
+        let category = rule.tag_id
+            .and_then(|id| app.category(id))
+            .map(|c| format!(" #{}", c.name))
+            .unwrap_or_default();
+        let until = rule.end_date
+            .map(|d| format!(" until {}", d.format("%Y-%m-%d")))
+            .unwrap_or_default();
+        let next: Vec<String> = rule.upcoming(3).iter().map(|d| d.format("%Y-%m-%d").to_string()).collect();
+        let next = if next.is_empty() { "finished".to_string() } else { format!("next {}", next.join(", ")) };
+        rules = rules.push(
+            Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(
+                    Column::new()
+                        .push(IcedText::new(format!(
+                            "{} {}{} – {} {}, {} from {}{}",
+                            rule.tran_type,
+                            rule.source,
+                            category,
+                            rule.amount,
+                            rule.currency,
+                            rule.frequency,
+                            rule.start_date.format("%Y-%m-%d"),
+                            until,
+                        )))
+                        .push(IcedText::new(next).size(14).style(Color::from_rgb(0.3, 0.3, 0.3)))
+                        .width(Length::Fill)
+                )
+                .push(Button::new(IcedText::new("Delete")).on_press(Message::DeleteRule(rule.id)))
+        );
+        for date in rule.pending().take_while(|d| *d <= horizon) {
+            upcoming.push((date, rule));
+        }
+    }
+    upcoming.sort_by_key(|(date, _)| *date);
+
+    let mut instances = Column::new().spacing(3);
+    if upcoming.is_empty() {
+        instances = instances.push(IcedText::new(format!("Nothing due in the next {} days.", UPCOMING_DAYS)));
+    }
+    for (date, rule) in upcoming {
+        let sign = if rule.tran_type == "Expense" { "-" } else { "+" };
+        instances = instances.push(IcedText::new(format!(
+            "{}  {} {}{} {}",
+            date.format("%Y-%m-%d"),
+            rule.source,
+            sign,
+            rule.amount,
+            rule.currency,
+        )));
+    }
+
+    let kind = if app.rule_type == "Income" { KIND_INCOME } else { KIND_EXPENSE };
+    let editor = Column::new()
+        .spacing(10)
+        .push(IcedText::new("New recurring transaction").size(18))
+        .push(PickList::new(&RULE_TYPES[..], Some(app.rule_type), Message::RuleTypeSelected))
+        .push(TextInput::new("Source (e.g. Rent)", &app.rule_source).on_input(Message::RuleSourceChanged))
+        .push(
+            Row::new().spacing(10)
+                .push(TextInput::new("Amount", &app.rule_amount).on_input(Message::RuleAmountChanged))
+                .push(PickList::new(
+                    &CURRENCIES[..],
+                    listed_currency(&app.rule_currency),
+                    |code| Message::RuleCurrencySelected(code.to_string()),
+                ))
+        )
+        .push(
+            PickList::new(
+                tree_order(&of_kind(&app.categories, kind)),
+                selected_option(app, app.rule_category.as_ref()),
+                |selected| Message::RuleCategorySelected(Some(selected.category)),
+            )
+            .placeholder("Category")
+        )
+        .push(
+            PickList::new(&app.accounts[..], app.rule_account.clone(), Message::RuleAccountSelected)
+                .placeholder("Account")
+        )
+        .push(
+            Row::new().spacing(10).align_items(Alignment::Center)
+                .push(IcedText::new("Repeats"))
+                .push(PickList::new(&FREQUENCIES[..], Some(app.rule_frequency), Message::RuleFrequencySelected))
+        )
+        .push(
+            Row::new().spacing(10)
+                .push(TextInput::new("Start (YYYY-MM-DD)", &app.rule_start).on_input(Message::RuleStartChanged))
+                .push(TextInput::new("End (optional)", &app.rule_end).on_input(Message::RuleEndChanged))
+        )
+        .push(Button::new(IcedText::new("Save rule")).on_press(Message::SaveRule));
+
+    Column::new()
+        .padding(20)
+        .spacing(10)
+        .push(IcedText::new("Recurring transactions").size(20))
+        .push(
+            Row::new()
+                .spacing(40)
+                .push(
+                    Column::new()
+                        .spacing(10)
+                        .push(Scrollable::new(rules).height(Length::FillPortion(1)))
+                        .push(IcedText::new(format!("Upcoming ({} days)", UPCOMING_DAYS)).size(16))
+                        .push(Scrollable::new(instances).height(Length::FillPortion(1)))
+                        .width(Length::FillPortion(1))
+                )
+                .push(Container::new(editor).width(Length::FillPortion(1)))
+                .height(Length::Fill)
+        )
+        .push(IcedText::new(&app.recurring_message))
+        .push(Button::new(IcedText::new("Back")).on_press(Message::CancelDashboardAction))
+        .into()
+}
+
+fn render_envelopes(app: &CombinedApp) -> Element<Message> {
+    let mut content = Column::new()
+        .padding(20)
+        .spacing(10)
+        .push(IcedText::new("Envelopes").size(20))
+        .push(Checkbox::new(
+            "Assign all income to envelopes",
+            app.envelope_book.enabled,
+            Message::EnvelopeModeToggled,
+        ));
+    if !app.envelope_book.enabled {
+        return content
+            .push(IcedText::new(&app.envelope_message))
+            .push(Button::new(IcedText::new("Back")).on_press(Message::CancelDashboardAction))
+            .into();
+    }
+
+    let to_assign = app.available_to_assign();
+    let to_assign_color = if to_assign.is_zero() {
+        Color::from_rgb(0.2, 0.2, 0.2)
+    } else if to_assign.is_negative() {
+        Color::from_rgb(0.8, 0.0, 0.0)
+    } else {
+        Color::from_rgb(0.0, 0.5, 0.0)
+    };
+    let since = app.envelope_book.start
+        .map(|d| format!(" (income since {})", d.format("%Y-%m-%d")))
+        .unwrap_or_default();
+
+    let mut envelopes = Column::new().spacing(8);
+    if app.envelope_book.envelopes.is_empty() {
+        envelopes = envelopes.push(IcedText::new("No envelopes yet."));
+    }
+    for envelope in &app.envelope_book.envelopes {
+        let balance = app.envelope_balance(envelope);
+        let category = envelope.tag_id
+            .and_then(|id| app.category(id))
+            .map(|c| format!("#{}", c.name))
+            .unwrap_or_else(|| "no category".to_string());
+        let color = if balance.is_negative() { Color::from_rgb(0.8, 0.0, 0.0) } else { Color::from_rgb(0.2, 0.2, 0.2) };
+        envelopes = envelopes.push(
+            Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(
+                    Column::new()
+                        .push(IcedText::new(format!("{}: {} {}", envelope.name, balance, app.base_currency)).style(color))
+                        .push(IcedText::new(category).size(14).style(Color::from_rgb(0.3, 0.3, 0.3)))
+                        .width(Length::Fill)
+                )
+                .push(Button::new(IcedText::new("Delete")).on_press(Message::DeleteEnvelope(envelope.id)))
+        );
+    }
+
+    let choices = app.envelope_choices();
+    let mover = Column::new()
+        .spacing(10)
+        .push(IcedText::new("Move money").size(18))
+        .push(
+            PickList::new(choices.clone(), app.move_from.clone(), Message::MoveFromSelected)
+                .placeholder("From")
+        )
+        .push(
+            PickList::new(choices, app.move_to.clone(), Message::MoveToSelected)
+                .placeholder("To")
+        )
+        .push(
+            TextInput::new("Amount", &app.move_amount_input)
+                .on_input(Message::MoveAmountChanged)
+                .on_submit(Message::MoveMoney)
+        )
+        .push(Button::new(IcedText::new("Move")).on_press(Message::MoveMoney));
+
+    let editor = Column::new()
+        .spacing(10)
+        .push(IcedText::new("New envelope").size(18))
+        .push(TextInput::new("Name (e.g. Groceries)", &app.envelope_name_input).on_input(Message::EnvelopeNameChanged))
+        .push(
+            PickList::new(
+                tree_order(&of_kind(&app.categories, KIND_EXPENSE)),
+                selected_option(app, app.envelope_category_input.as_ref()),
+                |selected| Message::EnvelopeCategorySelected(Some(selected.category)),
+            )
+            .placeholder("Expense category drawing from it")
+        )
+        .push(Button::new(IcedText::new("Create envelope")).on_press(Message::CreateEnvelope));
+
+    content = content
+        .push(
+            IcedText::new(format!("Available to assign: {} {}{}", to_assign, app.base_currency, since))
+                .size(18)
+                .style(to_assign_color)
+        )
+        .push(
+            Row::new()
+                .spacing(40)
+                .push(Scrollable::new(envelopes).width(Length::FillPortion(1)))
+                .push(
+                    Column::new()
+                        .spacing(30)
+                        .push(mover)
+                        .push(editor)
+                        .width(Length::FillPortion(1))
+                )
+                .height(Length::Fill)
+        )
+        .push(IcedText::new(&app.envelope_message))
+        .push(Button::new(IcedText::new("Back")).on_press(Message::CancelDashboardAction));
+    content.into()
+}
+
+fn render_goals(app: &CombinedApp) -> Element<Message> {
+    let today = chrono::Local::now().date_naive();
+    let mut goals = Column::new().spacing(12);
+    if app.savings_book.goals.is_empty() {
+        goals = goals.push(IcedText::new("No savings goals yet."));
+    }
+    for goal in &app.savings_book.goals {
+        let progress = app.goal_progress(goal, today);
+        let target = progress.target.to_f32().max(0.01);
+        let status = if progress.is_reached() {
+            "Reached".to_string()
+        } else if progress.months_left == 0 {
+            format!("Target date passed, {} {} missing", progress.remaining(), goal.currency)
+        } else {
+            format!(
+                "{} {} per month for {} more month(s)",
+                progress.monthly_needed(),
+                goal.currency,
+                progress.months_left,
+            )
+        };
+        let mut entry = Column::new()
+            .spacing(3)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        IcedText::new(format!(
+                            "{}: {} / {} {} by {}",
+                            goal.name,
+                            progress.saved,
+                            progress.target,
+                            goal.currency,
+                            goal.target_date.format("%Y-%m-%d"),
+                        ))
+                        .width(Length::Fill)
+                    )
+                    .push(Button::new(IcedText::new("Delete")).on_press(Message::DeleteGoal(goal.id)))
+            )
+            .push(ProgressBar::new(0.0..=target, progress.saved.to_f32().min(target)).height(Length::Fixed(8.0)))
+            .push(IcedText::new(status).size(14).style(Color::from_rgb(0.3, 0.3, 0.3)));
+        for contribution in app.savings_book.contributions.iter().filter(|c| c.goal_id == goal.id) {
+            let source = app.transactions
+                .iter()
+                .find(|t| t.tran_id == contribution.transaction_id)
+                .map(|t| format!("{} {} {}", t.date.format("%Y-%m-%d"), t.tran_source, t.currency))
+                .unwrap_or_else(|| "transaction in the trash".to_string());
+            entry = entry.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(IcedText::new(format!("  {} from {}", contribution.amount, source)).size(14).width(Length::Fill))
+                    .push(Button::new(IcedText::new("Remove").size(14)).on_press(Message::RemoveContribution(contribution.id)))
+            );
+        }
+        goals = goals.push(entry);
+    }
+
+    let earmark = Column::new()
+        .spacing(10)
+        .push(IcedText::new("Earmark money").size(18))
+        .push(
+            PickList::new(&app.savings_book.goals[..], app.earmark_goal.clone(), Message::EarmarkGoalSelected)
+                .placeholder("Goal")
+        )
+        .push(
+            PickList::new(app.earmark_sources(), app.earmark_source.clone(), Message::EarmarkSourceSelected)
+                .placeholder("From income or transfer")
+        )
+        .push(
+            TextInput::new("Amount", &app.earmark_amount_input)
+                .on_input(Message::EarmarkAmountChanged)
+                .on_submit(Message::Earmark)
+        )
+        .push(Button::new(IcedText::new("Earmark")).on_press(Message::Earmark));
+
+    let editor = Column::new()
+        .spacing(10)
+        .push(IcedText::new("New goal").size(18))
+        .push(TextInput::new("Name (e.g. Vacation)", &app.goal_name_input).on_input(Message::GoalNameChanged))
+        .push(
+            Row::new().spacing(10)
+                .push(TextInput::new("Target amount", &app.goal_target_input).on_input(Message::GoalTargetChanged))
+                .push(PickList::new(
+                    &CURRENCIES[..],
+                    listed_currency(&app.goal_currency_input),
+                    |code| Message::GoalCurrencySelected(code.to_string()),
+                ))
+        )
+        .push(TextInput::new("Target date (YYYY-MM-DD)", &app.goal_date_input).on_input(Message::GoalDateChanged))
+        .push(Button::new(IcedText::new("Save goal")).on_press(Message::CreateGoal));
+
+    Column::new()
+        .padding(20)
+        .spacing(10)
+        .push(IcedText::new("Savings goals").size(20))
+        .push(
+            Row::new()
+                .spacing(40)
+                .push(Scrollable::new(goals).width(Length::FillPortion(1)))
+                .push(
+                    Column::new()
+                        .spacing(30)
+                        .push(earmark)
+                        .push(editor)
+                        .width(Length::FillPortion(1))
+                )
+                .height(Length::Fill)
+        )
+        .push(IcedText::new(&app.goal_message))
+        .push(Button::new(IcedText::new("Back")).on_press(Message::CancelDashboardAction))
+        .into()
+}
+
+fn render_loans(app: &CombinedApp) -> Element<Message> {
+    let mut loans = Column::new().spacing(8);
+    if app.loan_book.loans.is_empty() {
+        loans = loans.push(IcedText::new("No loans yet."));
+    }
+    for loan in &app.loan_book.loans {
+        let (outstanding, paid) = app.loan_outstanding(loan);
+        let payoff = app.loan_projection(loan)
+            .last()
+            .map(|row| format!(", paid off {}", row.date.format("%Y-%m-%d")))
+            .unwrap_or_default();
+        loans = loans.push(
+            Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(
+                    Column::new()
+                        .push(IcedText::new(format!(
+                            "{} ({}) – {} {} at {} over {} months",
+                            loan.name,
+                            loan.direction,
+                            loan.principal,
+                            loan.currency,
+                            format_rate_bp(loan.annual_rate_bp),
+                            loan.term_months,
+                        )))
+                        .push(
+                            IcedText::new(format!("Outstanding {} {} after {} payment(s){}", outstanding, loan.currency, paid, payoff))
+                                .size(14)
+                                .style(Color::from_rgb(0.3, 0.3, 0.3))
+                        )
+                        .width(Length::Fill)
+                )
+                .push(Button::new(IcedText::new("Schedule")).on_press(Message::ShowLoan(loan.id)))
+                .push(Button::new(IcedText::new("Delete")).on_press(Message::DeleteLoan(loan.id)))
+        );
+    }
+
+    let mut details = Column::new().spacing(10);
+    if let Some(loan) = app.selected_loan.and_then(|id| app.loan(id)) {
+        let (_, paid) = app.loan_outstanding(loan);
+        let mut schedule = Column::new().spacing(2).push(
+            IcedText::new("  #   Date         Payment     Interest    Principal   Balance").size(14)
+        );
+        // Instalments already paid are shown as agreed; the rest is projected from the balance.
+        let rows = loan.schedule()
+            .into_iter()
+            .take(paid as usize)
+            .chain(app.loan_projection(loan));
+        for row in rows {
+            let color = if row.number <= paid { Color::from_rgb(0.5, 0.5, 0.5) } else { Color::from_rgb(0.1, 0.1, 0.1) };
+            schedule = schedule.push(
+                IcedText::new(format!(
+                    "{:>3}   {}   {:>10}  {:>10}  {:>10}  {:>12}",
+                    row.number,
+                    row.date.format("%Y-%m-%d"),
+                    row.payment,
+                    row.interest,
+                    row.principal,
+                    row.balance,
+                ))
+                .size(14)
+                .font(iced::Font::MONOSPACE)
+                .style(color)
+            );
+        }
+        details = details
+            .push(IcedText::new(format!("Amortization schedule: {}", loan.name)).size(18))
+            .push(Scrollable::new(schedule).height(Length::Fill))
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        TextInput::new("Amount", &app.repayment_amount_input)
+                            .on_input(Message::RepaymentAmountChanged)
+                            .width(Length::Fixed(120.0))
+                    )
+                    .push(
+                        TextInput::new("Date (YYYY-MM-DD)", &app.repayment_date_input)
+                            .on_input(Message::RepaymentDateChanged)
+                            .width(Length::Fixed(140.0))
+                    )
+                    .push(Button::new(IcedText::new("Book repayment")).on_press(Message::RecordRepayment))
+            );
+    }
+
+    let kind = if app.loan_direction == DIRECTION_LENT { KIND_INCOME } else { KIND_EXPENSE };
+    let editor = Column::new()
+        .spacing(10)
+        .push(IcedText::new("New loan").size(18))
+        .push(PickList::new(&DIRECTIONS[..], Some(app.loan_direction), Message::LoanDirectionSelected))
+        .push(TextInput::new("Name (e.g. Mortgage)", &app.loan_name_input).on_input(Message::LoanNameChanged))
+        .push(
+            Row::new().spacing(10)
+                .push(TextInput::new("Principal", &app.loan_principal_input).on_input(Message::LoanPrincipalChanged))
+                .push(PickList::new(
+                    &CURRENCIES[..],
+                    listed_currency(&app.loan_currency_input),
+                    |code| Message::LoanCurrencySelected(code.to_string()),
+                ))
+        )
+        .push(
+            Row::new().spacing(10)
+                .push(TextInput::new("Interest % per year", &app.loan_rate_input).on_input(Message::LoanRateChanged))
+                .push(TextInput::new("Term in months", &app.loan_term_input).on_input(Message::LoanTermChanged))
+        )
+        .push(TextInput::new("Start (YYYY-MM-DD)", &app.loan_start_input).on_input(Message::LoanStartChanged))
+        .push(
+            PickList::new(&app.accounts[..], app.loan_account.clone(), Message::LoanAccountSelected)
+                .placeholder("Account")
+        )
+        .push(
+            PickList::new(
+                tree_order(&of_kind(&app.categories, kind)),
+                selected_option(app, app.loan_interest_category.as_ref()),
+                |selected| Message::LoanInterestCategorySelected(Some(selected.category)),
+            )
+            .placeholder("Interest category")
+        )
+        .push(Button::new(IcedText::new("Save loan")).on_press(Message::CreateLoan));
+
+    Column::new()
+        .padding(20)
+        .spacing(10)
+        .push(IcedText::new("Loans").size(20))
+        .push(
+            Row::new()
+                .spacing(40)
+                .push(
+                    Column::new()
+                        .spacing(20)
+                        .push(Scrollable::new(loans).height(Length::FillPortion(1)))
+                        .push(Container::new(details).height(Length::FillPortion(2)))
+                        .width(Length::FillPortion(2))
+                )
+                .push(Container::new(editor).width(Length::FillPortion(1)))
+                .height(Length::Fill)
+        )
+        .push(IcedText::new(&app.loan_message))
+        .push(Button::new(IcedText::new("Back")).on_press(Message::CancelDashboardAction))
+        .into()
+}
+
+/// Rows shown in the import preview; the import itself covers the whole file.
+const IMPORT_PREVIEW_ROWS: usize = 20;
+
+fn render_import(app: &CombinedApp) -> Element<Message> {
+    let mapping = &app.import_mapping;
+    let choices = column_choices(&app.import_rows, mapping.has_header);
+    let column_picker = |label: &'static str, field: CsvField| -> Element<Message> {
+        let selected = choices.iter().find(|c| c.index == mapping.column(field)).cloned();
+        Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(IcedText::new(label).width(Length::Fixed(80.0)))
+            .push(
+                PickList::new(choices.clone(), selected, move |choice| Message::ImportColumnSelected(field, choice))
+                    .width(Length::Fixed(220.0))
+            )
+            .into()
+    };
+
+    let mut settings = Column::new()
+        .spacing(10)
+        .push(
+            Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(Button::new(IcedText::new("Choose file…")).on_press(Message::PickImportFile))
+                .push(IcedText::new(&app.import_file_name))
+        );
+    if app.import_format == ImportFormat::Csv {
+        settings = settings
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(PickList::new(&DELIMITERS[..], Some(mapping.delimiter), Message::ImportDelimiterSelected))
+                    .push(Checkbox::new("First row is a header", mapping.has_header, Message::ImportHeaderToggled))
+            )
+            .push(column_picker("Date", CsvField::Date))
+            .push(column_picker("Amount", CsvField::Amount))
+            .push(column_picker("Payee", CsvField::Payee))
+            .push(column_picker("Comment", CsvField::Comment))
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(IcedText::new("Date format"))
+                    .push(PickList::new(&DATE_FORMATS[..], Some(mapping.date_format), Message::ImportDateFormatSelected))
+                    .push(IcedText::new("Decimal"))
+                    .push(PickList::new(&DECIMAL_SEPARATORS[..], Some(mapping.decimal), Message::ImportDecimalSelected))
+            )
+            .push(PickList::new(&SIGN_CONVENTIONS[..], Some(mapping.sign), Message::ImportSignSelected));
+    } else {
+        settings = settings.push(IcedText::new(format!("{} statement", app.import_format)));
+    }
+    for statement in &app.import_statements {
+        settings = settings.push(IcedText::new(statement.summary()).size(14));
+        // Opening balance plus the rows must give the closing balance, or lines are missing.
+        if let (Some(difference), Some(computed)) = (statement.balance_difference(), statement.computed_closing()) {
+            let check = if difference.is_zero() {
+                IcedText::new(format!("Balances check out: computed closing balance {}", computed))
+                    .style(Color::from_rgb(0.0, 0.5, 0.0))
+            } else {
+                IcedText::new(format!("Computed closing balance {} is off by {}", computed, difference))
+                    .style(Color::from_rgb(0.8, 0.0, 0.0))
+            };
+            settings = settings.push(check.size(14));
+        }
+    }
+    if app.import_format == ImportFormat::Qif {
+        settings = settings.push(Checkbox::new(
+            "Dates are day/month/year",
+            app.import_day_first,
+            Message::ImportDayFirstToggled,
+        ));
+    }
+    settings = settings.push(
+        Row::new()
+            .spacing(10)
+            .push(PickList::new(
+                &CURRENCIES[..],
+                listed_currency(&app.import_currency),
+                |code| Message::ImportCurrencySelected(code.to_string()),
+            ))
+            .push(
+                PickList::new(&app.accounts[..], app.import_account.clone(), Message::ImportAccountSelected)
+                    .placeholder("Account")
+            )
+    );
+
+    let preview_rows = app.import_preview();
+    let valid = preview_rows.iter().filter(|(_, row)| row.is_ok()).count();
+    let mut preview = Column::new().spacing(4);
+    for (line, row) in preview_rows.iter().take(IMPORT_PREVIEW_ROWS) {
+        let text = match row {
+            Ok(row) => {
+                let conflict = row.category_conflict(&app.categories);
+                let category = match (row.category_label(), conflict) {
+                    (Some(path), Some(other)) if other.kind != row.category_kind() => format!(
+                        " [{}: '{}' is an {} category, left uncategorized]",
+                        path, other.name, other.kind
+                    ),
+                    (Some(path), Some(other)) => format!(
+                        " [{}: '{}' sits elsewhere in the category tree, left uncategorized]",
+                        path, other.name
+                    ),
+                    (Some(path), None) => format!(" [{}]", path),
+                    (None, _) => String::new(),
+                };
+                let text = IcedText::new(format!(
+                    "{:>4}  {}  {:<7}  {:>12}  {}{}{}",
+                    line,
+                    row.date.format("%Y-%m-%d"),
+                    row.kind(),
+                    row.amount.abs(),
+                    row.payee,
+                    row.comment.as_deref().map(|c| format!(" – {}", c)).unwrap_or_default(),
+                    category,
+                ));
+                if conflict.is_some() { text.style(Color::from_rgb(0.8, 0.5, 0.0)) } else { text }
+            }
+            Err(e) => IcedText::new(format!("{:>4}  {}", line, e)).style(Color::from_rgb(0.8, 0.0, 0.0)),
+        };
+        preview = preview.push(text.size(14).font(iced::Font::MONOSPACE));
+    }
+    if preview_rows.len() > IMPORT_PREVIEW_ROWS {
+        preview = preview.push(IcedText::new(format!("… and {} more rows", preview_rows.len() - IMPORT_PREVIEW_ROWS)).size(14));
+    }
+    let skipped = preview_rows.len() - valid;
+    let mut import_button = Button::new(IcedText::new(if skipped > 0 {
+        format!("Import {} transactions ({} rows skipped)", valid, skipped)
+    } else {
+        format!("Import {} transactions", valid)
+    }));
+    if valid > 0 {
+        import_button = import_button.on_press(Message::ImportTransactions);
+    }
+
+    Column::new()
+        .padding(20)
+        .spacing(10)
+        .push(IcedText::new("Import bank statement").size(20))
+        .push(
+            Row::new()
+                .spacing(40)
+                .push(Container::new(settings).width(Length::FillPortion(1)))
+                .push(
+                    Column::new()
+                        .spacing(10)
+                        .push(IcedText::new("Preview").size(18))
+                        .push(Scrollable::new(preview).height(Length::Fill))
+                        .push(import_button)
+                        .width(Length::FillPortion(2))
+                )
+                .height(Length::Fill)
+        )
+        .push(IcedText::new(&app.import_message))
+        .push(
+            Row::new()
+                .spacing(10)
+                .push(Button::new(IcedText::new("Back")).on_press(Message::CancelDashboardAction))
+                .push(Button::new(IcedText::new("Export all as QIF…")).on_press(Message::ExportQif))
+        )
+        .into()
+}